@@ -0,0 +1,76 @@
+//! Caddy implementation of the proxy backend
+//!
+//! This module adapts the Caddy container management and configuration
+//! rendering to the `ProxyBackend` trait.
+
+use std::path::PathBuf;
+
+use anyhow::Result;
+
+use super::{config, manager, proxy};
+use crate::{
+    config::{GlobalConfig, get_config_dir, load_global_config},
+    proxy::{ProxyBackend, SiteConfig},
+};
+
+/// Caddy reverse proxy backend
+pub struct CaddyBackend {
+    /// Container name from the [caddy] settings
+    container_name: String,
+}
+
+impl CaddyBackend {
+    pub fn new(global_config: &GlobalConfig) -> Self {
+        Self {
+            container_name: global_config.caddy.container_name.clone(),
+        }
+    }
+}
+
+impl ProxyBackend for CaddyBackend {
+    fn name(&self) -> &'static str {
+        "Caddy"
+    }
+
+    fn container_name(&self) -> &str {
+        &self.container_name
+    }
+
+    fn routes_dir(&self) -> Result<PathBuf> {
+        let config_dir = get_config_dir()?;
+        let global_config = load_global_config()?;
+        Ok(config_dir.join(&global_config.global.caddy_projects_dir))
+    }
+
+    fn routes_file_name(&self, project_name: &str) -> String {
+        format!("{}.caddy", project_name)
+    }
+
+    fn render_routes(&self, site: &SiteConfig) -> String {
+        config::render_site(site)
+    }
+
+    fn start(&self) -> Result<()> {
+        manager::start()
+    }
+
+    fn stop(&self) -> Result<()> {
+        manager::stop()
+    }
+
+    fn restart(&self) -> Result<()> {
+        manager::restart()
+    }
+
+    fn reload(&self) -> Result<()> {
+        proxy::reload()
+    }
+
+    fn status(&self) -> Result<()> {
+        manager::status()
+    }
+
+    fn is_running(&self) -> bool {
+        manager::is_running(&self.container_name)
+    }
+}
@@ -2,23 +2,29 @@
 //!
 //! This module handles starting, stopping, and monitoring the Caddy container.
 
-use std::fs;
-use std::io::{self, Write};
-use std::process::Command;
+use std::{
+    fs,
+    io::{self, Write},
+    path::Path,
+    process::Command,
+};
 
 use anyhow::{Context, Result};
 use colored::Colorize;
 
-use super::{CADDY_CONTAINER_NAME, CADDY_NETWORK_NAME, OMD_SERVICE_LABEL};
-use crate::config::{get_config_dir, load_global_config};
+use super::{ADMIN_PORT, OMD_SERVICE_LABEL};
+use crate::{
+    config::{CaddySettings, GlobalConfig, get_config_dir, load_global_config},
+    docker::network::ensure_network,
+};
 
-/// Check if Caddy container is running
-pub fn is_running() -> bool {
+/// Check if the Caddy container is running
+pub fn is_running(container_name: &str) -> bool {
     let output = Command::new("docker")
-        .args(&[
+        .args([
             "ps",
             "--filter",
-            &format!("name={}", CADDY_CONTAINER_NAME),
+            &format!("name={}", container_name),
             "--format",
             "{{.Names}}",
         ])
@@ -26,20 +32,20 @@ pub fn is_running() -> bool {
 
     if let Ok(output) = output {
         let stdout = String::from_utf8_lossy(&output.stdout);
-        stdout.contains(CADDY_CONTAINER_NAME)
+        stdout.lines().any(|name| name == container_name)
     } else {
         false
     }
 }
 
-/// Check if Caddy container exists (running or stopped)
-fn container_exists() -> bool {
+/// Check if the Caddy container exists (running or stopped)
+fn container_exists(container_name: &str) -> bool {
     let output = Command::new("docker")
-        .args(&[
+        .args([
             "ps",
             "-a",
             "--filter",
-            &format!("name={}", CADDY_CONTAINER_NAME),
+            &format!("name={}", container_name),
             "--format",
             "{{.Names}}",
         ])
@@ -47,17 +53,17 @@ fn container_exists() -> bool {
 
     if let Ok(output) = output {
         let stdout = String::from_utf8_lossy(&output.stdout);
-        stdout.contains(CADDY_CONTAINER_NAME)
+        stdout.lines().any(|name| name == container_name)
     } else {
         false
     }
 }
 
 /// Remove existing Caddy container
-fn remove_container() -> Result<()> {
+fn remove_container(container_name: &str) -> Result<()> {
     println!("{} Removing existing container...", "ℹ".blue());
     let status = Command::new("docker")
-        .args(&["rm", "-f", CADDY_CONTAINER_NAME])
+        .args(["rm", "-f", container_name])
         .status()
         .context("Failed to remove container")?;
 
@@ -69,10 +75,10 @@ fn remove_container() -> Result<()> {
 }
 
 /// Start existing stopped container
-fn start_existing_container() -> Result<()> {
+fn start_existing_container(container_name: &str) -> Result<()> {
     println!("{} Starting existing container...", "ℹ".blue());
     let status = Command::new("docker")
-        .args(&["start", CADDY_CONTAINER_NAME])
+        .args(["start", container_name])
         .status()
         .context("Failed to start container")?;
 
@@ -83,86 +89,167 @@ fn start_existing_container() -> Result<()> {
     Ok(())
 }
 
-/// Ensure Caddyfile exists in config directory
-fn ensure_caddyfile() -> Result<()> {
-    let config_dir = get_config_dir()?;
-    let caddyfile_path = config_dir.join("caddy/Caddyfile");
+/// Render the main Caddyfile from the global settings
+fn caddyfile_content(global_config: &GlobalConfig) -> String {
+    let settings = &global_config.caddy;
 
-    if caddyfile_path.exists() {
-        return Ok(());
+    let mut options = format!("    admin 0.0.0.0:{}\n", ADMIN_PORT);
+    if !global_config.global.enable_https {
+        options.push_str("    auto_https off\n");
+    }
+    // Listen on the published ports so redirects and URLs carry them
+    if settings.uses_custom_ports() {
+        options.push_str(&format!("    http_port {}\n", settings.http_port()));
+        options.push_str(&format!("    https_port {}\n", settings.https_port()));
     }
 
-    println!("{} Creating Caddyfile...", "ℹ".blue());
-
-    // Check if HTTPS is enabled in global config
-    let global_config = load_global_config().ok();
-    let enable_https = global_config
-        .as_ref()
-        .map(|c| c.global.enable_https)
-        .unwrap_or(false);
-
-    let auto_https_setting = if enable_https {
-        ""
-    } else {
-        "    auto_https off\n"
-    };
-    let caddyfile_content = format!(
+    format!(
         r#"{{
-    admin 0.0.0.0:2019
 {}}}
 
 # Import all project configurations
 import /etc/caddy/projects/*.caddy
 "#,
-        auto_https_setting
-    );
+        options
+    )
+}
 
-    fs::write(&caddyfile_path, caddyfile_content).context("Failed to write Caddyfile")?;
+/// Write the Caddyfile in the config directory for the current settings
+/// A Caddyfile with other content is kept as Caddyfile.bak
+fn ensure_caddyfile(global_config: &GlobalConfig) -> Result<()> {
+    let config_dir = get_config_dir()?;
+    let caddyfile_path = config_dir.join("caddy/Caddyfile");
+    let content = caddyfile_content(global_config);
 
+    if caddyfile_path.exists() {
+        let existing = fs::read_to_string(&caddyfile_path).context("Failed to read Caddyfile")?;
+        if existing == content {
+            return Ok(());
+        }
+
+        let backup_path = caddyfile_path.with_extension("bak");
+        fs::copy(&caddyfile_path, &backup_path).context("Failed to back up Caddyfile")?;
+        fs::write(&caddyfile_path, content).context("Failed to write Caddyfile")?;
+        println!(
+            "{} Updated Caddyfile for the current settings (previous version: {})",
+            "✓".green(),
+            backup_path.display()
+        );
+        return Ok(());
+    }
+
+    println!("{} Creating Caddyfile...", "ℹ".blue());
+    fs::write(&caddyfile_path, content).context("Failed to write Caddyfile")?;
     println!("{} Caddyfile created", "✓".green());
 
     Ok(())
 }
 
-/// Ensure caddy-net network exists
-fn ensure_caddy_network() -> Result<()> {
-    let output = Command::new("docker")
-        .args(&["network", "inspect", CADDY_NETWORK_NAME])
-        .output()
-        .context("Failed to check network")?;
-
-    if !output.status.success() {
-        println!("{} Creating {} network...", "ℹ".blue(), CADDY_NETWORK_NAME);
-        let status = Command::new("docker")
-            .args(&["network", "create", CADDY_NETWORK_NAME])
-            .status()
-            .context("Failed to create network")?;
-
-        if !status.success() {
-            anyhow::bail!("Failed to create {} network", CADDY_NETWORK_NAME);
-        }
+/// Arguments of `docker run` for the Caddy container
+fn run_args(global_config: &GlobalConfig, config_dir: &Path) -> Vec<String> {
+    let settings = &global_config.caddy;
+    let caddyfile_path = config_dir.join("caddy/Caddyfile");
+    let certs_path = config_dir.join(&global_config.global.caddy_certs_dir);
+    let projects_path = config_dir.join(&global_config.global.caddy_projects_dir);
+
+    let mut args: Vec<String> = vec![
+        "run".to_string(),
+        "-d".to_string(),
+        "--name".to_string(),
+        settings.container_name.clone(),
+        "--restart".to_string(),
+        "unless-stopped".to_string(),
+    ];
+    // Caddy listens on the published ports themselves (see caddyfile_content)
+    let (http_port, https_port) = (settings.http_port(), settings.https_port());
+    for port in [
+        format!("{}:{}", http_port, http_port),
+        format!("{}:{}", https_port, https_port),
+        format!("{}:{}/udp", https_port, https_port),
+    ] {
+        args.push("-p".to_string());
+        args.push(port);
+    }
+    if !settings.admin.is_empty() {
+        args.push("-p".to_string());
+        args.push(format!("{}:{}", settings.admin, ADMIN_PORT));
+    }
 
-        println!("{} Network created", "✓".green());
+    for volume in [
+        format!("{}:/etc/caddy/Caddyfile:ro", caddyfile_path.display()),
+        format!("{}:/certs:ro", certs_path.display()),
+        format!("{}:/etc/caddy/projects:ro", projects_path.display()),
+        "caddy_data:/data".to_string(),
+        "caddy_config:/config".to_string(),
+    ]
+    .into_iter()
+    .chain(settings.volumes.iter().cloned())
+    {
+        args.push("-v".to_string());
+        args.push(volume);
     }
 
-    Ok(())
+    args.push("--network".to_string());
+    args.push(global_config.caddy_network().to_string());
+
+    args.push("-e".to_string());
+    args.push(format!("CADDY_ADMIN=0.0.0.0:{}", ADMIN_PORT));
+    for (key, value) in &settings.env {
+        args.push("-e".to_string());
+        args.push(format!("{}={}", key, value));
+    }
+
+    args.push("--label".to_string());
+    args.push(format!("{}=caddy", OMD_SERVICE_LABEL));
+    args.push(settings.image.clone());
+
+    args
+}
+
+/// Admin API URL on the host, if it is published
+fn admin_url(settings: &CaddySettings) -> Option<String> {
+    if settings.admin.is_empty() {
+        return None;
+    }
+    // Wildcard bindings are reachable on localhost
+    let address = settings
+        .admin
+        .strip_prefix("0.0.0.0:")
+        .map(|port| format!("localhost:{}", port))
+        .unwrap_or_else(|| settings.admin.clone());
+    Some(format!("http://{}", address))
+}
+
+/// Print where to find the admin API and logs of a running container
+fn print_access(settings: &CaddySettings) {
+    if let Some(url) = admin_url(settings) {
+        println!("Caddy Admin API: {}", url);
+    }
+    println!("View logs: docker logs {} -f", settings.container_name);
 }
 
 /// Start Caddy container
 pub fn start() -> Result<()> {
-    if is_running() {
+    let global_config = load_global_config()?;
+    let settings = &global_config.caddy;
+    let container_name = settings.container_name.as_str();
+
+    if is_running(container_name) {
         println!("{} Caddy is already running", "ℹ".blue());
         return Ok(());
     }
 
     // Check if stopped container exists
-    if container_exists() {
+    if container_exists(container_name) {
         println!();
         println!("{} Found existing Caddy container (stopped)", "⚠".yellow());
         println!();
         println!("Choose an option:");
         println!("  1. {} - Start the existing container", "Start".green());
-        println!("  2. {} - Remove and recreate container", "Reset".yellow());
+        println!(
+            "  2. {} - Remove and recreate container (applies [caddy] changes)",
+            "Reset".yellow()
+        );
         println!();
         print!("Enter choice (1 or 2): ");
 
@@ -173,24 +260,12 @@ pub fn start() -> Result<()> {
 
         match input.trim() {
             "1" => {
-                start_existing_container()?;
-
-                // Wait a bit for Caddy to start
-                std::thread::sleep(std::time::Duration::from_secs(2));
-
-                if is_running() {
-                    println!("{}", "✓ Caddy started successfully".green());
-                    println!();
-                    println!("Caddy Admin API: http://localhost:2019");
-                    println!("View logs: docker logs {} -f", CADDY_CONTAINER_NAME);
-                } else {
-                    println!("{}", "⚠ Caddy may have failed to start".yellow());
-                    println!("Check logs: docker logs {}", CADDY_CONTAINER_NAME);
-                }
+                start_existing_container(container_name)?;
+                wait_and_report(settings);
                 return Ok(());
             }
             "2" => {
-                remove_container()?;
+                remove_container(container_name)?;
                 // Continue to create new container below
             }
             _ => {
@@ -201,81 +276,61 @@ pub fn start() -> Result<()> {
 
     println!("{}", "Starting Caddy reverse proxy...".blue());
 
-    // Ensure Caddyfile exists
-    ensure_caddyfile()?;
+    // Ensure Caddyfile matches the settings
+    ensure_caddyfile(&global_config)?;
 
     // Ensure network exists
-    ensure_caddy_network()?;
+    ensure_network(global_config.caddy_network())?;
 
-    // Get config directory for volume mounts
     let config_dir = get_config_dir()?;
-    let caddyfile_path = config_dir.join("caddy/Caddyfile");
-    let certs_path = config_dir.join("caddy/certs");
-    let projects_path = config_dir.join("caddy/projects");
 
-    println!("{} Starting Caddy container...", "ℹ".blue());
+    println!(
+        "{} Starting Caddy container ({})...",
+        "ℹ".blue(),
+        settings.image
+    );
 
-    // Start Caddy using docker run
     let status = Command::new("docker")
-        .args(&[
-            "run",
-            "-d",
-            "--name",
-            CADDY_CONTAINER_NAME,
-            "--restart",
-            "unless-stopped",
-            "-p",
-            "80:80",
-            "-p",
-            "443:443",
-            "-p",
-            "443:443/udp",
-            "-p",
-            "2019:2019",
-            "-v",
-            &format!("{}:/etc/caddy/Caddyfile:ro", caddyfile_path.display()),
-            "-v",
-            &format!("{}:/certs:ro", certs_path.display()),
-            "-v",
-            &format!("{}:/etc/caddy/projects:ro", projects_path.display()),
-            "-v",
-            "caddy_data:/data",
-            "-v",
-            "caddy_config:/config",
-            "--network",
-            CADDY_NETWORK_NAME,
-            "-e",
-            "CADDY_ADMIN=0.0.0.0:2019",
-            "--label",
-            &format!("{}=caddy", OMD_SERVICE_LABEL),
-            "caddy:latest",
-        ])
+        .args(run_args(&global_config, &config_dir))
         .status()
         .context("Failed to start Caddy")?;
 
     if !status.success() {
+        if !settings.uses_custom_ports() {
+            anyhow::bail!(
+                "Failed to start Caddy container\n\
+                If ports 80/443 are in use or cannot be bound, set high_ports = true in the \
+                [caddy] section of config.toml to use 8080/8443."
+            );
+        }
         anyhow::bail!("Failed to start Caddy container");
     }
 
+    wait_and_report(settings);
+
+    Ok(())
+}
+
+/// Wait for a started container and report whether it is running
+fn wait_and_report(settings: &CaddySettings) {
     // Wait a bit for Caddy to start
     std::thread::sleep(std::time::Duration::from_secs(2));
 
-    if is_running() {
+    if is_running(&settings.container_name) {
         println!("{}", "✓ Caddy started successfully".green());
         println!();
-        println!("Caddy Admin API: http://localhost:2019");
-        println!("View logs: docker logs {} -f", CADDY_CONTAINER_NAME);
+        print_access(settings);
     } else {
         println!("{}", "⚠ Caddy may have failed to start".yellow());
-        println!("Check logs: docker logs {}", CADDY_CONTAINER_NAME);
+        println!("Check logs: docker logs {}", settings.container_name);
     }
-
-    Ok(())
 }
 
 /// Stop Caddy container
 pub fn stop() -> Result<()> {
-    if !is_running() {
+    let settings = load_global_config()?.caddy;
+
+    if !is_running(&settings.container_name) {
         println!("{} Caddy is not running", "ℹ".blue());
         return Ok(());
     }
@@ -283,7 +338,7 @@ pub fn stop() -> Result<()> {
     println!("{}", "Stopping Caddy...".blue());
 
     let status = Command::new("docker")
-        .args(&["stop", CADDY_CONTAINER_NAME])
+        .args(["stop", &settings.container_name])
         .status()
         .context("Failed to stop Caddy")?;
 
@@ -298,7 +353,9 @@ pub fn stop() -> Result<()> {
 
 /// Restart Caddy container
 pub fn restart() -> Result<()> {
-    if !is_running() {
+    let settings = load_global_config()?.caddy;
+
+    if !is_running(&settings.container_name) {
         println!("{} Caddy is not running, starting it...", "ℹ".blue());
         return start();
     }
@@ -306,7 +363,7 @@ pub fn restart() -> Result<()> {
     println!("{}", "Restarting Caddy...".blue());
 
     let status = Command::new("docker")
-        .args(&["restart", CADDY_CONTAINER_NAME])
+        .args(["restart", &settings.container_name])
         .status()
         .context("Failed to restart Caddy")?;
 
@@ -321,20 +378,23 @@ pub fn restart() -> Result<()> {
 
 /// Show Caddy status
 pub fn status() -> Result<()> {
+    let global_config = load_global_config()?;
+    let settings = &global_config.caddy;
+
     println!("{}", "Caddy Status:".blue());
     println!();
 
-    if is_running() {
+    if is_running(&settings.container_name) {
         println!("  Status: {}", "Running".green());
 
         // Get container details
         let output = Command::new("docker")
-            .args(&[
+            .args([
                 "ps",
                 "--filter",
-                &format!("name={}", CADDY_CONTAINER_NAME),
+                &format!("name={}", settings.container_name),
                 "--format",
-                "table {{.Status}}\t{{.Ports}}",
+                "table {{.Image}}\t{{.Status}}\t{{.Ports}}",
             ])
             .output()
             .context("Failed to get container status")?;
@@ -343,10 +403,10 @@ pub fn status() -> Result<()> {
         for line in info.lines().skip(1) {
             println!("  {}", line);
         }
+        println!("  Network: {}", global_config.caddy_network());
 
         println!();
-        println!("Admin API: http://localhost:2019");
-        println!("Logs: docker logs {} -f", CADDY_CONTAINER_NAME);
+        print_access(settings);
     } else {
         println!("  Status: {}", "Not running".red());
         println!();
@@ -358,7 +418,9 @@ pub fn status() -> Result<()> {
 
 /// Show Caddy logs
 pub fn logs(follow: bool) -> Result<()> {
-    if !is_running() {
+    let settings = load_global_config()?.caddy;
+
+    if !is_running(&settings.container_name) {
         println!("{} Caddy is not running", "⚠".yellow());
         return Ok(());
     }
@@ -367,7 +429,7 @@ pub fn logs(follow: bool) -> Result<()> {
     if follow {
         args.push("-f");
     }
-    args.push(CADDY_CONTAINER_NAME);
+    args.push(&settings.container_name);
 
     let status = Command::new("docker")
         .args(&args)
@@ -381,18 +443,66 @@ pub fn logs(follow: bool) -> Result<()> {
     Ok(())
 }
 
-/// Auto-start Caddy if not running (called from project up)
-pub fn auto_start_if_needed() -> Result<()> {
-    if is_running() {
-        return Ok(());
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_args_use_settings() {
+        let mut global_config: GlobalConfig = toml::from_str(
+            "[global]\ncaddy_network = \"caddy-net\"\ncaddy_projects_dir = \"caddy/projects\"\ncaddy_certs_dir = \"caddy/certs\"\n\n[caddy]\nimage = \"caddy:2.10\"\ncontainer_name = \"edge\"\nnetwork = \"edge-net\"\nhttp_port = 8080\nhttps_port = 8443\nvolumes = [\"/srv/site:/srv/site:ro\"]\nenv = { CADDY_LOG_LEVEL = \"debug\" }\n",
+        )
+        .unwrap();
+        let args = run_args(&global_config, Path::new("/omd")).join(" ");
+
+        assert!(args.starts_with("run -d --name edge "));
+        assert!(args.contains("-p 8080:8080 -p 8443:8443 -p 8443:8443/udp -p 127.0.0.1:2019:2019"));
+        assert!(args.contains("-v /omd/caddy/certs:/certs:ro"));
+        assert!(args.contains("-v /srv/site:/srv/site:ro"));
+        assert!(args.contains("--network edge-net"));
+        assert!(args.contains("-e CADDY_LOG_LEVEL=debug"));
+        assert!(args.ends_with(" caddy:2.10"));
+        assert_eq!(
+            admin_url(&global_config.caddy).as_deref(),
+            Some("http://127.0.0.1:2019")
+        );
+
+        // The admin API can stay unpublished
+        global_config.caddy.admin = String::new();
+        assert!(
+            !run_args(&global_config, Path::new("/omd"))
+                .contains(&"127.0.0.1:2019:2019".to_string())
+        );
+        assert_eq!(admin_url(&global_config.caddy), None);
     }
 
-    println!();
-    println!("{} Caddy is not running", "ℹ".blue());
-    println!("{} Starting Caddy automatically...", "ℹ".blue());
-    println!();
-
-    start()?;
-
-    Ok(())
+    #[test]
+    fn test_caddyfile_ports() {
+        let mut global_config: GlobalConfig = toml::from_str(
+            "[global]\ncaddy_network = \"caddy-net\"\ncaddy_projects_dir = \"caddy/projects\"\ncaddy_certs_dir = \"caddy/certs\"\nenable_https = true\n",
+        )
+        .unwrap();
+        assert!(!caddyfile_content(&global_config).contains("_port"));
+        assert!(run_args(&global_config, Path::new("/omd")).contains(&"443:443".to_string()));
+
+        global_config.caddy.high_ports = true;
+        let caddyfile = caddyfile_content(&global_config);
+        assert!(caddyfile.contains("    http_port 8080\n    https_port 8443\n"));
+        assert!(!caddyfile.contains("auto_https off"));
+        assert_eq!(
+            global_config.project_url("shop.local"),
+            "https://shop.local:8443"
+        );
+        assert_eq!(global_config.caddy_site_port(), Some(8443));
+
+        // Explicit ports win over the high-port defaults
+        global_config.caddy.https_port = Some(9443);
+        global_config.global.enable_https = false;
+        assert_eq!(
+            global_config.project_url("shop.local"),
+            "http://shop.local:8080"
+        );
+        assert_eq!(global_config.caddy_site_port(), Some(8080));
+        assert!(caddyfile_content(&global_config).contains("https_port 9443"));
+    }
 }
@@ -3,13 +3,12 @@
 //! This module handles adding, removing, and listing manual proxy rules
 //! that are not tied to specific projects.
 
-use std::fs;
-use std::process::Command;
+use std::{fs, process::Command};
 
 use anyhow::{Context, Result};
 use colored::Colorize;
 
-use super::CADDY_CONTAINER_NAME;
+use super::manager::is_running;
 use crate::config::{get_config_dir, load_global_config};
 
 /// Proxy rule storage
@@ -26,7 +25,7 @@ pub fn add(domain: &str, target: &str) -> Result<()> {
     let caddy_projects_dir = config_dir.join(&global_config.global.caddy_projects_dir);
 
     // Create a safe filename from domain
-    let filename = domain.replace('.', "_").replace(':', "_");
+    let filename = domain.replace(['.', ':'], "_");
     let config_file = caddy_projects_dir.join(format!("{}.caddy", filename));
 
     // Check if rule already exists
@@ -39,11 +38,16 @@ pub fn add(domain: &str, target: &str) -> Result<()> {
         return Ok(());
     }
 
-    // Generate Caddy configuration
+    // Generate Caddy configuration (listening on the HTTPS port in high-port mode)
     let cert_name = domain.replace('.', "_");
+    let address = if global_config.caddy.uses_custom_ports() {
+        format!("{}:{}", domain, global_config.caddy.https_port())
+    } else {
+        domain.to_string()
+    };
     let caddy_config = format!(
         "# Auto-generated proxy rule\n# Domain: {}\n# Target: {}\n\n{} {{\n    tls /certs/{}.crt /certs/{}.key\n    reverse_proxy {}\n}}\n",
-        domain, target, domain, cert_name, cert_name, target
+        domain, target, address, cert_name, cert_name, target
     );
 
     fs::write(&config_file, caddy_config).context("Failed to write proxy configuration")?;
@@ -68,7 +72,7 @@ pub fn remove(domain: &str) -> Result<()> {
     let caddy_projects_dir = config_dir.join(&global_config.global.caddy_projects_dir);
 
     // Try to find the config file
-    let filename = domain.replace('.', "_").replace(':', "_");
+    let filename = domain.replace(['.', ':'], "_");
     let config_file = caddy_projects_dir.join(format!("{}.caddy", filename));
 
     if !config_file.exists() {
@@ -117,14 +121,13 @@ pub fn list() -> Result<()> {
         let entry = entry?;
         let path = entry.path();
 
-        if let Some(ext) = path.extension() {
-            if ext == "caddy" {
-                if let Ok(content) = fs::read_to_string(&path) {
-                    // Parse domain and target from config file
-                    if let Some(rule) = parse_proxy_rule(&content) {
-                        rules.push(rule);
-                    }
-                }
+        if let Some(ext) = path.extension()
+            && ext == "caddy"
+            && let Ok(content) = fs::read_to_string(&path)
+        {
+            // Parse domain and target from config file
+            if let Some(rule) = parse_proxy_rule(&content) {
+                rules.push(rule);
             }
         }
     }
@@ -134,7 +137,7 @@ pub fn list() -> Result<()> {
         return Ok(());
     }
 
-    println!("  {:<40} {}", "DOMAIN", "TARGET");
+    println!("  {:<40} TARGET", "DOMAIN");
     println!("  {}", "-".repeat(60));
 
     for rule in rules {
@@ -146,32 +149,18 @@ pub fn list() -> Result<()> {
 
 /// Reload Caddy configuration
 pub fn reload() -> Result<()> {
-    // Check if Caddy is running
-    let output = Command::new("docker")
-        .args(&[
-            "ps",
-            "--filter",
-            &format!("name={}", CADDY_CONTAINER_NAME),
-            "--format",
-            "{{.Names}}",
-        ])
-        .output()
-        .context("Failed to check Caddy status")?;
-
-    let caddy_running = String::from_utf8_lossy(&output.stdout)
-        .trim()
-        .contains(CADDY_CONTAINER_NAME);
+    let container_name = load_global_config()?.caddy.container_name;
 
-    if !caddy_running {
+    if !is_running(&container_name) {
         println!("{} Caddy is not running, skipping reload", "⚠".yellow());
         return Ok(());
     }
 
     println!("{} Reloading Caddy configuration...", "ℹ".blue());
     let status = Command::new("docker")
-        .args(&[
+        .args([
             "exec",
-            CADDY_CONTAINER_NAME,
+            &container_name,
             "caddy",
             "reload",
             "--config",
@@ -198,15 +187,17 @@ fn parse_proxy_rule(content: &str) -> Option<ProxyRule> {
         let line = line.trim();
 
         // Find domain (first non-comment line that's not empty and not a block start)
-        if domain.is_none() && !line.is_empty() && !line.starts_with('#') && !line.starts_with('{')
+        if domain.is_none()
+            && !line.is_empty()
+            && !line.starts_with('#')
+            && !line.starts_with('{')
+            && !line.contains("reverse_proxy")
         {
-            if !line.contains("reverse_proxy") {
-                // Extract domain by splitting on whitespace and removing trailing '{'
-                let domain_str = line.split_whitespace().next().unwrap_or(line);
-                let domain_clean = domain_str.trim_end_matches('{').trim();
-                if !domain_clean.is_empty() {
-                    domain = Some(domain_clean.to_string());
-                }
+            // Extract domain by splitting on whitespace and removing trailing '{'
+            let domain_str = line.split_whitespace().next().unwrap_or(line);
+            let domain_clean = domain_str.trim_end_matches('{').trim();
+            if !domain_clean.is_empty() {
+                domain = Some(domain_clean.to_string());
             }
         }
 
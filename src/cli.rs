@@ -2,7 +2,12 @@
 //!
 //! This module contains all the clap-based command definitions and argument parsing.
 
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
+
+use crate::{
+    dns::setup::ResolverKind,
+    project::{bulk::Selection, init::parse_route},
+};
 
 #[derive(Parser)]
 #[command(name = "omd")]
@@ -16,7 +21,64 @@ pub struct Cli {
 #[derive(Subcommand)]
 pub enum Commands {
     /// Initialize omd.toml in current directory
-    Init,
+    ///
+    /// Values not given as flags are prompted for (or defaulted with --yes).
+    Init {
+        /// Project name (defaults to the directory name)
+        #[arg(long)]
+        name: Option<String>,
+        /// Project domain (defaults to <name>.local)
+        #[arg(long)]
+        domain: Option<String>,
+        /// Docker network name (defaults to <name>-net)
+        #[arg(long)]
+        network: Option<String>,
+        /// Docker Compose file (defaults to docker-compose.yml)
+        #[arg(long, value_name = "FILE")]
+        compose_file: Option<String>,
+        /// Templates for a new compose file, comma separated ("" creates none)
+        #[arg(long, value_delimiter = ',', value_name = "SERVICES")]
+        services: Option<Vec<String>>,
+        /// Caddy route, repeatable (e.g. --route api=bff:8080)
+        #[arg(long = "route", value_name = "SUBDOMAIN=CONTAINER:PORT", value_parser = parse_route)]
+        routes: Vec<(String, String)>,
+        /// Overwrite an existing omd.toml without asking
+        #[arg(long)]
+        force: bool,
+        /// Do not prompt; use defaults for values not given as flags
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Add template services to the project in the current directory
+    Add {
+        /// Template names (see `omd templates list`)
+        #[arg(required = true, value_name = "SERVICE")]
+        services: Vec<String>,
+        /// Merge the services into the compose file instead of the omd-managed
+        /// include file (docker-compose.omd.yml); refused if the compose file has
+        /// comments or YAML anchors
+        #[arg(long)]
+        merge: bool,
+    },
+    /// Remove services from the project in the current directory
+    RmService {
+        /// Service names
+        #[arg(required = true, value_name = "SERVICE")]
+        services: Vec<String>,
+        /// Rewrite the compose file even if its comments or YAML anchors are lost
+        #[arg(long)]
+        rewrite: bool,
+    },
+    /// Upgrade omd.toml to the current format version, keeping comments
+    Migrate,
+    /// Regenerate routes and hosts entries when omd.toml or compose files change
+    Watch {
+        #[command(flatten)]
+        target: ProjectArg,
+        /// Also run 'docker compose up -d' when compose files change
+        #[arg(long)]
+        restart: bool,
+    },
     /// Manage Caddy reverse proxy
     Caddy {
         #[command(subcommand)]
@@ -48,6 +110,21 @@ pub enum Commands {
         #[command(subcommand)]
         subcommand: HostsCommands,
     },
+    /// Local DNS resolver for wildcard project domains
+    Dns {
+        #[command(subcommand)]
+        subcommand: DnsCommands,
+    },
+    /// Browse service templates
+    Templates {
+        #[command(subcommand)]
+        subcommand: TemplatesCommands,
+    },
+    /// Check and manage configuration files
+    Config {
+        #[command(subcommand)]
+        subcommand: ConfigCommands,
+    },
 }
 
 #[derive(Subcommand)]
@@ -72,6 +149,8 @@ pub enum CaddyCommands {
 pub enum NetworkCommands {
     /// List all networks
     List,
+    /// Remove a network (refused while registered projects use it)
+    Remove { name: String },
 }
 
 #[derive(Subcommand)]
@@ -82,8 +161,16 @@ pub enum ProxyCommands {
     Remove { domain: String },
     /// List all proxy rules
     List,
-    /// Reload Caddy configuration
+    /// Reload the configured reverse proxy
     Reload,
+    /// Start the configured reverse proxy (Caddy or Traefik)
+    Start,
+    /// Stop the configured reverse proxy
+    Stop,
+    /// Restart the configured reverse proxy
+    Restart,
+    /// Show the configured reverse proxy status
+    Status,
 }
 
 #[derive(Subcommand)]
@@ -91,17 +178,193 @@ pub enum ProjectCommands {
     /// List all registered projects
     List,
     /// Configure project and start containers (run from project directory)
-    Up,
+    Up {
+        /// Apply a [profiles.<name>] section of omd.toml
+        #[arg(long, value_name = "NAME", conflicts_with_all = ["all", "group"])]
+        profile: Option<String>,
+        #[command(flatten)]
+        selection: BulkArgs,
+    },
     /// Stop containers (run from project directory)
-    Down,
+    Down {
+        #[command(flatten)]
+        selection: BulkArgs,
+    },
+    /// Restart containers, or only the given services
+    Restart {
+        /// Services to restart (default: all)
+        #[arg(conflicts_with_all = ["all", "group"])]
+        services: Vec<String>,
+        #[command(flatten)]
+        selection: BulkArgs,
+        /// A registered project instead of the current one
+        #[arg(long, short = 'p', value_name = "NAME", conflicts_with_all = ["all", "group"])]
+        project: Option<String>,
+    },
+    /// Show service logs (default: all services)
+    Logs {
+        services: Vec<String>,
+        /// Follow log output
+        #[arg(long, short = 'f')]
+        follow: bool,
+        /// Number of lines to show from the end of the logs
+        #[arg(long, value_name = "N")]
+        tail: Option<String>,
+        #[command(flatten)]
+        target: ProjectArg,
+    },
+    /// List the project's containers
+    Ps {
+        #[command(flatten)]
+        target: ProjectArg,
+    },
+    /// Run a command in a running service container
+    Exec {
+        service: String,
+        /// Command and arguments, after `--`
+        #[arg(last = true, required = true)]
+        command: Vec<String>,
+        #[command(flatten)]
+        target: ProjectArg,
+    },
+    /// Pull service images (default: all services)
+    Pull {
+        services: Vec<String>,
+        #[command(flatten)]
+        target: ProjectArg,
+    },
+    /// Build service images (default: all services)
+    Build {
+        services: Vec<String>,
+        /// Do not use cache when building the images
+        #[arg(long)]
+        no_cache: bool,
+        #[command(flatten)]
+        target: ProjectArg,
+    },
+    /// Show the registration and containers of the project (run from project directory)
+    Status,
     /// Stop containers and remove all project configuration (run from project directory)
     Remove,
 }
 
+/// Select a registered project by name instead of the current directory
+#[derive(Args)]
+pub struct ProjectArg {
+    /// A registered project instead of the current one
+    #[arg(long, short = 'p', value_name = "NAME")]
+    pub project: Option<String>,
+}
+
+/// Apply a project command to several projects instead of the current one
+#[derive(Args)]
+pub struct BulkArgs {
+    /// All registered projects
+    #[arg(long, conflicts_with = "group")]
+    pub all: bool,
+    /// The projects of a [groups] entry in config.toml
+    #[arg(long, value_name = "NAME")]
+    pub group: Option<String>,
+}
+
+impl BulkArgs {
+    /// The selected projects, or None for the current project
+    pub fn selection(self) -> Option<Selection> {
+        match (self.all, self.group) {
+            (true, _) => Some(Selection::All),
+            (false, Some(group)) => Some(Selection::Group(group)),
+            (false, None) => None,
+        }
+    }
+}
+
 #[derive(Subcommand)]
 pub enum HostsCommands {
     /// List all domains managed by oh-my-dockers
     List,
     /// Remove all oh-my-dockers managed entries from /etc/hosts
     Cleanup,
+    /// Show differences between the hosts file and registered projects
+    Diff,
+    /// Rewrite the hosts sections of all registered projects in one step
+    Sync,
+    /// List hosts file backups
+    Backups,
+    /// Restore the hosts file from a backup (newest by default)
+    Restore {
+        /// Backup timestamp (YYYYMMDD_HHMMSS) or a unique prefix of it
+        timestamp: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum DnsCommands {
+    /// Run the resolver in the foreground
+    Serve {
+        /// Address to listen on (default: dns.listen in config.toml)
+        #[arg(long, value_name = "ADDR")]
+        listen: Option<String>,
+        /// Upstream resolver for non-project names
+        #[arg(long, value_name = "ADDR")]
+        upstream: Option<String>,
+    },
+    /// Delegate project TLDs to the resolver via a system resolver drop-in
+    Setup {
+        /// System resolver to configure
+        #[arg(value_enum)]
+        resolver: ResolverKind,
+        /// Only print the drop-in without writing it
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Remove the system resolver drop-in
+    Teardown {
+        /// System resolver to clean up
+        #[arg(value_enum)]
+        resolver: ResolverKind,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum TemplatesCommands {
+    /// List built-in, user and project templates
+    List,
+    /// Show a template and the compose service it generates
+    Show {
+        /// Template name
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ConfigCommands {
+    /// Validate the project's omd.toml and config.toml
+    Validate,
+    /// Print a config.toml value (e.g. global.enable_https)
+    Get {
+        /// Dotted key
+        key: String,
+    },
+    /// Set a config.toml value, keeping comments
+    Set {
+        /// Dotted key (e.g. networks.shared-net.subnet)
+        key: String,
+        /// New value (TOML literal or plain string)
+        value: String,
+    },
+    /// Open config.toml in $EDITOR and validate on save
+    Edit,
+    /// Migrate config.toml to the current version
+    Migrate {
+        /// Show the changes without writing them
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// List config.toml backups
+    Backups,
+    /// Restore config.toml from a backup (default: the newest)
+    Rollback {
+        /// Backup number from `omd config backups`, or its file name
+        backup: Option<String>,
+    },
 }
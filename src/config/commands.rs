@@ -0,0 +1,420 @@
+//! Configuration commands (validate, get, set, edit, migrate, backups, rollback)
+
+use std::{
+    env, fs,
+    io::{self, Write},
+    path::Path,
+    process::Command,
+};
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use toml_edit::DocumentMut;
+
+use super::{
+    CONFIG_VERSION, GlobalConfig, edit, get_config_dir, migration,
+    validate::{Report, validate_global, validate_global_file, validate_project_file},
+};
+use crate::{
+    diff::{print_diff, unified_diff},
+    project::config::find_project_config,
+};
+
+/// Validate the project's omd.toml (if any) and config.toml
+pub fn validate() -> Result<()> {
+    let mut reports = Vec::new();
+
+    let current_dir = env::current_dir().context("Failed to get current directory")?;
+    if let Some(project_path) = find_project_config(&current_dir) {
+        reports.push(validate_project_file(&project_path)?);
+    }
+
+    let global_path = get_config_dir()?.join("config.toml");
+    if global_path.exists() {
+        reports.push(validate_global_file(&global_path)?);
+    }
+
+    if reports.is_empty() {
+        println!(
+            "{} No omd.toml in the current or parent directories and no {}",
+            "ℹ".blue(),
+            global_path.display()
+        );
+        return Ok(());
+    }
+
+    let mut errors = 0;
+    for report in &reports {
+        report.print();
+
+        let (error_count, warning_count) = (report.error_count(), report.warning_count());
+        errors += error_count;
+        if error_count > 0 {
+            println!(
+                "{} {}: {} error(s), {} warning(s)",
+                "✗".red(),
+                report.path.display(),
+                error_count,
+                warning_count
+            );
+        } else if warning_count > 0 {
+            println!(
+                "{} {}: valid with {} warning(s)",
+                "⚠".yellow(),
+                report.path.display(),
+                warning_count
+            );
+        } else {
+            println!("{} {}: valid", "✓".green(), report.path.display());
+        }
+    }
+
+    if errors > 0 {
+        anyhow::bail!("Configuration has {} error(s)", errors);
+    }
+
+    Ok(())
+}
+
+/// Print a config.toml value
+/// Keys missing from the file show their effective default
+pub fn get(key: &str) -> Result<()> {
+    let path = edit::parse_key(key)?;
+    let config_path = get_config_dir()?.join("config.toml");
+    let content = fs::read_to_string(&config_path)
+        .context(format!("Failed to read {}", config_path.display()))?;
+
+    let file: toml::Value = toml::from_str(&content).context("Failed to parse config.toml")?;
+    if let Some(value) = edit::get_value(&file, &path) {
+        print_value(value);
+        return Ok(());
+    }
+
+    let config: GlobalConfig = file.try_into().context("Failed to parse config.toml")?;
+    let defaults = toml::Value::try_from(&config).context("Failed to serialize configuration")?;
+    match edit::get_value(&defaults, &path) {
+        Some(value) => {
+            print_value(value);
+            Ok(())
+        }
+        None => anyhow::bail!("'{}' is not set in {}", key, config_path.display()),
+    }
+}
+
+/// Set a config.toml value, keeping comments and formatting
+pub fn set(key: &str, raw_value: &str) -> Result<()> {
+    let path = edit::parse_key(key)?;
+    if path.len() == 1 && path[0] == "version" {
+        anyhow::bail!("'version' is managed by oh-my-dockers and cannot be set");
+    }
+
+    let config_path = get_config_dir()?.join("config.toml");
+    let content = fs::read_to_string(&config_path)
+        .context(format!("Failed to read {}", config_path.display()))?;
+    let mut doc: DocumentMut = content
+        .parse()
+        .context(format!("Failed to parse {}", config_path.display()))?;
+
+    edit::set_value(&mut doc, &path, edit::parse_value(raw_value))?;
+    let updated = doc.to_string();
+
+    let report = validate_global(&config_path, updated.clone());
+    if report.error_count() > 0 {
+        report.print();
+        anyhow::bail!(
+            "Not saved: '{}' would make {} invalid",
+            key,
+            config_path.display()
+        );
+    }
+
+    if updated == content {
+        println!("{} {} is unchanged", "ℹ".blue(), key);
+        return Ok(());
+    }
+
+    fs::write(&config_path, &updated)
+        .context(format!("Failed to write {}", config_path.display()))?;
+    println!("{} Set {} = {}", "✓".green(), key, raw_value);
+    print_follow_ups(&[key.to_string()]);
+
+    Ok(())
+}
+
+/// Open config.toml in $VISUAL/$EDITOR and validate it before saving
+pub fn edit() -> Result<()> {
+    let config_dir = get_config_dir()?;
+    let config_path = config_dir.join("config.toml");
+    let original = fs::read_to_string(&config_path)
+        .context(format!("Failed to read {}", config_path.display()))?;
+
+    // Edit a copy so a half-finished or invalid file never replaces config.toml
+    let draft_path = config_dir.join("config.edit.toml");
+    fs::write(&draft_path, &original)
+        .context(format!("Failed to write {}", draft_path.display()))?;
+
+    let result = edit_draft(&config_path, &draft_path, &original);
+    let _ = fs::remove_file(&draft_path);
+    result
+}
+
+fn edit_draft(config_path: &Path, draft_path: &Path, original: &str) -> Result<()> {
+    loop {
+        run_editor(draft_path)?;
+
+        let edited = fs::read_to_string(draft_path)
+            .context(format!("Failed to read {}", draft_path.display()))?;
+        if edited == original {
+            println!("{} No changes", "ℹ".blue());
+            return Ok(());
+        }
+
+        let report = validate_global(config_path, edited.clone());
+        if report.error_count() == 0 {
+            report.print();
+            fs::write(config_path, &edited)
+                .context(format!("Failed to write {}", config_path.display()))?;
+            println!("{} Saved {}", "✓".green(), config_path.display());
+            print_follow_ups(&changed_keys(original, &edited));
+            return Ok(());
+        }
+
+        report.print();
+        print_error_summary(&report);
+        if !confirm("Edit again? [Y/n]: ")? {
+            anyhow::bail!(
+                "Changes discarded; {} was not modified",
+                config_path.display()
+            );
+        }
+    }
+}
+
+/// Migrate config.toml to the current version, or show the changes with `dry_run`
+pub fn migrate(dry_run: bool) -> Result<()> {
+    let config_path = get_config_dir()?.join("config.toml");
+    let content = fs::read_to_string(&config_path)
+        .context(format!("Failed to read {}", config_path.display()))?;
+
+    let version = migration::config_version(&content)?;
+    if version > CONFIG_VERSION {
+        return Err(migration::newer_version_error(version));
+    }
+    if version == CONFIG_VERSION {
+        println!(
+            "{} config.toml is already at version {}",
+            "✓".green(),
+            CONFIG_VERSION
+        );
+        return Ok(());
+    }
+
+    println!(
+        "{} Config migration: v{} -> v{}",
+        "ℹ".blue(),
+        version,
+        CONFIG_VERSION
+    );
+
+    if !dry_run {
+        return migration::apply_migration(&config_path, &content);
+    }
+
+    let Some(migrated) = migration::migrated_content(&content)? else {
+        return Ok(());
+    };
+    let label = config_path.display().to_string();
+    println!();
+    print_diff(&unified_diff(
+        &content,
+        &migrated,
+        &label,
+        &format!("{} (migrated)", label),
+    ));
+    println!();
+    println!("Apply with: {}", "omd config migrate".bright_white());
+
+    Ok(())
+}
+
+/// List the backups of config.toml
+pub fn backups() -> Result<()> {
+    let config_path = get_config_dir()?.join("config.toml");
+    let backups = migration::list_backups(&config_path)?;
+
+    if backups.is_empty() {
+        println!("{} No backups of {}", "ℹ".blue(), config_path.display());
+        return Ok(());
+    }
+
+    println!("{}", "Config backups (newest first):".bright_white());
+    for (i, backup) in backups.iter().enumerate() {
+        let created = backup
+            .created
+            .map(|created| created.format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_else(|| "unknown date".to_string());
+        let version = backup
+            .version
+            .map(|version| format!("v{}", version))
+            .unwrap_or_else(|| "unreadable".to_string());
+        println!(
+            "  {}. {}  {}  {}",
+            i + 1,
+            backup.name().bright_white(),
+            created,
+            version
+        );
+    }
+    println!();
+    println!(
+        "Restore one with: {}",
+        "omd config rollback [number]".bright_white()
+    );
+
+    Ok(())
+}
+
+/// Restore config.toml from a backup
+/// The current file is backed up first, so a rollback can itself be rolled back
+pub fn rollback(selector: Option<&str>) -> Result<()> {
+    let config_path = get_config_dir()?.join("config.toml");
+    let mut backups = migration::list_backups(&config_path)?;
+    if backups.is_empty() {
+        anyhow::bail!("No backups of {} to restore", config_path.display());
+    }
+
+    let index = match selector {
+        None => 0,
+        Some(selector) => match selector.parse::<usize>() {
+            Ok(number) if (1..=backups.len()).contains(&number) => number - 1,
+            Ok(number) => anyhow::bail!(
+                "No backup number {}; 'omd config backups' lists {} backup(s)",
+                number,
+                backups.len()
+            ),
+            Err(_) => backups
+                .iter()
+                .position(|backup| backup.name() == selector)
+                .with_context(|| {
+                    format!("No backup named '{}'. Run 'omd config backups'", selector)
+                })?,
+        },
+    };
+    let backup = backups.swap_remove(index);
+
+    let Some(version) = backup.version else {
+        anyhow::bail!("{} is not a valid config.toml", backup.path.display());
+    };
+    if version > CONFIG_VERSION {
+        anyhow::bail!(
+            "{} is version {}, newer than this omd supports (version {})",
+            backup.name(),
+            version,
+            CONFIG_VERSION
+        );
+    }
+
+    let content = fs::read_to_string(&backup.path)
+        .context(format!("Failed to read {}", backup.path.display()))?;
+    migration::backup_config(&config_path)?;
+    fs::write(&config_path, content)
+        .context(format!("Failed to write {}", config_path.display()))?;
+    migration::prune_backups(&config_path)?;
+
+    println!(
+        "{} Restored {} from {}",
+        "✓".green(),
+        config_path.display(),
+        backup.name()
+    );
+    if version < CONFIG_VERSION {
+        println!(
+            "{} The restored file is version {}; it is migrated to v{} on the next command",
+            "ℹ".blue(),
+            version,
+            CONFIG_VERSION
+        );
+    }
+
+    Ok(())
+}
+
+/// Launch the user's editor on a file
+fn run_editor(path: &Path) -> Result<()> {
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().context("$EDITOR is empty")?;
+
+    let status = Command::new(program)
+        .args(parts)
+        .arg(path)
+        .status()
+        .context(format!("Failed to run editor '{}'", editor))?;
+    if !status.success() {
+        anyhow::bail!("Editor '{}' exited with {}", editor, status);
+    }
+
+    Ok(())
+}
+
+/// Keys changed between two versions of config.toml
+fn changed_keys(old: &str, new: &str) -> Vec<String> {
+    match (
+        toml::from_str::<toml::Value>(old),
+        toml::from_str::<toml::Value>(new),
+    ) {
+        (Ok(old), Ok(new)) => edit::changed_keys(&old, &new),
+        _ => Vec::new(),
+    }
+}
+
+/// Print the actions needed for changed keys, once per action
+fn print_follow_ups(keys: &[String]) {
+    let mut printed = Vec::new();
+    for key in keys {
+        if let Some(action) = edit::follow_up(key)
+            && !printed.contains(&action)
+        {
+            println!("{} {}", "ℹ".blue(), action);
+            printed.push(action);
+        }
+    }
+}
+
+fn print_error_summary(report: &Report) {
+    println!(
+        "{} {}: {} error(s), {} warning(s)",
+        "✗".red(),
+        report.path.display(),
+        report.error_count(),
+        report.warning_count()
+    );
+}
+
+/// Print a value: strings without quotes, tables as TOML
+fn print_value(value: &toml::Value) {
+    match value {
+        toml::Value::String(s) => println!("{}", s),
+        toml::Value::Table(table) => {
+            print!("{}", toml::to_string_pretty(table).unwrap_or_default())
+        }
+        value => println!("{}", value),
+    }
+}
+
+/// Ask a yes/no question
+fn confirm(prompt: &str) -> Result<bool> {
+    print!("{}", prompt);
+    io::stdout().flush()?;
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+
+    let input = input.trim();
+    if input.is_empty() {
+        return Ok(prompt.contains("[Y/n]"));
+    }
+    Ok(input.eq_ignore_ascii_case("y") || input.eq_ignore_ascii_case("yes"))
+}
@@ -0,0 +1,228 @@
+//! Format-preserving edits of config.toml (omd config get/set/edit)
+//!
+//! Keys are dotted paths such as `global.enable_https` or
+//! `networks.shared-net.subnet`. Segments containing dots can be quoted:
+//! `networks."my.net".subnet`. Edits go through toml_edit, so comments and
+//! formatting of the rest of the file are kept.
+
+use anyhow::{Context, Result};
+use toml_edit::{DocumentMut, Item, Table, Value};
+
+/// Split a dotted key into its segments
+pub fn parse_key(key: &str) -> Result<Vec<String>> {
+    let mut segments = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut was_quoted = false;
+
+    for c in key.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                was_quoted = true;
+            }
+            '.' if !quoted => {
+                if current.is_empty() && !was_quoted {
+                    anyhow::bail!("Invalid key '{}': empty segment", key);
+                }
+                segments.push(std::mem::take(&mut current));
+                was_quoted = false;
+            }
+            _ => current.push(c),
+        }
+    }
+
+    if quoted {
+        anyhow::bail!("Invalid key '{}': unterminated quote", key);
+    }
+    if current.is_empty() && !was_quoted {
+        anyhow::bail!("Invalid key '{}': empty segment", key);
+    }
+    segments.push(current);
+
+    Ok(segments)
+}
+
+/// Look up a value in a parsed configuration
+pub fn get_value<'a>(value: &'a toml::Value, path: &[String]) -> Option<&'a toml::Value> {
+    path.iter()
+        .try_fold(value, |value, segment| value.get(segment.as_str()))
+}
+
+/// Parse a value given on the command line
+/// TOML literals (`true`, `8443`, `["a", "b"]`) keep their type; anything else
+/// is a string, so `172.30.0.0/16` needs no quotes
+pub fn parse_value(raw: &str) -> Value {
+    raw.parse::<Value>().unwrap_or_else(|_| Value::from(raw))
+}
+
+/// Set a value, creating missing tables on the way
+/// The decoration (spacing, trailing comment) of an existing value is kept
+pub fn set_value(doc: &mut DocumentMut, path: &[String], value: Value) -> Result<()> {
+    let (last, parents) = path.split_last().context("Empty key")?;
+
+    let mut table = doc.as_table_mut() as &mut dyn toml_edit::TableLike;
+    for (i, segment) in parents.iter().enumerate() {
+        if !table.contains_key(segment) {
+            table.insert(segment, Item::Table(Table::new()));
+        }
+        table = table
+            .get_mut(segment)
+            .and_then(Item::as_table_like_mut)
+            .with_context(|| format!("'{}' is not a table", parents[..=i].join(".")))?;
+    }
+
+    match table.get_mut(last) {
+        Some(Item::Value(existing)) => {
+            let decor = existing.decor().clone();
+            *existing = value;
+            *existing.decor_mut() = decor;
+        }
+        Some(item) if item.is_table_like() => {
+            anyhow::bail!(
+                "'{}' is a table; set one of its keys instead",
+                path.join(".")
+            );
+        }
+        _ => {
+            table.insert(last, Item::Value(value));
+        }
+    }
+
+    Ok(())
+}
+
+/// Dotted keys whose values differ between two configurations
+pub fn changed_keys(old: &toml::Value, new: &toml::Value) -> Vec<String> {
+    let mut changed = Vec::new();
+    collect_changes(old, new, String::new(), &mut changed);
+    changed
+}
+
+fn collect_changes(old: &toml::Value, new: &toml::Value, prefix: String, out: &mut Vec<String>) {
+    match (old.as_table(), new.as_table()) {
+        (Some(old_table), Some(new_table)) => {
+            let mut keys: Vec<&String> = old_table.keys().collect();
+            keys.extend(new_table.keys().filter(|k| !old_table.contains_key(*k)));
+
+            for key in keys {
+                let path = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                match (old_table.get(key), new_table.get(key)) {
+                    (Some(a), Some(b)) => collect_changes(a, b, path, out),
+                    _ => out.push(path),
+                }
+            }
+        }
+        _ if old != new => out.push(prefix),
+        _ => {}
+    }
+}
+
+/// Follow-up action needed after changing a key, if any
+pub fn follow_up(key: &str) -> Option<&'static str> {
+    let section = key.split('.').next().unwrap_or(key);
+    match (section, key) {
+        (_, "global.enable_https") => Some(
+            "Recreate the proxy (omd caddy stop, omd caddy start, choose Reset) so the Caddyfile \
+             is regenerated, then run 'omd project up' in each project",
+        ),
+        (_, "global.proxy_backend") => Some(
+            "Stop the previous proxy (omd caddy stop / omd proxy stop), then run 'omd project up' \
+             in each project",
+        ),
+        (_, "global.caddy_network" | "global.caddy_projects_dir" | "global.caddy_certs_dir") => {
+            Some("Recreate the proxy container (omd caddy stop, omd caddy start, choose Reset)")
+        }
+        (_, "global.hosts_file") => Some("Run 'omd hosts sync' to write the new hosts file"),
+        ("caddy", _) => {
+            Some("Recreate the Caddy container (omd caddy stop, omd caddy start, choose Reset)")
+        }
+        ("traefik", _) => Some("Run 'omd proxy restart' to recreate the Traefik container"),
+        ("networks", _) => Some(
+            "Existing Docker networks are not changed: remove the network (docker network rm) \
+             and run 'omd project up' to create it again",
+        ),
+        ("dns", _) => Some("Restart 'omd dns serve' to use the new setting"),
+        ("defaults", _) => Some("Run 'omd project up' in each project to apply the new defaults"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"# Global Configuration
+version = 1
+
+[global]
+caddy_network = "caddy-net"
+enable_https = true # local certificates
+
+[networks]
+# Caddy reverse proxy network
+caddy-net = {}
+"#;
+
+    fn key(key: &str) -> Vec<String> {
+        parse_key(key).unwrap()
+    }
+
+    #[test]
+    fn test_parse_key() {
+        assert_eq!(key("global.enable_https"), vec!["global", "enable_https"]);
+        assert_eq!(
+            key("networks.\"my.net\".subnet"),
+            vec!["networks", "my.net", "subnet"]
+        );
+        assert!(parse_key("global..enable_https").is_err());
+        assert!(parse_key("networks.\"my.net").is_err());
+    }
+
+    #[test]
+    fn test_set_value_keeps_comments() {
+        let mut doc: DocumentMut = CONFIG.parse().unwrap();
+
+        set_value(&mut doc, &key("global.enable_https"), parse_value("false")).unwrap();
+        set_value(
+            &mut doc,
+            &key("networks.caddy-net.subnet"),
+            parse_value("172.30.0.0/16"),
+        )
+        .unwrap();
+        set_value(&mut doc, &key("caddy.https_port"), parse_value("8443")).unwrap();
+
+        let content = doc.to_string();
+        assert!(content.starts_with("# Global Configuration\n"));
+        assert!(content.contains("enable_https = false # local certificates\n"));
+        assert!(content.contains("# Caddy reverse proxy network\n"));
+        assert!(content.contains("caddy-net = { subnet = \"172.30.0.0/16\" }"));
+        assert!(content.contains("[caddy]\nhttps_port = 8443\n"));
+
+        assert!(set_value(&mut doc, &key("global"), parse_value("1")).is_err());
+        assert!(set_value(&mut doc, &key("version.minor"), parse_value("1")).is_err());
+    }
+
+    #[test]
+    fn test_changed_keys() {
+        let old: toml::Value = toml::from_str(CONFIG).unwrap();
+        let new: toml::Value = toml::from_str(
+            &CONFIG
+                .replace("true", "false")
+                .replace("caddy-net = {}", "caddy-net = {}\nshared = {}"),
+        )
+        .unwrap();
+
+        assert_eq!(
+            changed_keys(&old, &new),
+            vec!["global.enable_https", "networks.shared"]
+        );
+        assert!(follow_up("networks.shared").is_some());
+        assert!(follow_up("defaults.timezone").is_some());
+        assert!(follow_up("version").is_none());
+    }
+}
@@ -3,32 +3,62 @@
 //! This module handles automatic migration of config.toml when the
 //! configuration structure changes between versions.
 
-use std::fs;
-use std::path::Path;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
 
 use anyhow::{Context, Result};
-use chrono::Local;
+use chrono::{Local, NaiveDateTime};
 use colored::Colorize;
 use toml::Value;
 
 use super::CONFIG_VERSION;
 
-/// Check if migration is needed and perform it if necessary
-pub fn migrate_config_if_needed(config_path: &Path) -> Result<()> {
-    let content = fs::read_to_string(config_path)
-        .context("Failed to read config.toml for migration check")?;
+/// Number of backups kept next to config.toml
+const MAX_BACKUPS: usize = 5;
 
-    // Parse as generic TOML value to check version
-    let mut config: Value =
-        toml::from_str(&content).context("Failed to parse config.toml for migration")?;
+/// Backups are named config.toml.backup.<timestamp>
+const BACKUP_MARKER: &str = ".backup.";
+
+/// Timestamp format of backup file names
+const BACKUP_TIMESTAMP: &str = "%Y%m%d_%H%M%S";
+
+/// Version of a config.toml file (0 if the field is missing)
+pub fn config_version(content: &str) -> Result<u32> {
+    let config: Value = toml::from_str(content).context("Failed to parse config.toml")?;
+    Ok(version_of(&config))
+}
 
-    // Get current version (default to 0 if not present)
-    let current_version = config
+fn version_of(config: &Value) -> u32 {
+    config
         .get("version")
         .and_then(|v| v.as_integer())
-        .unwrap_or(0) as u32;
+        .unwrap_or(0) as u32
+}
+
+/// Error for a config.toml written by a newer omd
+pub fn newer_version_error(version: u32) -> anyhow::Error {
+    anyhow::anyhow!(
+        "config.toml is version {}, newer than this omd supports (version {}). \
+         Update omd, or restore an older file with 'omd config rollback'",
+        version,
+        CONFIG_VERSION
+    )
+}
+
+/// Check if migration is needed and perform it if necessary
+pub fn migrate_config_if_needed(config_path: &Path) -> Result<()> {
+    let content = fs::read_to_string(config_path)
+        .context("Failed to read config.toml for migration check")?;
 
-    if current_version >= CONFIG_VERSION {
+    let current_version = config_version(&content)?;
+    if current_version > CONFIG_VERSION {
+        // Never downgrade; commands keep working with the settings this omd knows
+        println!("{} {}", "⚠".yellow(), newer_version_error(current_version));
+        return Ok(());
+    }
+    if current_version == CONFIG_VERSION {
         // No migration needed
         return Ok(());
     }
@@ -40,8 +70,22 @@ pub fn migrate_config_if_needed(config_path: &Path) -> Result<()> {
         CONFIG_VERSION
     );
 
-    // Backup before migration
-    backup_config(config_path)?;
+    apply_migration(config_path, &content)
+}
+
+/// Migrate config.toml content to the current version
+/// Returns None if the content is already current
+pub fn migrated_content(content: &str) -> Result<Option<String>> {
+    let mut config: Value =
+        toml::from_str(content).context("Failed to parse config.toml for migration")?;
+
+    let current_version = version_of(&config);
+    if current_version > CONFIG_VERSION {
+        return Err(newer_version_error(current_version));
+    }
+    if current_version == CONFIG_VERSION {
+        return Ok(None);
+    }
 
     // Apply migrations sequentially
     let mut version = current_version;
@@ -53,19 +97,29 @@ pub fn migrate_config_if_needed(config_path: &Path) -> Result<()> {
     // Update version field
     config
         .as_table_mut()
-        .unwrap()
+        .context("Config is not a table")?
         .insert("version".to_string(), Value::Integer(CONFIG_VERSION as i64));
 
-    // Write migrated config
     let new_content = toml::to_string_pretty(&config).context("Failed to serialize config")?;
 
     // Add header comment
-    let final_content = format!(
+    Ok(Some(format!(
         "# Global Configuration for oh-my-dockers\n# DO NOT EDIT the version field manually\n{}",
         new_content
-    );
+    )))
+}
+
+/// Back up config.toml, then write the migrated content
+pub fn apply_migration(config_path: &Path, content: &str) -> Result<()> {
+    let Some(new_content) = migrated_content(content)? else {
+        return Ok(());
+    };
 
-    fs::write(config_path, final_content).context("Failed to write migrated config")?;
+    // Backup before migration
+    backup_config(config_path)?;
+
+    fs::write(config_path, new_content).context("Failed to write migrated config")?;
+    prune_backups(config_path)?;
 
     println!(
         "{} Config migrated successfully to v{}",
@@ -76,15 +130,23 @@ pub fn migrate_config_if_needed(config_path: &Path) -> Result<()> {
     Ok(())
 }
 
-/// Backup the config file before migration
-fn backup_config(config_path: &Path) -> Result<()> {
-    let timestamp = Local::now().format("%Y%m%d_%H%M%S");
+/// Backup the config file before migration or rollback
+pub fn backup_config(config_path: &Path) -> Result<PathBuf> {
+    let timestamp = Local::now().format(BACKUP_TIMESTAMP);
     let backup_name = format!(
-        "{}.backup.{}",
+        "{}{}{}",
         config_path.file_name().unwrap().to_string_lossy(),
+        BACKUP_MARKER,
         timestamp
     );
-    let backup_path = config_path.parent().unwrap().join(backup_name);
+    let mut backup_path = config_path.parent().unwrap().join(&backup_name);
+
+    // Never overwrite a backup written in the same second
+    let mut counter = 1;
+    while backup_path.exists() {
+        counter += 1;
+        backup_path = backup_path.with_file_name(format!("{}-{}", backup_name, counter));
+    }
 
     fs::copy(config_path, &backup_path).context("Failed to create config backup")?;
 
@@ -94,6 +156,73 @@ fn backup_config(config_path: &Path) -> Result<()> {
         backup_path.display()
     );
 
+    Ok(backup_path)
+}
+
+/// A backup of config.toml
+#[derive(Debug)]
+pub struct Backup {
+    pub path: PathBuf,
+    /// When the backup was written (from the file name)
+    pub created: Option<NaiveDateTime>,
+    /// Config version of the backup, if it parses
+    pub version: Option<u32>,
+}
+
+impl Backup {
+    /// File name of the backup
+    pub fn name(&self) -> String {
+        self.path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default()
+    }
+}
+
+/// Backups of config.toml, newest first
+pub fn list_backups(config_path: &Path) -> Result<Vec<Backup>> {
+    let dir = config_path.parent().context("config.toml has no parent")?;
+    let prefix = format!(
+        "{}{}",
+        config_path.file_name().unwrap().to_string_lossy(),
+        BACKUP_MARKER
+    );
+
+    let mut backups = Vec::new();
+    for entry in fs::read_dir(dir).context(format!("Failed to read {}", dir.display()))? {
+        let path = entry?.path();
+        let Some(timestamp) = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_prefix(&prefix))
+        else {
+            continue;
+        };
+
+        // Backups written in the same second have a "-<n>" suffix
+        let timestamp = timestamp.split('-').next().unwrap_or(timestamp);
+        let created = NaiveDateTime::parse_from_str(timestamp, BACKUP_TIMESTAMP).ok();
+        let version = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| config_version(&content).ok());
+        backups.push(Backup {
+            path,
+            created,
+            version,
+        });
+    }
+
+    // Timestamps sort chronologically
+    backups.sort_by_key(|backup| std::cmp::Reverse(backup.name()));
+    Ok(backups)
+}
+
+/// Delete all but the newest MAX_BACKUPS backups
+pub fn prune_backups(config_path: &Path) -> Result<()> {
+    for backup in list_backups(config_path)?.into_iter().skip(MAX_BACKUPS) {
+        fs::remove_file(&backup.path)
+            .context(format!("Failed to remove {}", backup.path.display()))?;
+    }
     Ok(())
 }
 
@@ -104,14 +233,7 @@ fn migrate_from_version(config: &mut Value, from_version: u32) -> Result<()> {
         // Add more migrations here as needed:
         // 1 => migrate_v1_to_v2(config)?,
         // 2 => migrate_v2_to_v3(config)?,
-        _ => {
-            // Unknown version, skip
-            println!(
-                "{} Unknown config version {}, skipping migration",
-                "⚠".yellow(),
-                from_version
-            );
-        }
+        _ => anyhow::bail!("No migration from config version {}", from_version),
     }
 
     Ok(())
@@ -177,12 +299,9 @@ fn migrate_v0_to_v1(config: &mut Value) -> Result<()> {
     }
 
     // Ensure [defaults] section exists
+    // (empty: defaults are applied to every container, so none is assumed)
     if !table.contains_key("defaults") {
-        let mut defaults = toml::map::Map::new();
-        defaults.insert(
-            "timezone".to_string(),
-            Value::String("Asia/Tokyo".to_string()),
-        );
+        let defaults = toml::map::Map::new();
         table.insert("defaults".to_string(), Value::Table(defaults));
         println!("  {} Added [defaults] section", "+".green());
     }
@@ -209,6 +328,7 @@ mod tests {
 
         assert!(config.get("global").is_some());
         assert!(config.get("defaults").is_some());
+        assert!(config["defaults"].get("timezone").is_none());
         assert!(config.get("networks").is_some());
     }
 
@@ -233,4 +353,58 @@ caddy_network = "my-net"
         assert!(global.get("caddy_certs_dir").is_some());
         assert!(global.get("enable_https").is_some());
     }
+
+    #[test]
+    fn test_migrated_content() {
+        let migrated = migrated_content("[global]\ncaddy_network = \"my-net\"\n")
+            .unwrap()
+            .unwrap();
+        assert_eq!(config_version(&migrated).unwrap(), CONFIG_VERSION);
+        assert!(migrated.contains("caddy_network = \"my-net\""));
+
+        // Current files are left alone, newer files are refused
+        assert!(migrated_content(&migrated).unwrap().is_none());
+        let newer = format!("version = {}\n", CONFIG_VERSION + 1);
+        assert!(migrated_content(&newer).is_err());
+    }
+
+    #[test]
+    fn test_backups_are_listed_newest_first_and_pruned() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("config.toml");
+        fs::write(&config_path, "version = 1\n").unwrap();
+        fs::write(dir.path().join("other.toml.backup.20250101_000000"), "").unwrap();
+        for day in 1..=7 {
+            fs::write(
+                dir.path()
+                    .join(format!("config.toml.backup.202501{:02}_120000", day)),
+                "",
+            )
+            .unwrap();
+        }
+
+        let copy = backup_config(&config_path).unwrap();
+        assert_ne!(backup_config(&config_path).unwrap(), copy);
+        fs::remove_file(&copy).unwrap();
+
+        let backups = list_backups(&config_path).unwrap();
+        assert_eq!(backups.len(), 8);
+        assert!(backups[0].name().ends_with("-2"));
+        assert!(backups[0].created.is_some());
+        fs::remove_file(&backups[0].path).unwrap();
+
+        let backups = list_backups(&config_path).unwrap();
+        assert_eq!(backups.len(), 7);
+        assert_eq!(backups[0].name(), "config.toml.backup.20250107_120000");
+        assert_eq!(backups[0].version, Some(0));
+        assert!(backups[0].created.is_some());
+
+        prune_backups(&config_path).unwrap();
+        let backups = list_backups(&config_path).unwrap();
+        assert_eq!(backups.len(), MAX_BACKUPS);
+        assert_eq!(
+            backups.last().unwrap().name(),
+            "config.toml.backup.20250103_120000"
+        );
+    }
 }
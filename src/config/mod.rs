@@ -3,7 +3,10 @@
 //! This module handles the global configuration for oh-my-dockers,
 //! stored in ~/.oh-my-dockers/config.toml.
 
+pub mod commands;
+pub mod edit;
 mod migration;
+pub mod validate;
 
 use std::{
     collections::HashMap,
@@ -12,8 +15,11 @@ use std::{
 };
 
 use anyhow::{Context, Result};
+use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 
+use crate::proxy::ProxyKind;
+
 /// Current configuration version
 /// Increment this when making breaking changes to the config structure
 pub const CONFIG_VERSION: u32 = 1;
@@ -32,14 +38,23 @@ pub fn get_config_dir() -> Result<PathBuf> {
 }
 
 /// Ensure the configuration directory and all subdirectories exist
-pub fn ensure_config_dir() -> Result<PathBuf> {
+/// With `migrate`, an older config.toml is migrated to the current version
+pub fn ensure_config_dir(migrate: bool) -> Result<PathBuf> {
     let config_dir = get_config_dir()?;
 
     // Create main directory
     fs::create_dir_all(&config_dir).context("Failed to create config directory")?;
 
     // Create subdirectories
-    let subdirs = ["caddy", "caddy/certs", "caddy/projects"];
+    let subdirs = [
+        "caddy",
+        "caddy/certs",
+        "caddy/projects",
+        "traefik",
+        "traefik/dynamic",
+        "templates",
+        "compose",
+    ];
 
     for subdir in &subdirs {
         let dir_path = config_dir.join(subdir);
@@ -52,7 +67,7 @@ pub fn ensure_config_dir() -> Result<PathBuf> {
     if !config_file.exists() {
         // Create default config with current version
         create_default_config(&config_file)?;
-    } else {
+    } else if migrate {
         // Check if migration is needed
         migration::migrate_config_if_needed(&config_file)?;
     }
@@ -67,6 +82,9 @@ fn create_default_config(config_path: &Path) -> Result<()> {
 version = {}
 
 [global]
+# Reverse proxy backend: "caddy" (default) or "traefik"
+proxy_backend = "caddy"
+
 # Caddy network name
 caddy_network = "caddy-net"
 
@@ -79,9 +97,20 @@ caddy_certs_dir = "caddy/certs"
 # Set to true to enable HTTPS with self-signed certificates for local domains
 enable_https = true
 
+# Hosts file managed by omd (default: /etc/hosts)
+# Can also be set with the OH_MY_DOCKERS_HOSTS_FILE environment variable
+# hosts_file = "/etc/hosts"
+
+# Applied to every service of every project by 'omd project up'
+# (services keep the settings they define themselves)
 [defaults]
-# Default timezone
-timezone = "Asia/Tokyo"
+# Timezone passed to containers as TZ (default: unset, containers keep their own)
+# timezone = "Europe/Berlin"
+# restart = "unless-stopped"
+# env = {{ LOG_LEVEL = "debug" }}
+# labels = {{ "com.example.team" = "platform" }}
+# logging = {{ driver = "json-file", options = {{ max-size = "10m", max-file = "3" }} }}
+# limits = {{ cpus = 2.0, memory = "2g", pids = 512 }}
 
 # Network definitions
 # Networks are automatically created when running 'omd project up'
@@ -91,6 +120,53 @@ caddy-net = {{}}
 
 # You can define additional networks with custom settings:
 # my-network = {{ driver = "bridge", subnet = "172.20.0.0/16", gateway = "172.20.0.1" }}
+
+# Projects started and stopped together ('omd project up --group <name>')
+# [groups]
+# payments = ["api", "ledger", "gateway"]
+# With startup order: projects start once their dependencies are up
+# platform = {{ projects = ["db", "api", "web"], depends_on = {{ api = ["db"], web = ["api"] }} }}
+
+# Embedded DNS resolver for wildcard project domains ('omd dns serve')
+[dns]
+# Loopback address the resolver listens on (UDP and TCP)
+listen = "127.0.0.1:53535"
+
+# Upstream resolver for all other names (default: first nameserver in /etc/resolv.conf)
+# upstream = "1.1.1.1:53"
+
+# Caddy container created by 'omd caddy start'
+# After changing these, run 'omd caddy stop' and 'omd caddy start', then choose Reset
+[caddy]
+image = "caddy:latest"
+container_name = "oh-my-dockers-caddy"
+# Network to join (default: global.caddy_network)
+# network = "caddy-net"
+# Use 8080/8443 when 80/443 are taken or cannot be bound (high-port mode)
+high_ports = false
+# Or choose the ports (default: 80/443, or 8080/8443 with high_ports)
+# http_port = 8080
+# https_port = 8443
+# Host address of the admin API ("" to not publish it)
+admin = "127.0.0.1:2019"
+# Extra volumes and environment variables
+# volumes = ["/path/to/site:/srv/site:ro"]
+# env = {{ CADDY_LOG_LEVEL = "debug" }}
+
+# Traefik container created by 'omd proxy start' (proxy_backend = "traefik")
+# After changing these, run 'omd proxy restart'
+[traefik]
+image = "traefik:v3.1"
+# Network to join (default: global.caddy_network)
+# network = "caddy-net"
+# Use 8080/8443 when 80/443 are taken or cannot be bound (high-port mode)
+high_ports = false
+# Or choose the ports (default: 80/443, or 8080/8443 with high_ports)
+# http_port = 8080
+# https_port = 8443
+# Host address of the dashboard and API ("" to not publish them)
+dashboard = ""
+# dashboard = "127.0.0.1:8090"
 "#,
         CONFIG_VERSION
     );
@@ -111,6 +187,70 @@ pub struct GlobalConfig {
     pub defaults: DefaultSettings,
     #[serde(default)]
     pub networks: HashMap<String, NetworkDefinition>,
+    #[serde(default)]
+    pub dns: DnsSettings,
+    #[serde(default)]
+    pub caddy: CaddySettings,
+    #[serde(default)]
+    pub traefik: TraefikSettings,
+    /// Projects started and stopped together with `--group <name>`
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub groups: IndexMap<String, GroupDefinition>,
+}
+
+impl GlobalConfig {
+    /// HTTP and HTTPS ports the configured proxy backend is published on
+    pub fn proxy_ports(&self) -> (u16, u16) {
+        match self.global.proxy_backend {
+            ProxyKind::Caddy => (self.caddy.http_port(), self.caddy.https_port()),
+            ProxyKind::Traefik => (self.traefik.http_port(), self.traefik.https_port()),
+        }
+    }
+
+    /// URL of a project domain as seen from the browser
+    /// Includes the port when the proxy does not use 80/443
+    pub fn project_url(&self, domain: &str) -> String {
+        let (http_port, https_port) = self.proxy_ports();
+        let (scheme, port, default_port) = if self.global.enable_https {
+            ("https", https_port, 443)
+        } else {
+            ("http", http_port, 80)
+        };
+
+        if port != default_port {
+            format!("{}://{}:{}", scheme, domain, port)
+        } else {
+            format!("{}://{}", scheme, domain)
+        }
+    }
+
+    /// Listen port of generated Caddy sites, if it is not the default one
+    pub fn caddy_site_port(&self) -> Option<u16> {
+        if self.global.proxy_backend != ProxyKind::Caddy || !self.caddy.uses_custom_ports() {
+            return None;
+        }
+        Some(if self.global.enable_https {
+            self.caddy.https_port()
+        } else {
+            self.caddy.http_port()
+        })
+    }
+
+    /// Network the Caddy container joins ([caddy] network or global.caddy_network)
+    pub fn caddy_network(&self) -> &str {
+        self.caddy
+            .network
+            .as_deref()
+            .unwrap_or(&self.global.caddy_network)
+    }
+
+    /// Network the Traefik container joins ([traefik] network or global.caddy_network)
+    pub fn traefik_network(&self) -> &str {
+        self.traefik
+            .network
+            .as_deref()
+            .unwrap_or(&self.global.caddy_network)
+    }
 }
 
 fn default_version() -> u32 {
@@ -127,12 +267,272 @@ pub struct GlobalSettings {
     /// When true, uses 'tls internal' for automatic local certificates
     #[serde(default)]
     pub enable_https: bool,
+    /// Reverse proxy backend used for project routes (default: caddy)
+    #[serde(default)]
+    pub proxy_backend: ProxyKind,
+    /// Hosts file managed by omd (default: /etc/hosts)
+    /// The OH_MY_DOCKERS_HOSTS_FILE environment variable takes precedence
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hosts_file: Option<String>,
 }
 
-/// Default settings section
+/// Defaults applied to every service of every project
+/// They are written to a generated compose override at `omd project up`;
+/// settings a service defines itself are never replaced
 #[derive(Debug, Deserialize, Serialize, Default)]
 pub struct DefaultSettings {
+    /// Timezone, passed to containers as TZ
     pub timezone: Option<String>,
+    /// Environment variables
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub env: IndexMap<String, String>,
+    /// Container labels
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub labels: IndexMap<String, String>,
+    /// Restart policy: "no", "always", "on-failure[:N]" or "unless-stopped"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub restart: Option<String>,
+    /// Logging driver and options
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub logging: Option<LoggingDefaults>,
+    /// Resource limits
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limits: Option<LimitDefaults>,
+}
+
+/// Logging settings in [defaults.logging]
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct LoggingDefaults {
+    /// Logging driver, e.g. "json-file" or "local"
+    pub driver: String,
+    /// Driver options, e.g. max-size = "10m"
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub options: IndexMap<String, String>,
+}
+
+/// Resource limits in [defaults.limits]
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct LimitDefaults {
+    /// Number of CPUs, e.g. 1.5
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpus: Option<f64>,
+    /// Memory with unit, e.g. "512m" or "2g"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory: Option<String>,
+    /// Maximum number of processes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pids: Option<u32>,
+}
+
+/// A group of projects
+///   [groups]
+///   payments = ["api", "ledger", "gateway"]
+///
+///   [groups.platform]
+///   projects = ["db", "api", "web"]
+///   depends_on = { api = ["db"], web = ["api"] }
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum GroupDefinition {
+    /// Independent projects, all started at once
+    Projects(Vec<String>),
+    /// Projects with startup order
+    Ordered {
+        projects: Vec<String>,
+        /// Projects that must be up before a project starts
+        #[serde(default)]
+        depends_on: IndexMap<String, Vec<String>>,
+    },
+}
+
+impl GroupDefinition {
+    /// Member projects
+    pub fn projects(&self) -> &[String] {
+        match self {
+            GroupDefinition::Projects(projects) => projects,
+            GroupDefinition::Ordered { projects, .. } => projects,
+        }
+    }
+
+    /// Startup stages: projects of a stage only depend on earlier stages
+    /// and can start in parallel
+    pub fn stages(&self) -> Result<Vec<Vec<String>>> {
+        let projects = self.projects();
+        let depends_on = match self {
+            GroupDefinition::Projects(_) => return Ok(vec![projects.to_vec()]),
+            GroupDefinition::Ordered { depends_on, .. } => depends_on,
+        };
+
+        for (project, dependencies) in depends_on {
+            for name in std::iter::once(project).chain(dependencies) {
+                if !projects.contains(name) {
+                    anyhow::bail!(
+                        "'{}' in depends_on is not one of the group's projects",
+                        name
+                    );
+                }
+            }
+        }
+
+        let mut stages: Vec<Vec<String>> = Vec::new();
+        let mut remaining: Vec<&String> = projects.iter().collect();
+        while !remaining.is_empty() {
+            let started: Vec<&String> = stages.iter().flatten().collect();
+            let (ready, blocked): (Vec<&String>, Vec<&String>) =
+                remaining.into_iter().partition(|project| {
+                    depends_on
+                        .get(*project)
+                        .is_none_or(|deps| deps.iter().all(|dep| started.contains(&dep)))
+                });
+
+            if ready.is_empty() {
+                let names: Vec<&str> = blocked.iter().map(|name| name.as_str()).collect();
+                anyhow::bail!("depends_on has a cycle between {}", names.join(", "));
+            }
+            stages.push(ready.into_iter().cloned().collect());
+            remaining = blocked;
+        }
+
+        Ok(stages)
+    }
+}
+
+/// Embedded DNS resolver settings
+#[derive(Debug, Deserialize, Serialize)]
+pub struct DnsSettings {
+    /// Loopback address the resolver listens on (UDP and TCP)
+    #[serde(default = "default_dns_listen")]
+    pub listen: String,
+    /// Upstream resolver for non-project names
+    /// Defaults to the first nameserver in /etc/resolv.conf
+    pub upstream: Option<String>,
+}
+
+impl Default for DnsSettings {
+    fn default() -> Self {
+        Self {
+            listen: default_dns_listen(),
+            upstream: None,
+        }
+    }
+}
+
+fn default_dns_listen() -> String {
+    "127.0.0.1:53535".to_string()
+}
+
+/// Caddy container settings
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct CaddySettings {
+    /// Image and tag of the container
+    pub image: String,
+    /// Container name
+    pub container_name: String,
+    /// Network the container joins (defaults to global.caddy_network)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub network: Option<String>,
+    /// Publish on 8080/8443 instead of 80/443 (high-port mode)
+    pub high_ports: bool,
+    /// HTTP port (default: 80, or 8080 in high-port mode)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub http_port: Option<u16>,
+    /// HTTPS port, TCP and UDP (default: 443, or 8443 in high-port mode)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub https_port: Option<u16>,
+    /// Host address the admin API is published on ("" = not published)
+    pub admin: String,
+    /// Extra volumes as "source:target[:options]"
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub volumes: Vec<String>,
+    /// Extra environment variables
+    #[serde(skip_serializing_if = "IndexMap::is_empty")]
+    pub env: IndexMap<String, String>,
+}
+
+impl Default for CaddySettings {
+    fn default() -> Self {
+        Self {
+            image: "caddy:latest".to_string(),
+            container_name: "oh-my-dockers-caddy".to_string(),
+            network: None,
+            high_ports: false,
+            http_port: None,
+            https_port: None,
+            admin: "127.0.0.1:2019".to_string(),
+            volumes: Vec::new(),
+            env: IndexMap::new(),
+        }
+    }
+}
+
+impl CaddySettings {
+    /// HTTP port Caddy is published and listens on
+    pub fn http_port(&self) -> u16 {
+        self.http_port
+            .unwrap_or(if self.high_ports { 8080 } else { 80 })
+    }
+
+    /// HTTPS port Caddy is published and listens on
+    pub fn https_port(&self) -> u16 {
+        self.https_port
+            .unwrap_or(if self.high_ports { 8443 } else { 443 })
+    }
+
+    /// Whether Caddy uses other ports than 80 and 443
+    pub fn uses_custom_ports(&self) -> bool {
+        self.http_port() != 80 || self.https_port() != 443
+    }
+}
+
+/// Traefik container settings
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TraefikSettings {
+    /// Image and tag of the container
+    pub image: String,
+    /// Network the container joins (defaults to global.caddy_network)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub network: Option<String>,
+    /// Publish on 8080/8443 instead of 80/443 (high-port mode)
+    pub high_ports: bool,
+    /// HTTP port (default: 80, or 8080 in high-port mode)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub http_port: Option<u16>,
+    /// HTTPS port (default: 443, or 8443 in high-port mode)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub https_port: Option<u16>,
+    /// Host address the dashboard and API are published on ("" = not published)
+    pub dashboard: String,
+}
+
+impl Default for TraefikSettings {
+    fn default() -> Self {
+        Self {
+            image: "traefik:v3.1".to_string(),
+            network: None,
+            high_ports: false,
+            http_port: None,
+            https_port: None,
+            dashboard: String::new(),
+        }
+    }
+}
+
+impl TraefikSettings {
+    /// HTTP port Traefik is published and listens on
+    pub fn http_port(&self) -> u16 {
+        self.http_port
+            .unwrap_or(if self.high_ports { 8080 } else { 80 })
+    }
+
+    /// HTTPS port Traefik is published and listens on
+    pub fn https_port(&self) -> u16 {
+        self.https_port
+            .unwrap_or(if self.high_ports { 8443 } else { 443 })
+    }
 }
 
 /// Network definition for custom networks
@@ -154,3 +554,77 @@ pub fn load_global_config() -> Result<GlobalConfig> {
 
     Ok(config)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_group_stages() {
+        let config: GlobalConfig = toml::from_str(
+            r#"
+[global]
+caddy_network = "caddy-net"
+caddy_projects_dir = "caddy/projects"
+caddy_certs_dir = "caddy/certs"
+
+[groups]
+payments = ["api", "ledger", "gateway"]
+platform = { projects = ["web", "api", "db", "cache"], depends_on = { api = ["db"], web = ["api", "cache"] } }
+cycle = { projects = ["a", "b"], depends_on = { a = ["b"], b = ["a"] } }
+unknown = { projects = ["a"], depends_on = { a = ["b"] } }
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.groups["payments"].stages().unwrap(),
+            vec![vec!["api", "ledger", "gateway"]]
+        );
+        assert_eq!(
+            config.groups["platform"].stages().unwrap(),
+            vec![vec!["db", "cache"], vec!["api"], vec!["web"]]
+        );
+        assert!(config.groups["cycle"].stages().is_err());
+        assert!(config.groups["unknown"].stages().is_err());
+    }
+
+    #[test]
+    fn test_project_url() {
+        let url = |extra: &str| {
+            let config: GlobalConfig = toml::from_str(&format!(
+                "[global]\ncaddy_network = \"caddy-net\"\ncaddy_projects_dir = \"caddy/projects\"\ncaddy_certs_dir = \"caddy/certs\"\n{}",
+                extra
+            ))
+            .unwrap();
+            config.project_url("shop.local")
+        };
+
+        // Caddy (default backend)
+        assert_eq!(url("enable_https = true\n"), "https://shop.local");
+        assert_eq!(
+            url("[caddy]\nhigh_ports = true\n"),
+            "http://shop.local:8080"
+        );
+        assert_eq!(
+            url("enable_https = true\n[caddy]\nhttps_port = 9443\n[traefik]\nhttps_port = 443\n"),
+            "https://shop.local:9443"
+        );
+
+        // Traefik uses its own ports
+        let traefik = "proxy_backend = \"traefik\"\nenable_https = true\n";
+        assert_eq!(url(traefik), "https://shop.local");
+        assert_eq!(
+            url(&format!("{}[caddy]\nhigh_ports = true\n", traefik)),
+            "https://shop.local"
+        );
+        assert_eq!(
+            url(&format!("{}[traefik]\nhigh_ports = true\n", traefik)),
+            "https://shop.local:8443"
+        );
+        assert_eq!(
+            url("proxy_backend = \"traefik\"\n[traefik]\nhttp_port = 8000\n"),
+            "http://shop.local:8000"
+        );
+    }
+}
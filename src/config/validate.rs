@@ -0,0 +1,1238 @@
+//! Configuration validation (omd.toml and config.toml)
+//!
+//! Values that deserialize fine can still fail later inside Caddy or Docker,
+//! e.g. a domain with spaces or a route target without a port. This module
+//! checks them up front and reports each problem with its line and column and,
+//! where possible, a suggested fix.
+
+use std::{
+    fmt,
+    net::{IpAddr, SocketAddr},
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde::de::DeserializeOwned;
+use toml_edit::{Document, Item, Table, TableLike};
+
+use super::GlobalConfig;
+use crate::{
+    dns,
+    project::config::{PROJECT_CONFIG_VERSION, ProjectConfig},
+};
+
+/// TLDs on the HSTS preload list: browsers only open them over HTTPS
+const HSTS_PRELOADED_TLDS: &[&str] = &[
+    "app",
+    "bank",
+    "boo",
+    "chrome",
+    "dad",
+    "day",
+    "dev",
+    "esq",
+    "foo",
+    "gle",
+    "gmail",
+    "google",
+    "ing",
+    "insurance",
+    "meme",
+    "mov",
+    "new",
+    "nexus",
+    "page",
+    "phd",
+    "prof",
+    "rsvp",
+    "search",
+    "youtube",
+    "zip",
+];
+
+/// Top-level tables of omd.toml
+const PROJECT_SECTIONS: &[&str] = &[
+    "version",
+    "project",
+    "network",
+    "caddy",
+    "hosts",
+    "credentials",
+    "profiles",
+    "defaults",
+];
+/// Keys of the [project] table
+const PROJECT_KEYS: &[&str] = &["name", "path", "domain", "compose_file"];
+/// Keys of the [network] table
+const NETWORK_KEYS: &[&str] = &["name", "shared", "shared_services"];
+/// Top-level tables of config.toml
+const GLOBAL_SECTIONS: &[&str] = &[
+    "version", "global", "defaults", "networks", "dns", "caddy", "traefik", "groups",
+];
+/// Keys of the [global] table
+const GLOBAL_KEYS: &[&str] = &[
+    "caddy_network",
+    "caddy_projects_dir",
+    "caddy_certs_dir",
+    "enable_https",
+    "proxy_backend",
+    "hosts_file",
+];
+
+/// Keys of the [defaults] table of config.toml
+const DEFAULTS_KEYS: &[&str] = &["timezone", "env", "labels", "restart", "logging", "limits"];
+
+/// Keys of the [caddy] table of config.toml
+const CADDY_KEYS: &[&str] = &[
+    "image",
+    "container_name",
+    "network",
+    "high_ports",
+    "http_port",
+    "https_port",
+    "admin",
+    "volumes",
+    "env",
+];
+
+/// Keys of the [traefik] table of config.toml
+const TRAEFIK_KEYS: &[&str] = &[
+    "image",
+    "network",
+    "high_ports",
+    "http_port",
+    "https_port",
+    "dashboard",
+];
+
+/// Diagnostic severity
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// A problem found in a configuration file
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    /// Byte range in the file
+    pub span: Option<Range<usize>>,
+    pub suggestion: Option<String>,
+}
+
+/// Validation result of one file
+#[derive(Debug)]
+pub struct Report {
+    pub path: PathBuf,
+    content: String,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl Report {
+    fn new(path: &Path, content: String) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            content,
+            diagnostics: Vec::new(),
+        }
+    }
+
+    fn error(&mut self, span: Option<Range<usize>>, message: String, suggestion: Option<String>) {
+        self.push(Severity::Error, span, message, suggestion);
+    }
+
+    fn warning(&mut self, span: Option<Range<usize>>, message: String, suggestion: Option<String>) {
+        self.push(Severity::Warning, span, message, suggestion);
+    }
+
+    fn push(
+        &mut self,
+        severity: Severity,
+        span: Option<Range<usize>>,
+        message: String,
+        suggestion: Option<String>,
+    ) {
+        self.diagnostics.push(Diagnostic {
+            severity,
+            message,
+            span,
+            suggestion,
+        });
+    }
+
+    /// Number of errors
+    pub fn error_count(&self) -> usize {
+        self.diagnostics
+            .iter()
+            .filter(|d| d.severity == Severity::Error)
+            .count()
+    }
+
+    /// Number of warnings
+    pub fn warning_count(&self) -> usize {
+        self.diagnostics.len() - self.error_count()
+    }
+
+    /// 1-based line and column of a byte offset
+    pub fn line_col(&self, offset: usize) -> (usize, usize) {
+        let offset = offset.min(self.content.len());
+        let before = &self.content[..offset];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+        let column = self.content[line_start..offset].chars().count() + 1;
+        (line, column)
+    }
+
+    /// Print all diagnostics with the offending source line
+    pub fn print(&self) {
+        for diagnostic in &self.diagnostics {
+            println!("{}", self.render(diagnostic));
+        }
+    }
+
+    /// Render a diagnostic:
+    ///
+    /// ```text
+    /// error: network name "shop net" contains characters Docker does not allow
+    ///   --> omd.toml:6:8
+    ///    |
+    ///  6 | name = "shop net"
+    ///    |        ^^^^^^^^^^
+    ///    = help: use letters, digits, '_', '.' and '-', e.g. "shop-net"
+    /// ```
+    fn render(&self, diagnostic: &Diagnostic) -> String {
+        let label = match diagnostic.severity {
+            Severity::Error => diagnostic.severity.to_string().red().bold(),
+            Severity::Warning => diagnostic.severity.to_string().yellow().bold(),
+        };
+        let mut out = format!("{}: {}\n", label, diagnostic.message.bold());
+
+        match &diagnostic.span {
+            Some(span) => {
+                let (line, column) = self.line_col(span.start);
+                let number = line.to_string();
+                let gutter = " ".repeat(number.len());
+                let source = self.content.lines().nth(line - 1).unwrap_or_default();
+
+                // Underline up to the end of the first line of the span
+                let available = source.chars().count().saturating_sub(column - 1);
+                let width = self.content[span.clone()]
+                    .lines()
+                    .next()
+                    .map(|s| s.chars().count())
+                    .unwrap_or(0)
+                    .clamp(1, available.max(1));
+
+                out.push_str(&format!(
+                    "{}{} {}:{}:{}\n",
+                    gutter,
+                    "-->".blue(),
+                    self.path.display(),
+                    line,
+                    column
+                ));
+                out.push_str(&format!("{} {}\n", gutter, "|".blue()));
+                out.push_str(&format!("{} {} {}\n", number.blue(), "|".blue(), source));
+                out.push_str(&format!(
+                    "{} {} {}{}\n",
+                    gutter,
+                    "|".blue(),
+                    " ".repeat(column - 1),
+                    "^".repeat(width).red()
+                ));
+                if let Some(suggestion) = &diagnostic.suggestion {
+                    out.push_str(&format!("{} {} help: {}\n", gutter, "=".blue(), suggestion));
+                }
+            }
+            None => {
+                out.push_str(&format!("  {} {}\n", "-->".blue(), self.path.display()));
+                if let Some(suggestion) = &diagnostic.suggestion {
+                    out.push_str(&format!("  {} help: {}\n", "=".blue(), suggestion));
+                }
+            }
+        }
+
+        out
+    }
+}
+
+/// Validate an omd.toml file
+pub fn validate_project_file(path: &Path) -> Result<Report> {
+    let content =
+        std::fs::read_to_string(path).context(format!("Failed to read {}", path.display()))?;
+    Ok(validate_project(path, content))
+}
+
+/// Validate omd.toml content
+pub fn validate_project(path: &Path, content: String) -> Report {
+    let mut report = Report::new(path, content);
+    let Some((config, doc)) = parse::<ProjectConfig>(&mut report) else {
+        return report;
+    };
+    let root = doc.as_table();
+
+    check_unknown_keys(&mut report, root, PROJECT_SECTIONS, "");
+    if let Some(table) = root.get("project").and_then(Item::as_table_like) {
+        check_unknown_keys(&mut report, table, PROJECT_KEYS, "project.");
+    }
+    if let Some(table) = root.get("network").and_then(Item::as_table_like) {
+        check_unknown_keys(&mut report, table, NETWORK_KEYS, "network.");
+    }
+
+    // version
+    if config.version > PROJECT_CONFIG_VERSION {
+        report.warning(
+            value_span(root, &["version"]),
+            format!(
+                "format version {} is newer than this omd supports (version {})",
+                config.version, PROJECT_CONFIG_VERSION
+            ),
+            Some("update omd; settings it does not know may be ignored".to_string()),
+        );
+    } else if config.version < PROJECT_CONFIG_VERSION {
+        report.warning(
+            value_span(root, &["version"]),
+            format!(
+                "format version {} is older than the current version {}",
+                config.version, PROJECT_CONFIG_VERSION
+            ),
+            Some("run 'omd migrate' to upgrade the file".to_string()),
+        );
+    }
+
+    // [project]
+    if !is_valid_project_name(&config.project.name) {
+        report.error(
+            value_span(root, &["project", "name"]),
+            format!(
+                "project name \"{}\" is not a valid Docker Compose project name",
+                config.project.name
+            ),
+            Some(format!(
+                "use lowercase letters, digits, '-' and '_', e.g. \"{}\"",
+                suggest_name(&config.project.name)
+            )),
+        );
+    }
+
+    let domain_span = value_span(root, &["project", "domain"]);
+    check_domain(&mut report, &config.project.domain, domain_span);
+
+    let project_dir = path.parent().unwrap_or(Path::new("."));
+    let compose_path = project_dir.join(&config.project.compose_file);
+    if !compose_path.exists() {
+        report.warning(
+            value_span(root, &["project", "compose_file"]),
+            format!(
+                "compose file \"{}\" does not exist",
+                config.project.compose_file
+            ),
+            Some("create it, or generate one with 'omd init'".to_string()),
+        );
+    }
+
+    // [network]
+    if !is_valid_network_name(&config.network.name) {
+        report.error(
+            value_span(root, &["network", "name"]),
+            format!(
+                "network name \"{}\" contains characters Docker does not allow",
+                config.network.name
+            ),
+            Some(format!(
+                "use letters, digits, '_', '.' and '-', e.g. \"{}\"",
+                suggest_name(&config.network.name)
+            )),
+        );
+    }
+
+    let shared_items = root
+        .get("network")
+        .and_then(|network| network.get("shared"))
+        .and_then(Item::as_array);
+    for (i, shared) in config.network.shared.iter().enumerate() {
+        let span = shared_items
+            .and_then(|items| items.get(i))
+            .and_then(|item| item.span());
+        if !is_valid_network_name(shared) {
+            report.error(
+                span,
+                format!(
+                    "network name \"{}\" contains characters Docker does not allow",
+                    shared
+                ),
+                Some(format!(
+                    "use letters, digits, '_', '.' and '-', e.g. \"{}\"",
+                    suggest_name(shared)
+                )),
+            );
+        } else if *shared == config.network.name {
+            report.error(
+                span,
+                format!("\"{}\" is the project's own network", shared),
+                Some("list only networks shared with other projects".to_string()),
+            );
+        }
+    }
+    if config.network.shared.is_empty() && !config.network.shared_services.is_empty() {
+        report.warning(
+            value_span(root, &["network", "shared_services"]),
+            "shared_services has no effect without shared networks".to_string(),
+            Some("add the networks to 'shared', or remove 'shared_services'".to_string()),
+        );
+    }
+
+    // [hosts]
+    let alias_items = root
+        .get("hosts")
+        .and_then(|hosts| hosts.get("aliases"))
+        .and_then(Item::as_array);
+    for (i, alias) in config.hosts.aliases.iter().enumerate() {
+        let span = alias_items
+            .and_then(|items| items.get(i))
+            .and_then(|item| item.span());
+        if *alias == config.project.domain {
+            report.warning(
+                span,
+                format!("\"{}\" is the project domain", alias),
+                Some("list only extra host names".to_string()),
+            );
+        } else {
+            check_domain(&mut report, alias, span);
+        }
+    }
+
+    // [caddy.routes] in file order
+    if let Some(routes) = root
+        .get("caddy")
+        .and_then(|caddy| caddy.get("routes"))
+        .and_then(Item::as_table_like)
+    {
+        check_routes(&mut report, routes);
+    }
+
+    // [profiles.<name>]
+    for (name, profile) in &config.profiles {
+        if let Some(domain) = &profile.domain {
+            let span = value_span(root, &["profiles", name, "domain"]);
+            check_domain(&mut report, domain, span);
+        }
+
+        for file in &profile.compose_files {
+            if !project_dir.join(file).exists() {
+                report.warning(
+                    value_span(root, &["profiles", name, "compose_files"]),
+                    format!(
+                        "compose file \"{}\" of profile \"{}\" does not exist",
+                        file, name
+                    ),
+                    None,
+                );
+            }
+        }
+
+        if let Some(routes) = root
+            .get("profiles")
+            .and_then(|profiles| profiles.get(name))
+            .and_then(|profile| profile.get("routes"))
+            .and_then(Item::as_table_like)
+        {
+            check_routes(&mut report, routes);
+        }
+    }
+
+    report
+}
+
+/// Check the network and ports of a proxy container section ([caddy] or [traefik])
+fn check_proxy_container(
+    report: &mut Report,
+    root: &Table,
+    section: &str,
+    network: Option<&str>,
+    (http_port, https_port): (u16, u16),
+) {
+    if let Some(network) = network
+        && !is_valid_network_name(network)
+    {
+        report.error(
+            value_span(root, &[section, "network"]),
+            format!(
+                "network name \"{}\" contains characters Docker does not allow",
+                network
+            ),
+            Some(format!("e.g. \"{}\"", suggest_name(network))),
+        );
+    }
+    for (key, port) in [("http_port", http_port), ("https_port", https_port)] {
+        if port == 0 {
+            report.error(
+                value_span(root, &[section, key]),
+                format!("{} must be between 1 and 65535", key),
+                None,
+            );
+        }
+    }
+    if http_port == https_port {
+        report.error(
+            value_span(root, &[section, "https_port"]),
+            format!("http_port and https_port are both {}", https_port),
+            Some("publish HTTP and HTTPS on different ports".to_string()),
+        );
+    }
+}
+
+/// Check the entries of a routes table in file order
+fn check_routes(report: &mut Report, routes: &dyn TableLike) {
+    for (subdomain, item) in routes.iter() {
+        let key_span = routes.key(subdomain).and_then(|key| key.span());
+        check_route(
+            report,
+            subdomain,
+            item.as_str().unwrap_or_default(),
+            key_span,
+            item.span(),
+        );
+    }
+}
+
+/// Validate a config.toml file
+pub fn validate_global_file(path: &Path) -> Result<Report> {
+    let content =
+        std::fs::read_to_string(path).context(format!("Failed to read {}", path.display()))?;
+    Ok(validate_global(path, content))
+}
+
+/// Validate config.toml content
+pub fn validate_global(path: &Path, content: String) -> Report {
+    let mut report = Report::new(path, content);
+    let Some((config, doc)) = parse::<GlobalConfig>(&mut report) else {
+        return report;
+    };
+    let root = doc.as_table();
+
+    check_unknown_keys(&mut report, root, GLOBAL_SECTIONS, "");
+    if let Some(table) = root.get("global").and_then(Item::as_table_like) {
+        check_unknown_keys(&mut report, table, GLOBAL_KEYS, "global.");
+    }
+    if let Some(table) = root.get("caddy").and_then(Item::as_table_like) {
+        check_unknown_keys(&mut report, table, CADDY_KEYS, "caddy.");
+    }
+    if let Some(table) = root.get("traefik").and_then(Item::as_table_like) {
+        check_unknown_keys(&mut report, table, TRAEFIK_KEYS, "traefik.");
+    }
+    if let Some(table) = root.get("defaults").and_then(Item::as_table_like) {
+        check_unknown_keys(&mut report, table, DEFAULTS_KEYS, "defaults.");
+    }
+
+    if !is_valid_network_name(&config.global.caddy_network) {
+        report.error(
+            value_span(root, &["global", "caddy_network"]),
+            format!(
+                "network name \"{}\" contains characters Docker does not allow",
+                config.global.caddy_network
+            ),
+            Some(format!(
+                "use letters, digits, '_', '.' and '-', e.g. \"{}\"",
+                suggest_name(&config.global.caddy_network)
+            )),
+        );
+    }
+
+    // [networks]
+    for (name, network) in &config.networks {
+        if !is_valid_network_name(name) {
+            report.error(
+                key_span(root, &["networks"], name),
+                format!(
+                    "network name \"{}\" contains characters Docker does not allow",
+                    name
+                ),
+                Some(format!("e.g. \"{}\"", suggest_name(name))),
+            );
+        }
+        if let Some(subnet) = &network.subnet
+            && !is_valid_cidr(subnet)
+        {
+            report.error(
+                value_span(root, &["networks", name, "subnet"]),
+                format!("subnet \"{}\" is not in CIDR notation", subnet),
+                Some("use an address and prefix length, e.g. \"172.20.0.0/16\"".to_string()),
+            );
+        }
+        if let Some(gateway) = &network.gateway
+            && gateway.parse::<IpAddr>().is_err()
+        {
+            report.error(
+                value_span(root, &["networks", name, "gateway"]),
+                format!("gateway \"{}\" is not an IP address", gateway),
+                None,
+            );
+        }
+    }
+
+    // [dns]
+    match dns::parse_socket_addr(&config.dns.listen) {
+        Ok(listen) if !listen.ip().is_loopback() => report.warning(
+            value_span(root, &["dns", "listen"]),
+            format!(
+                "listen address \"{}\" is not a loopback address, other hosts can use the resolver",
+                config.dns.listen
+            ),
+            Some("e.g. \"127.0.0.1:53535\"".to_string()),
+        ),
+        Ok(_) => {}
+        Err(_) => report.error(
+            value_span(root, &["dns", "listen"]),
+            format!("listen address \"{}\" is not IP:PORT", config.dns.listen),
+            Some("e.g. \"127.0.0.1:53535\"".to_string()),
+        ),
+    }
+    if let Some(upstream) = &config.dns.upstream
+        && dns::parse_socket_addr(upstream).is_err()
+    {
+        report.error(
+            value_span(root, &["dns", "upstream"]),
+            format!("upstream \"{}\" is not an IP address", upstream),
+            Some("e.g. \"1.1.1.1:53\"".to_string()),
+        );
+    }
+
+    // [caddy]
+    let caddy = &config.caddy;
+    if !is_valid_network_name(&caddy.container_name) {
+        report.error(
+            value_span(root, &["caddy", "container_name"]),
+            format!(
+                "container name \"{}\" contains characters Docker does not allow",
+                caddy.container_name
+            ),
+            Some(format!("e.g. \"{}\"", suggest_name(&caddy.container_name))),
+        );
+    }
+    check_proxy_container(
+        &mut report,
+        root,
+        "caddy",
+        caddy.network.as_deref(),
+        (caddy.http_port(), caddy.https_port()),
+    );
+    if !caddy.admin.is_empty() && caddy.admin.parse::<SocketAddr>().is_err() {
+        report.error(
+            value_span(root, &["caddy", "admin"]),
+            format!("admin address \"{}\" is not IP:PORT", caddy.admin),
+            Some("e.g. \"127.0.0.1:2019\", or \"\" to not publish the admin API".to_string()),
+        );
+    }
+
+    // [traefik]
+    let traefik = &config.traefik;
+    let ports = (traefik.http_port(), traefik.https_port());
+    check_proxy_container(
+        &mut report,
+        root,
+        "traefik",
+        traefik.network.as_deref(),
+        ports,
+    );
+    if !traefik.dashboard.is_empty() {
+        match traefik.dashboard.parse::<SocketAddr>() {
+            Ok(address) if [ports.0, ports.1].contains(&address.port()) => report.error(
+                value_span(root, &["traefik", "dashboard"]),
+                format!(
+                    "dashboard port {} is already the HTTP or HTTPS port",
+                    address.port()
+                ),
+                Some("publish the dashboard on another port, e.g. \"127.0.0.1:8090\"".to_string()),
+            ),
+            Ok(_) => {}
+            Err(_) => report.error(
+                value_span(root, &["traefik", "dashboard"]),
+                format!("dashboard address \"{}\" is not IP:PORT", traefik.dashboard),
+                Some("e.g. \"127.0.0.1:8090\", or \"\" to not publish the dashboard".to_string()),
+            ),
+        }
+    }
+
+    // [groups]
+    for (name, group) in &config.groups {
+        if let Err(e) = group.stages() {
+            report.error(
+                key_span(root, &["groups"], name),
+                format!("group \"{}\": {}", name, e),
+                None,
+            );
+        }
+    }
+
+    // [defaults]
+    let zoneinfo = Path::new("/usr/share/zoneinfo");
+    if let Some(timezone) = &config.defaults.timezone
+        && zoneinfo.is_dir()
+        && !zoneinfo.join(timezone).is_file()
+    {
+        report.warning(
+            value_span(root, &["defaults", "timezone"]),
+            format!("unknown timezone \"{}\"", timezone),
+            Some("use an IANA name such as \"Europe/Berlin\" or \"UTC\"".to_string()),
+        );
+    }
+    if let Some(restart) = &config.defaults.restart
+        && !is_valid_restart_policy(restart)
+    {
+        report.error(
+            value_span(root, &["defaults", "restart"]),
+            format!("unknown restart policy \"{}\"", restart),
+            Some(
+                "use \"no\", \"always\", \"on-failure\", \"on-failure:3\" or \"unless-stopped\""
+                    .to_string(),
+            ),
+        );
+    }
+    if let Some(limits) = &config.defaults.limits {
+        if let Some(cpus) = limits.cpus
+            && cpus <= 0.0
+        {
+            report.error(
+                value_span(root, &["defaults", "limits", "cpus"]),
+                format!("cpus must be greater than 0, got {}", cpus),
+                None,
+            );
+        }
+        if let Some(memory) = &limits.memory
+            && !is_valid_memory(memory)
+        {
+            report.error(
+                value_span(root, &["defaults", "limits", "memory"]),
+                format!("memory \"{}\" is not a size", memory),
+                Some("use a number with a unit, e.g. \"512m\" or \"2g\"".to_string()),
+            );
+        }
+    }
+
+    report
+}
+
+/// Validate omd.toml and config.toml before starting a project
+/// Prints all diagnostics and fails if there are errors
+pub fn ensure_valid(project_path: &Path) -> Result<()> {
+    let mut reports = vec![validate_project_file(project_path)?];
+    let global_path = super::get_config_dir()?.join("config.toml");
+    if global_path.exists() {
+        reports.push(validate_global_file(&global_path)?);
+    }
+
+    let mut errors = 0;
+    for report in &reports {
+        report.print();
+        errors += report.error_count();
+    }
+
+    if errors > 0 {
+        anyhow::bail!(
+            "Configuration has {} error(s). Fix them and run the command again.",
+            errors
+        );
+    }
+
+    Ok(())
+}
+
+/// Deserialize the content and parse it for spans
+/// Syntax and type errors are reported and end the validation
+fn parse<T: DeserializeOwned>(report: &mut Report) -> Option<(T, Document<String>)> {
+    let config = match toml::from_str::<T>(&report.content) {
+        Ok(config) => config,
+        Err(e) => {
+            report.error(e.span(), e.message().trim().to_string(), None);
+            return None;
+        }
+    };
+
+    let doc = match Document::parse(report.content.clone()) {
+        Ok(doc) => doc,
+        Err(e) => {
+            report.error(e.span(), e.message().trim().to_string(), None);
+            return None;
+        }
+    };
+
+    Some((config, doc))
+}
+
+/// Restart policies of docker compose
+fn is_valid_restart_policy(policy: &str) -> bool {
+    match policy.split_once(':') {
+        Some(("on-failure", retries)) => retries.parse::<u32>().is_ok(),
+        Some(_) => false,
+        None => matches!(policy, "no" | "always" | "on-failure" | "unless-stopped"),
+    }
+}
+
+/// Memory sizes such as "512m", "2g" or "1073741824"
+fn is_valid_memory(memory: &str) -> bool {
+    let lower = memory.to_ascii_lowercase();
+    let digits = lower.strip_suffix(['b', 'k', 'm', 'g']).unwrap_or(&lower);
+    !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit())
+}
+
+/// Check a project domain
+fn check_domain(report: &mut Report, domain: &str, span: Option<Range<usize>>) {
+    if !is_valid_domain(domain) {
+        report.error(
+            span,
+            format!(
+                "domain \"{}\" is not a valid DNS name (lowercase letters, digits and '-' per label)",
+                domain
+            ),
+            Some(format!("use \"{}\"", suggest_domain(domain))),
+        );
+        return;
+    }
+
+    if let Some(tld) = domain.rsplit('.').next()
+        && HSTS_PRELOADED_TLDS.contains(&tld)
+    {
+        let base = domain.strip_suffix(tld).unwrap_or(domain);
+        report.warning(
+            span,
+            format!(
+                "\".{}\" is HSTS-preloaded: browsers only open it over HTTPS with a trusted certificate",
+                tld
+            ),
+            Some(format!("use a local TLD instead, e.g. \"{}test\"", base)),
+        );
+    }
+}
+
+/// Check a `[caddy.routes]` entry
+fn check_route(
+    report: &mut Report,
+    subdomain: &str,
+    target: &str,
+    key_span: Option<Range<usize>>,
+    value_span: Option<Range<usize>>,
+) {
+    if !is_valid_domain(subdomain) {
+        report.error(
+            key_span,
+            format!("route \"{}\" is not a valid DNS label", subdomain),
+            Some(format!("use \"{}\"", suggest_domain(subdomain))),
+        );
+    }
+
+    match target.rsplit_once(':') {
+        Some((host, port)) if !host.is_empty() && port.parse::<u16>().is_ok_and(|p| p > 0) => {}
+        Some((host, port)) if !host.is_empty() && !port.chars().all(|c| c.is_ascii_digit()) => {
+            // e.g. "http://app" or "app:http"
+            report.error(
+                value_span,
+                format!("route target \"{}\" has no valid port", target),
+                Some("use \"container:port\", e.g. \"bff:8080\"".to_string()),
+            );
+        }
+        Some(_) => report.error(
+            value_span,
+            format!("route target \"{}\" has an invalid port", target),
+            Some("ports range from 1 to 65535".to_string()),
+        ),
+        None => report.error(
+            value_span,
+            format!("route target \"{}\" has no port", target),
+            Some(format!("add the container port, e.g. \"{}:80\"", target)),
+        ),
+    }
+}
+
+/// Warn about keys of a table that omd does not know
+fn check_unknown_keys(report: &mut Report, table: &dyn TableLike, known: &[&str], prefix: &str) {
+    for (key, _) in table.iter() {
+        if known.contains(&key) {
+            continue;
+        }
+
+        let suggestion = closest(key, known).map(|k| format!("did you mean \"{}{}\"?", prefix, k));
+        report.warning(
+            table.key(key).and_then(|k| k.span()),
+            format!("unknown key \"{}{}\" is ignored", prefix, key),
+            suggestion,
+        );
+    }
+}
+
+/// Span of the value at a path of keys
+fn value_span(root: &Table, path: &[&str]) -> Option<Range<usize>> {
+    let mut item = root.get(path.first()?)?;
+    for key in &path[1..] {
+        item = item.get(key)?;
+    }
+    item.span()
+}
+
+/// Span of a key in the table at a path of keys
+fn key_span(root: &Table, path: &[&str], key: &str) -> Option<Range<usize>> {
+    let mut item = root.get(path.first()?)?;
+    for segment in &path[1..] {
+        item = item.get(segment)?;
+    }
+    item.as_table_like()?.key(key)?.span()
+}
+
+/// Docker Compose project names: lowercase letters, digits, '-' and '_'
+pub(crate) fn is_valid_project_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_lowercase() || c.is_ascii_digit())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '-' | '_'))
+}
+
+/// Docker network names: letters, digits, '_', '.' and '-'
+pub(crate) fn is_valid_network_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphanumeric())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'))
+}
+
+/// Lowercase DNS name: labels of letters, digits and inner '-'
+pub(crate) fn is_valid_domain(domain: &str) -> bool {
+    !domain.is_empty()
+        && domain.split('.').all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        })
+}
+
+/// IPv4 or IPv6 subnet in CIDR notation
+fn is_valid_cidr(value: &str) -> bool {
+    let Some((address, prefix)) = value.split_once('/') else {
+        return false;
+    };
+    let Ok(address) = address.parse::<IpAddr>() else {
+        return false;
+    };
+    let max = if address.is_ipv4() { 32 } else { 128 };
+    prefix.parse::<u8>().is_ok_and(|p| p <= max)
+}
+
+/// Turn a name into lowercase letters, digits and '-'
+pub(crate) fn suggest_name(name: &str) -> String {
+    let mut suggestion = String::new();
+    for c in name.trim().chars() {
+        if c.is_ascii_alphanumeric() {
+            suggestion.push(c.to_ascii_lowercase());
+        } else if !suggestion.ends_with('-') {
+            suggestion.push('-');
+        }
+    }
+    suggestion.trim_matches('-').to_string()
+}
+
+/// Turn each label of a domain into a valid DNS label
+pub(crate) fn suggest_domain(domain: &str) -> String {
+    domain
+        .split('.')
+        .map(suggest_name)
+        .filter(|label| !label.is_empty())
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Closest known key within an edit distance of 2
+fn closest<'a>(key: &str, known: &[&'a str]) -> Option<&'a str> {
+    known
+        .iter()
+        .map(|candidate| (edit_distance(key, candidate), *candidate))
+        .filter(|(distance, _)| *distance <= 2)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Levenshtein distance
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != *cb);
+            current.push(
+                (previous[j] + cost)
+                    .min(previous[j + 1] + 1)
+                    .min(current[j] + 1),
+            );
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project(content: &str) -> Report {
+        validate_project(Path::new("/nonexistent/omd.toml"), content.to_string())
+    }
+
+    fn messages(report: &Report) -> Vec<String> {
+        report
+            .diagnostics
+            .iter()
+            .map(|d| format!("{}: {}", d.severity, d.message))
+            .collect()
+    }
+
+    #[test]
+    fn test_valid_project() {
+        let report = project(
+            "version = 1\n\n[project]\nname = \"shop\"\ndomain = \"shop.local\"\n\n[network]\nname = \"shop-net\"\n\n[caddy.routes]\napi = \"bff:8080\"\n",
+        );
+
+        // Only the missing compose file is reported
+        assert_eq!(report.error_count(), 0);
+        assert_eq!(report.warning_count(), 1);
+    }
+
+    #[test]
+    fn test_invalid_values_have_spans_and_suggestions() {
+        let report = project(
+            "version = 1\n\n[project]\nname = \"Shop\"\ndomain = \"My Shop.local\"\n\n[network]\nname = \"shop net\"\n\n[caddy.routes]\nAPI = \"bff\"\nweb = \"web:http\"\n",
+        );
+
+        assert_eq!(report.error_count(), 6, "{:?}", messages(&report));
+
+        let domain = &report.diagnostics[1];
+        assert_eq!(report.line_col(domain.span.clone().unwrap().start), (5, 10));
+        assert_eq!(domain.suggestion.as_deref(), Some("use \"my-shop.local\""));
+
+        let route = report
+            .diagnostics
+            .iter()
+            .find(|d| d.message.contains("\"API\""))
+            .unwrap();
+        assert_eq!(report.line_col(route.span.clone().unwrap().start), (11, 1));
+
+        let target = report
+            .diagnostics
+            .iter()
+            .find(|d| d.message.contains("has no port"))
+            .unwrap();
+        assert_eq!(
+            target.suggestion.as_deref(),
+            Some("add the container port, e.g. \"bff:80\"")
+        );
+    }
+
+    #[test]
+    fn test_hsts_tld_and_unknown_keys() {
+        let report = project(
+            "[project]\nname = \"shop\"\ndomain = \"shop.dev\"\n\n[netwrok]\nname = \"shop-net\"\n",
+        );
+
+        // The misspelled section is a missing required field for serde
+        assert_eq!(report.error_count(), 1);
+        assert!(report.diagnostics[0].message.contains("network"));
+
+        let report = project(
+            "[project]\nname = \"shop\"\ndomain = \"shop.dev\"\n\n[network]\nname = \"shop-net\"\n\n[hots]\n",
+        );
+        let messages = messages(&report);
+        assert!(messages.iter().any(|m| m.contains("HSTS-preloaded")));
+        let unknown = report
+            .diagnostics
+            .iter()
+            .find(|d| d.message.contains("hots"))
+            .unwrap();
+        assert_eq!(
+            unknown.suggestion.as_deref(),
+            Some("did you mean \"hosts\"?")
+        );
+    }
+
+    #[test]
+    fn test_format_version() {
+        let content = "[project]\nname = \"shop\"\ndomain = \"shop.local\"\n\n[network]\nname = \"shop-net\"\n";
+
+        let report = project(content);
+        let messages = messages(&report);
+        assert!(messages.iter().any(|m| m.contains("format version 0")));
+
+        let report = project(&format!("version = 99\n{}", content));
+        let newer = report
+            .diagnostics
+            .iter()
+            .find(|d| d.message.contains("newer"))
+            .unwrap();
+        assert_eq!(report.line_col(newer.span.clone().unwrap().start), (1, 11));
+        assert_eq!(report.error_count(), 0);
+    }
+
+    #[test]
+    fn test_shared_networks() {
+        let report = project(
+            "version = 1\n\n[project]\nname = \"shop\"\ndomain = \"shop.local\"\n\n[network]\nname = \"shop-net\"\nshared = [\"infra-net\", \"shop-net\", \"bad net\"]\n",
+        );
+
+        assert_eq!(report.error_count(), 2, "{:?}", messages(&report));
+        let own = report
+            .diagnostics
+            .iter()
+            .find(|d| d.message.contains("own network"))
+            .unwrap();
+        assert_eq!(report.line_col(own.span.clone().unwrap().start), (9, 24));
+    }
+
+    #[test]
+    fn test_hosts_aliases() {
+        let report = project(
+            "version = 1\n\n[project]\nname = \"shop\"\ndomain = \"shop.local\"\n\n[network]\nname = \"shop-net\"\n\n[hosts]\naliases = [\"shop.test\", \"Shop Admin.test\", \"shop.local\"]\n",
+        );
+
+        assert_eq!(report.error_count(), 1, "{:?}", messages(&report));
+        let invalid = &report
+            .diagnostics
+            .iter()
+            .find(|d| d.message.contains("Shop Admin"))
+            .unwrap();
+        assert_eq!(
+            report.line_col(invalid.span.clone().unwrap().start),
+            (11, 25)
+        );
+        assert_eq!(
+            invalid.suggestion.as_deref(),
+            Some("use \"shop-admin.test\"")
+        );
+
+        let messages = messages(&report);
+        assert!(
+            messages
+                .iter()
+                .any(|m| m.contains("\"shop.local\" is the project domain"))
+        );
+    }
+
+    #[test]
+    fn test_syntax_error_span() {
+        let report = project("[project]\nname = \"shop\ndomain = \"shop.local\"\n");
+
+        assert_eq!(report.error_count(), 1);
+        let (line, _) = report.line_col(report.diagnostics[0].span.clone().unwrap().start);
+        assert_eq!(line, 2);
+    }
+
+    #[test]
+    fn test_global_config() {
+        let content = "version = 1\n\n[global]\ncaddy_network = \"caddy-net\"\ncaddy_projects_dir = \"caddy/projects\"\ncaddy_certs_dir = \"caddy/certs\"\nenable_http = true\n\n[networks]\nbad = { subnet = \"172.20.0.0\" }\n\n[dns]\nlisten = \"localhost\"\n";
+        let report = validate_global(Path::new("config.toml"), content.to_string());
+        let messages = messages(&report);
+
+        assert_eq!(report.error_count(), 2, "{:?}", messages);
+        assert!(messages.iter().any(|m| m.contains("CIDR")));
+        assert!(messages.iter().any(|m| m.contains("listen address")));
+        assert_eq!(
+            report.diagnostics[0].suggestion.as_deref(),
+            Some("did you mean \"global.enable_https\"?")
+        );
+
+        let content = content.replace("\"localhost\"", "\"0.0.0.0:53\"");
+        let report = validate_global(Path::new("config.toml"), content);
+        assert_eq!(report.error_count(), 1);
+        assert!(
+            report
+                .diagnostics
+                .iter()
+                .any(|d| d.message.contains("not a loopback"))
+        );
+    }
+
+    #[test]
+    fn test_caddy_settings() {
+        let content = "[global]\ncaddy_network = \"caddy-net\"\ncaddy_projects_dir = \"caddy/projects\"\ncaddy_certs_dir = \"caddy/certs\"\n\n[caddy]\nimage = \"caddy:2.10\"\nhttp_port = 8443\nhttps_port = 8443\nadmin = \"localhost:2019\"\n";
+        let report = validate_global(Path::new("config.toml"), content.to_string());
+        let messages = messages(&report);
+
+        assert_eq!(report.error_count(), 2, "{:?}", messages);
+        assert!(messages.iter().any(|m| m.contains("both 8443")));
+        assert!(messages.iter().any(|m| m.contains("admin address")));
+
+        // Unset keys keep their defaults
+        let config: GlobalConfig = toml::from_str(content).unwrap();
+        assert_eq!(config.caddy.container_name, "oh-my-dockers-caddy");
+        assert_eq!(config.caddy_network(), "caddy-net");
+    }
+
+    #[test]
+    fn test_traefik_settings() {
+        let content = "[global]\ncaddy_network = \"caddy-net\"\ncaddy_projects_dir = \"caddy/projects\"\ncaddy_certs_dir = \"caddy/certs\"\n\n[traefik]\ndashboard = \"8090\"\ninsecure = true\nhttps_port = 80\n";
+        let report = validate_global(Path::new("config.toml"), content.to_string());
+        let messages = messages(&report);
+
+        assert_eq!(report.error_count(), 2, "{:?}", messages);
+        assert!(messages.iter().any(|m| m.contains("dashboard address")));
+        assert!(messages.iter().any(|m| m.contains("both 80")));
+        assert!(messages.iter().any(|m| m.contains("traefik.insecure")));
+
+        // The dashboard is not published by default
+        let config: GlobalConfig =
+            toml::from_str(&content.replace("[traefik]", "[other]")).unwrap();
+        assert_eq!(config.traefik.dashboard, "");
+    }
+
+    #[test]
+    fn test_defaults() {
+        let content = "[global]\ncaddy_network = \"caddy-net\"\ncaddy_projects_dir = \"caddy/projects\"\ncaddy_certs_dir = \"caddy/certs\"\n\n[defaults]\nrestart = \"sometimes\"\nlimits = { cpus = 0.0, memory = \"2 GB\" }\nlogging = { driver = \"local\" }\nenvs = {}\n";
+        let report = validate_global(Path::new("config.toml"), content.to_string());
+        let messages = messages(&report);
+
+        assert_eq!(report.error_count(), 3, "{:?}", messages);
+        assert!(messages.iter().any(|m| m.contains("restart policy")));
+        assert!(messages.iter().any(|m| m.contains("cpus")));
+        assert!(messages.iter().any(|m| m.contains("not a size")));
+        assert!(messages.iter().any(|m| m.contains("defaults.envs")));
+
+        assert!(is_valid_restart_policy("on-failure:3"));
+
+        assert!(!is_valid_restart_policy("always:3"));
+        assert!(is_valid_memory("512M"));
+    }
+
+    #[test]
+    fn test_groups() {
+        let content = "[global]\ncaddy_network = \"caddy-net\"\ncaddy_projects_dir = \"caddy/projects\"\ncaddy_certs_dir = \"caddy/certs\"\n\n[groups]\nok = [\"a\", \"b\"]\nloop = { projects = [\"a\", \"b\"], depends_on = { a = [\"b\"], b = [\"a\"] } }\n";
+        let report = validate_global(Path::new("config.toml"), content.to_string());
+        assert_eq!(report.error_count(), 1, "{:?}", report.diagnostics);
+        assert_eq!(
+            report.line_col(report.diagnostics[0].span.clone().unwrap().start),
+            (8, 1)
+        );
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("netwrok", "network"), 2);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(closest("routs", &["routes", "hosts"]), Some("routes"));
+        assert_eq!(closest("zzz", &["routes"]), None);
+    }
+}
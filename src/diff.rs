@@ -0,0 +1,59 @@
+//! Unified diff rendering
+//!
+//! This module renders line-based diffs used to preview changes to files
+//! before they are written.
+
+use colored::Colorize;
+use similar::TextDiff;
+
+/// Render a unified diff between two texts
+/// Returns an empty string if both texts are identical
+pub fn unified_diff(old: &str, new: &str, old_label: &str, new_label: &str) -> String {
+    if old == new {
+        return String::new();
+    }
+
+    TextDiff::from_lines(old, new)
+        .unified_diff()
+        .context_radius(3)
+        .header(old_label, new_label)
+        .to_string()
+}
+
+/// Print a unified diff with colors
+pub fn print_diff(diff: &str) {
+    for line in diff.lines() {
+        if line.starts_with("+++") || line.starts_with("---") {
+            println!("  {}", line.bright_white());
+        } else if line.starts_with('+') {
+            println!("  {}", line.green());
+        } else if line.starts_with('-') {
+            println!("  {}", line.red());
+        } else if line.starts_with("@@") {
+            println!("  {}", line.cyan());
+        } else {
+            println!("  {}", line);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unified_diff() {
+        let old = "127.0.0.1 localhost\n127.0.0.1 old.local\n";
+        let new = "127.0.0.1 localhost\n127.0.0.1 new.local\n";
+
+        assert_eq!(
+            unified_diff(old, new, "current", "backup"),
+            "--- current\n+++ backup\n@@ -1,2 +1,2 @@\n 127.0.0.1 localhost\n-127.0.0.1 old.local\n+127.0.0.1 new.local\n"
+        );
+    }
+
+    #[test]
+    fn test_unified_diff_identical() {
+        assert_eq!(unified_diff("a\n", "a\n", "a", "b"), "");
+    }
+}
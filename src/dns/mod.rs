@@ -0,0 +1,316 @@
+//! Embedded local DNS resolver
+//!
+//! /etc/hosts cannot express wildcards, so this module provides a small
+//! resolver that answers `<domain>` and `*.<domain>` for every registered
+//! project with loopback addresses and forwards all other queries:
+//! - DNS wire format handling
+//! - UDP/TCP server
+//! - systemd-resolved / dnsmasq integration
+
+pub mod packet;
+pub mod server;
+pub mod setup;
+
+use std::{
+    fs,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::SystemTime,
+};
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+
+use crate::{config::load_global_config, project::registry::PortRegistry};
+
+/// Upstream used when none is configured and /etc/resolv.conf has none
+const FALLBACK_UPSTREAM: &str = "1.1.1.1:53";
+
+/// Get the domains and aliases of all registered projects
+pub fn project_domains() -> Result<Vec<String>> {
+    let registry = PortRegistry::load()?;
+    Ok(registry
+        .list_projects()
+        .iter()
+        .flat_map(|entry| std::iter::once(&entry.domain).chain(&entry.aliases))
+        .map(|domain| domain.to_ascii_lowercase())
+        .collect())
+}
+
+/// Domains and addresses of the registered projects
+///
+/// The registry is read again only when its file changes (modification time
+/// or size), not on every query.
+pub struct ProjectDomains {
+    path: PathBuf,
+    /// Modification time and size of the registry file that was loaded
+    stamp: Option<(SystemTime, u64)>,
+    /// Lowercase domain and aliases of every project, with its addresses
+    entries: Vec<(String, Vec<IpAddr>)>,
+}
+
+impl ProjectDomains {
+    /// Load the domains from a registry file
+    pub fn load(path: PathBuf) -> Result<Self> {
+        let mut domains = Self {
+            path,
+            stamp: None,
+            entries: Vec::new(),
+        };
+        domains.reload(file_stamp(&domains.path))?;
+        Ok(domains)
+    }
+
+    /// Read the registry again if its file changed since it was loaded
+    /// On errors (e.g. a registry that is being written) the previous domains stay
+    fn refresh(&mut self) -> Result<()> {
+        let stamp = file_stamp(&self.path);
+        if stamp == self.stamp {
+            return Ok(());
+        }
+        self.reload(stamp)
+    }
+
+    fn reload(&mut self, stamp: Option<(SystemTime, u64)>) -> Result<()> {
+        let registry = PortRegistry::load_from(&self.path)?;
+        self.entries = registry
+            .list_projects()
+            .iter()
+            .flat_map(|entry| {
+                std::iter::once(&entry.domain)
+                    .chain(&entry.aliases)
+                    .map(|domain| {
+                        (
+                            domain.trim_end_matches('.').to_ascii_lowercase(),
+                            entry.addresses.clone(),
+                        )
+                    })
+            })
+            .collect();
+        self.stamp = stamp;
+        Ok(())
+    }
+
+    /// Look up the addresses configured for a queried name
+    ///
+    /// Returns None if the name does not belong to a registered project. An
+    /// empty list means the project uses the default loopback addresses.
+    pub fn lookup(&mut self, name: &str) -> Option<Vec<IpAddr>> {
+        if let Err(e) = self.refresh() {
+            println!(
+                "{} Failed to reload the registry, using the previous domains: {:#}",
+                "⚠".yellow(),
+                e
+            );
+        }
+
+        let domains: Vec<String> = self.entries.iter().map(|(d, _)| d.clone()).collect();
+        let domain = match_project_domain(name, &domains)?;
+        self.entries
+            .iter()
+            .find(|(d, _)| d == domain)
+            .map(|(_, addresses)| addresses.clone())
+    }
+}
+
+/// Modification time and size of a file, None if it does not exist
+fn file_stamp(path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// Find the project domain that a queried name belongs to
+///
+/// Matches the domain itself and any of its subdomains.
+pub fn match_project_domain<'a>(name: &str, domains: &'a [String]) -> Option<&'a str> {
+    let name = name.trim_end_matches('.').to_ascii_lowercase();
+
+    domains
+        .iter()
+        .map(|domain| domain.trim_end_matches('.'))
+        .filter(|domain| {
+            name == *domain
+                || name
+                    .strip_suffix(domain)
+                    .is_some_and(|prefix| prefix.ends_with('.'))
+        })
+        // Prefer the most specific domain when projects are nested
+        .max_by_key(|domain| domain.len())
+}
+
+/// Parse a socket address, defaulting to port 53
+pub fn parse_socket_addr(value: &str) -> Result<SocketAddr> {
+    if let Ok(addr) = value.parse::<SocketAddr>() {
+        return Ok(addr);
+    }
+
+    let ip: IpAddr = value
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse()
+        .context(format!("Invalid address: {}", value))?;
+
+    Ok(SocketAddr::new(ip, 53))
+}
+
+/// Pick the first nameserver from /etc/resolv.conf that is not the resolver itself
+fn system_upstream(listen: SocketAddr) -> Option<SocketAddr> {
+    let content = fs::read_to_string("/etc/resolv.conf").ok()?;
+
+    content
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("nameserver"))
+        .filter_map(|value| value.trim().parse::<IpAddr>().ok())
+        .map(|ip| SocketAddr::new(ip, 53))
+        .find(|addr| *addr != listen)
+}
+
+/// Resolve the listen address from the CLI or config.toml
+pub fn listen_addr(listen: Option<&str>) -> Result<SocketAddr> {
+    match listen {
+        Some(listen) => parse_socket_addr(listen),
+        None => parse_socket_addr(&load_global_config()?.dns.listen),
+    }
+}
+
+/// Run the resolver in the foreground
+pub fn serve(listen: Option<&str>, upstream: Option<&str>) -> Result<()> {
+    let global_config = load_global_config()?;
+    let listen = listen_addr(listen)?;
+
+    let upstream = match upstream.or(global_config.dns.upstream.as_deref()) {
+        Some(upstream) => parse_socket_addr(upstream)?,
+        None => system_upstream(listen)
+            .unwrap_or_else(|| FALLBACK_UPSTREAM.parse().expect("valid fallback upstream")),
+    };
+
+    let domains = project_domains()?;
+
+    // Forwarding for anyone who can reach the port makes an open resolver
+    if !listen.ip().is_loopback() {
+        println!(
+            "{} {} is not a loopback address: other hosts can use the resolver, \
+            including to forward arbitrary queries to {}",
+            "⚠".yellow(),
+            listen,
+            upstream
+        );
+        println!();
+    }
+
+    println!("{}", "Starting omd DNS resolver...".blue());
+    println!();
+    println!(
+        "  Listening: {} (UDP/TCP)",
+        listen.to_string().bright_white()
+    );
+    println!("  Upstream:  {}", upstream);
+    if domains.is_empty() {
+        println!("  Domains:   {}", "none registered yet".yellow());
+    } else {
+        println!("  Domains:   {}", domains.join(", "));
+    }
+    println!();
+    println!(
+        "{} Project domains are reloaded when the registry changes",
+        "ℹ".blue()
+    );
+    println!("Press Ctrl+C to stop");
+
+    server::run(
+        listen,
+        server::Resolver {
+            upstream,
+            domains: Mutex::new(ProjectDomains::load(PortRegistry::get_registry_path()?)?),
+            ipv4: vec![Ipv4Addr::LOCALHOST],
+            ipv6: vec![Ipv6Addr::LOCALHOST],
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_match_project_domain() {
+        let domains = vec!["shop.local".to_string(), "admin.shop.local".to_string()];
+
+        assert_eq!(
+            match_project_domain("shop.local", &domains),
+            Some("shop.local")
+        );
+        assert_eq!(
+            match_project_domain("API.shop.local.", &domains),
+            Some("shop.local")
+        );
+        assert_eq!(
+            match_project_domain("x.admin.shop.local", &domains),
+            Some("admin.shop.local")
+        );
+        assert_eq!(match_project_domain("myshop.local", &domains), None);
+        assert_eq!(match_project_domain("example.com", &domains), None);
+    }
+
+    #[test]
+    fn test_project_domains_reload_on_change() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("registry.json");
+        let registry = |domain: &str, addresses: &str| {
+            format!(
+                r#"{{"projects": {{"shop": {{"name": "shop", "path": "/srv/shop", "domain": "{}", "network": "shop-net", "ports": [], "containers": [], "addresses": [{}]}}}}}}"#,
+                domain, addresses
+            )
+        };
+
+        // A missing registry has no domains
+        let mut domains = ProjectDomains::load(path.clone()).unwrap();
+        assert_eq!(domains.lookup("shop.local"), None);
+
+        fs::write(&path, registry("Shop.local", "")).unwrap();
+        assert_eq!(domains.lookup("api.shop.local."), Some(vec![]));
+
+        fs::write(&path, registry("shop.local", "\"192.168.1.20\"")).unwrap();
+        assert_eq!(
+            domains.lookup("shop.local"),
+            Some(vec!["192.168.1.20".parse().unwrap()])
+        );
+
+        // Aliases resolve to the project addresses
+        let with_alias = registry("shop.local", "\"192.168.1.20\"")
+            .replace(r#""addresses""#, r#""aliases": ["shop.test"], "addresses""#);
+        fs::write(&path, with_alias).unwrap();
+        assert_eq!(
+            domains.lookup("www.shop.test"),
+            Some(vec!["192.168.1.20".parse().unwrap()])
+        );
+
+        // A registry that cannot be parsed keeps the previous domains
+        fs::write(&path, "{").unwrap();
+        assert_eq!(
+            domains.lookup("shop.local"),
+            Some(vec!["192.168.1.20".parse().unwrap()])
+        );
+
+        fs::remove_file(&path).unwrap();
+        assert_eq!(domains.lookup("shop.local"), None);
+    }
+
+    #[test]
+    fn test_parse_socket_addr_defaults_to_port_53() {
+        assert_eq!(
+            parse_socket_addr("1.1.1.1").unwrap(),
+            "1.1.1.1:53".parse::<SocketAddr>().unwrap()
+        );
+        assert_eq!(
+            parse_socket_addr("[::1]").unwrap(),
+            "[::1]:53".parse::<SocketAddr>().unwrap()
+        );
+        assert_eq!(
+            parse_socket_addr("127.0.0.1:5353").unwrap(),
+            "127.0.0.1:5353".parse::<SocketAddr>().unwrap()
+        );
+        assert!(parse_socket_addr("not-an-ip").is_err());
+    }
+}
@@ -0,0 +1,277 @@
+//! Minimal DNS wire format handling
+//!
+//! This module parses incoming queries and builds responses for the
+//! embedded resolver. Only the parts of RFC 1035 needed to answer A/AAAA
+//! questions are implemented; everything else is forwarded untouched.
+
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use anyhow::Result;
+
+/// Size of the fixed DNS header
+const HEADER_LEN: usize = 12;
+
+/// Record type A (IPv4 address)
+pub const TYPE_A: u16 = 1;
+/// Record type AAAA (IPv6 address)
+pub const TYPE_AAAA: u16 = 28;
+/// Query type ANY
+pub const TYPE_ANY: u16 = 255;
+
+/// Record class IN
+const CLASS_IN: u16 = 1;
+
+/// Response code: server failure
+pub const RCODE_SERVFAIL: u16 = 2;
+/// Response code: not implemented
+pub const RCODE_NOTIMP: u16 = 4;
+
+/// TTL for local answers, kept short so registry changes show up quickly
+const LOCAL_TTL: u32 = 5;
+
+/// A parsed DNS query (first question only)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Query {
+    /// Transaction ID
+    pub id: u16,
+    /// Header flags of the query
+    pub flags: u16,
+    /// Queried name, lowercased and without trailing dot
+    pub name: String,
+    /// Query type
+    pub qtype: u16,
+    /// Query class
+    pub qclass: u16,
+    /// Offset of the first byte after the question section
+    pub question_end: usize,
+}
+
+impl Query {
+    /// Opcode of the query (0 = standard query)
+    pub fn opcode(&self) -> u16 {
+        (self.flags >> 11) & 0x0f
+    }
+}
+
+/// Parse the header and first question of a DNS message
+pub fn parse_query(message: &[u8]) -> Result<Query> {
+    if message.len() < HEADER_LEN {
+        anyhow::bail!("DNS message too short");
+    }
+
+    let id = read_u16(message, 0)?;
+    let flags = read_u16(message, 2)?;
+    let qdcount = read_u16(message, 4)?;
+
+    if flags & 0x8000 != 0 {
+        anyhow::bail!("DNS message is a response, not a query");
+    }
+    if qdcount == 0 {
+        anyhow::bail!("DNS query has no question");
+    }
+
+    let mut labels: Vec<String> = Vec::new();
+    let mut pos = HEADER_LEN;
+    loop {
+        let len = *message
+            .get(pos)
+            .ok_or_else(|| anyhow::anyhow!("Truncated DNS name"))? as usize;
+        pos += 1;
+
+        if len == 0 {
+            break;
+        }
+        if len & 0xc0 != 0 {
+            anyhow::bail!("Compressed names are not supported in questions");
+        }
+
+        let label = message
+            .get(pos..pos + len)
+            .ok_or_else(|| anyhow::anyhow!("Truncated DNS label"))?;
+        labels.push(String::from_utf8_lossy(label).to_ascii_lowercase());
+        pos += len;
+    }
+
+    let qtype = read_u16(message, pos)?;
+    let qclass = read_u16(message, pos + 2)?;
+
+    Ok(Query {
+        id,
+        flags,
+        name: labels.join("."),
+        qtype,
+        qclass,
+        question_end: pos + 4,
+    })
+}
+
+/// Build an authoritative answer for a locally resolved name
+///
+/// Only addresses matching the query type are included; a name without
+/// matching addresses gets an empty NOERROR answer.
+pub fn build_local_response(
+    message: &[u8],
+    query: &Query,
+    ipv4: &[Ipv4Addr],
+    ipv6: &[Ipv6Addr],
+) -> Vec<u8> {
+    let mut answers: Vec<(u16, Vec<u8>)> = Vec::new();
+
+    if query.qclass == CLASS_IN {
+        if matches!(query.qtype, TYPE_A | TYPE_ANY) {
+            answers.extend(ipv4.iter().map(|ip| (TYPE_A, ip.octets().to_vec())));
+        }
+        if matches!(query.qtype, TYPE_AAAA | TYPE_ANY) {
+            answers.extend(ipv6.iter().map(|ip| (TYPE_AAAA, ip.octets().to_vec())));
+        }
+    }
+
+    let mut response = response_header(message, query, 0, answers.len() as u16);
+
+    for (rtype, rdata) in answers {
+        // Pointer to the name in the question section
+        response.extend_from_slice(&[0xc0, HEADER_LEN as u8]);
+        response.extend_from_slice(&rtype.to_be_bytes());
+        response.extend_from_slice(&CLASS_IN.to_be_bytes());
+        response.extend_from_slice(&LOCAL_TTL.to_be_bytes());
+        response.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+        response.extend_from_slice(&rdata);
+    }
+
+    response
+}
+
+/// Build an empty response carrying only a response code
+pub fn build_error_response(message: &[u8], query: &Query, rcode: u16) -> Vec<u8> {
+    response_header(message, query, rcode, 0)
+}
+
+/// Copy header and question of a query and turn it into a response
+fn response_header(message: &[u8], query: &Query, rcode: u16, ancount: u16) -> Vec<u8> {
+    let flags = 0x8000 // QR: response
+        | (query.flags & 0x7800) // opcode
+        | 0x0400 // AA: authoritative
+        | (query.flags & 0x0100) // RD: copied from the query
+        | 0x0080 // RA: recursion available (through the upstream)
+        | (rcode & 0x000f);
+
+    let mut response = Vec::with_capacity(query.question_end + 64);
+    response.extend_from_slice(&query.id.to_be_bytes());
+    response.extend_from_slice(&flags.to_be_bytes());
+    response.extend_from_slice(&1u16.to_be_bytes()); // QDCOUNT
+    response.extend_from_slice(&ancount.to_be_bytes());
+    response.extend_from_slice(&0u16.to_be_bytes()); // NSCOUNT
+    response.extend_from_slice(&0u16.to_be_bytes()); // ARCOUNT
+    response.extend_from_slice(&message[HEADER_LEN..query.question_end]);
+    response
+}
+
+fn read_u16(message: &[u8], offset: usize) -> Result<u16> {
+    let bytes = message
+        .get(offset..offset + 2)
+        .ok_or_else(|| anyhow::anyhow!("Truncated DNS message"))?;
+    Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build a standard recursive query for a name
+    fn query_message(name: &str, qtype: u16) -> Vec<u8> {
+        let mut message = vec![0x12, 0x34, 0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0];
+        for label in name.split('.') {
+            message.push(label.len() as u8);
+            message.extend_from_slice(label.as_bytes());
+        }
+        message.push(0);
+        message.extend_from_slice(&qtype.to_be_bytes());
+        message.extend_from_slice(&CLASS_IN.to_be_bytes());
+        message
+    }
+
+    #[test]
+    fn test_parse_query() {
+        let message = query_message("API.Shop.local", TYPE_AAAA);
+        let query = parse_query(&message).unwrap();
+
+        assert_eq!(query.id, 0x1234);
+        assert_eq!(query.name, "api.shop.local");
+        assert_eq!(query.qtype, TYPE_AAAA);
+        assert_eq!(query.opcode(), 0);
+        assert_eq!(query.question_end, message.len());
+    }
+
+    #[test]
+    fn test_parse_query_rejects_truncated_message() {
+        let message = query_message("shop.local", TYPE_A);
+        assert!(parse_query(&message[..message.len() - 3]).is_err());
+        assert!(parse_query(&message[..5]).is_err());
+    }
+
+    #[test]
+    fn test_build_local_response_a_record() {
+        let message = query_message("api.shop.local", TYPE_A);
+        let query = parse_query(&message).unwrap();
+
+        let response = build_local_response(
+            &message,
+            &query,
+            &[Ipv4Addr::LOCALHOST],
+            &[Ipv6Addr::LOCALHOST],
+        );
+
+        // Header: same ID, QR + AA + RD + RA, one question, one answer
+        assert_eq!(&response[0..2], &[0x12, 0x34]);
+        assert_eq!(read_u16(&response, 2).unwrap(), 0x8580);
+        assert_eq!(read_u16(&response, 6).unwrap(), 1);
+
+        // Answer: name pointer, type A, class IN, TTL, 4 bytes of address
+        let answer = &response[message.len()..];
+        assert_eq!(&answer[0..2], &[0xc0, 0x0c]);
+        assert_eq!(read_u16(answer, 2).unwrap(), TYPE_A);
+        assert_eq!(read_u16(answer, 10).unwrap(), 4);
+        assert_eq!(&answer[12..16], &[127, 0, 0, 1]);
+        assert_eq!(answer.len(), 16);
+    }
+
+    #[test]
+    fn test_build_local_response_any_returns_both_families() {
+        let message = query_message("shop.local", TYPE_ANY);
+        let query = parse_query(&message).unwrap();
+
+        let response = build_local_response(
+            &message,
+            &query,
+            &[Ipv4Addr::LOCALHOST],
+            &[Ipv6Addr::LOCALHOST],
+        );
+
+        assert_eq!(read_u16(&response, 6).unwrap(), 2);
+        assert_eq!(response.len(), message.len() + 16 + 28);
+    }
+
+    #[test]
+    fn test_build_local_response_other_type_is_empty() {
+        // MX query for a local name: NOERROR without answers
+        let message = query_message("shop.local", 15);
+        let query = parse_query(&message).unwrap();
+
+        let response = build_local_response(&message, &query, &[Ipv4Addr::LOCALHOST], &[]);
+
+        assert_eq!(read_u16(&response, 2).unwrap() & 0x000f, 0);
+        assert_eq!(read_u16(&response, 6).unwrap(), 0);
+        assert_eq!(response.len(), message.len());
+    }
+
+    #[test]
+    fn test_build_error_response() {
+        let message = query_message("shop.local", TYPE_A);
+        let query = parse_query(&message).unwrap();
+
+        let response = build_error_response(&message, &query, RCODE_SERVFAIL);
+
+        assert_eq!(read_u16(&response, 2).unwrap() & 0x000f, RCODE_SERVFAIL);
+        assert_eq!(read_u16(&response, 6).unwrap(), 0);
+    }
+}
@@ -0,0 +1,240 @@
+//! UDP/TCP server for the embedded resolver
+//!
+//! Queries for project domains are answered locally, everything else is
+//! forwarded to the upstream resolver.
+
+use std::{
+    io::{Read, Write},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpListener, TcpStream, UdpSocket},
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+
+use super::{
+    ProjectDomains,
+    packet::{
+        Query, RCODE_NOTIMP, RCODE_SERVFAIL, build_error_response, build_local_response,
+        parse_query,
+    },
+};
+
+/// Timeout for upstream queries
+const UPSTREAM_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Maximum size of a DNS message accepted over UDP
+const MAX_UDP_MESSAGE: usize = 4096;
+
+/// Threads answering UDP queries; forwarded queries can block one for up to
+/// `UPSTREAM_TIMEOUT`
+const UDP_WORKERS: usize = 4;
+
+/// Threads serving TCP connections, one connection at a time each
+const TCP_WORKERS: usize = 2;
+
+/// Idle timeout of a TCP connection
+const TCP_IDLE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Resolves queries against the registry and an upstream server
+pub struct Resolver {
+    /// Upstream resolver for non-project names
+    pub upstream: SocketAddr,
+    /// IPv4 addresses returned for projects without configured addresses
+    pub ipv4: Vec<Ipv4Addr>,
+    /// IPv6 addresses returned for projects without configured addresses
+    pub ipv6: Vec<Ipv6Addr>,
+    /// Registered project domains
+    pub domains: Mutex<ProjectDomains>,
+}
+
+impl Resolver {
+    /// Handle a raw DNS message and produce the raw response
+    /// Returns None for messages that cannot be parsed as a query
+    pub fn handle(&self, message: &[u8], over_tcp: bool) -> Option<Vec<u8>> {
+        let query = parse_query(message).ok()?;
+
+        if query.opcode() != 0 {
+            return Some(build_error_response(message, &query, RCODE_NOTIMP));
+        }
+
+        let addresses = self
+            .domains
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .lookup(&query.name);
+        if let Some(addresses) = addresses {
+            if addresses.is_empty() {
+                return Some(build_local_response(
+                    message, &query, &self.ipv4, &self.ipv6,
+                ));
+            }
+
+            let (ipv4, ipv6) = split_addresses(&addresses);
+            return Some(build_local_response(message, &query, &ipv4, &ipv6));
+        }
+
+        match self.forward(message, over_tcp) {
+            Ok(response) => Some(response),
+            Err(e) => {
+                println!(
+                    "{} Upstream query for {} failed: {}",
+                    "⚠".yellow(),
+                    query.name,
+                    e
+                );
+                Some(servfail(message, &query))
+            }
+        }
+    }
+
+    /// Forward a query to the upstream resolver using the same transport
+    fn forward(&self, message: &[u8], over_tcp: bool) -> Result<Vec<u8>> {
+        if over_tcp {
+            let mut stream = TcpStream::connect_timeout(&self.upstream, UPSTREAM_TIMEOUT)
+                .context("Failed to connect to upstream")?;
+            stream.set_read_timeout(Some(UPSTREAM_TIMEOUT))?;
+            write_tcp_message(&mut stream, message)?;
+            read_tcp_message(&mut stream)?.context("Upstream closed the connection")
+        } else {
+            let bind_addr: SocketAddr = if self.upstream.is_ipv4() {
+                (Ipv4Addr::UNSPECIFIED, 0).into()
+            } else {
+                (Ipv6Addr::UNSPECIFIED, 0).into()
+            };
+            let socket = UdpSocket::bind(bind_addr).context("Failed to bind upstream socket")?;
+            socket.set_read_timeout(Some(UPSTREAM_TIMEOUT))?;
+            socket.connect(self.upstream)?;
+            socket.send(message)?;
+
+            let mut buf = vec![0u8; MAX_UDP_MESSAGE];
+            let len = socket.recv(&mut buf).context("No answer from upstream")?;
+            buf.truncate(len);
+            Ok(buf)
+        }
+    }
+}
+
+/// Split configured addresses by family
+fn split_addresses(addresses: &[IpAddr]) -> (Vec<Ipv4Addr>, Vec<Ipv6Addr>) {
+    let mut ipv4 = Vec::new();
+    let mut ipv6 = Vec::new();
+    for address in addresses {
+        match address {
+            IpAddr::V4(ip) => ipv4.push(*ip),
+            IpAddr::V6(ip) => ipv6.push(*ip),
+        }
+    }
+    (ipv4, ipv6)
+}
+
+fn servfail(message: &[u8], query: &Query) -> Vec<u8> {
+    build_error_response(message, query, RCODE_SERVFAIL)
+}
+
+/// Run the UDP and TCP listeners until the process is terminated
+///
+/// A fixed number of worker threads share the sockets, so a burst of queries
+/// waits in the socket buffers instead of starting a thread per query.
+pub fn run(listen: SocketAddr, resolver: Resolver) -> Result<()> {
+    let resolver = Arc::new(resolver);
+
+    let udp_socket =
+        UdpSocket::bind(listen).context(format!("Failed to bind UDP socket on {}", listen))?;
+    let tcp_listener =
+        TcpListener::bind(listen).context(format!("Failed to bind TCP socket on {}", listen))?;
+
+    let mut workers = Vec::new();
+    for _ in 0..TCP_WORKERS {
+        let listener = tcp_listener
+            .try_clone()
+            .context("Failed to clone TCP listener")?;
+        let resolver = Arc::clone(&resolver);
+        workers.push(thread::spawn(move || serve_tcp(&listener, &resolver)));
+    }
+    for _ in 0..UDP_WORKERS {
+        let socket = udp_socket
+            .try_clone()
+            .context("Failed to clone UDP socket")?;
+        let resolver = Arc::clone(&resolver);
+        workers.push(thread::spawn(move || serve_udp(&socket, &resolver)));
+    }
+
+    for worker in workers {
+        let _ = worker.join();
+    }
+    Ok(())
+}
+
+/// Answer UDP queries one after another
+fn serve_udp(socket: &UdpSocket, resolver: &Resolver) {
+    let mut buf = vec![0u8; MAX_UDP_MESSAGE];
+    loop {
+        let (len, peer) = match socket.recv_from(&mut buf) {
+            Ok(received) => received,
+            Err(e) => {
+                println!("{} UDP receive error: {}", "⚠".yellow(), e);
+                continue;
+            }
+        };
+
+        if let Some(response) = resolver.handle(&buf[..len], false) {
+            let _ = socket.send_to(&response, peer);
+        }
+    }
+}
+
+/// Accept TCP connections and serve them one after another
+fn serve_tcp(listener: &TcpListener, resolver: &Resolver) {
+    loop {
+        let stream = match listener.accept() {
+            Ok((stream, _)) => stream,
+            Err(e) => {
+                println!("{} TCP accept error: {}", "⚠".yellow(), e);
+                continue;
+            }
+        };
+
+        if let Err(e) = serve_tcp_connection(stream, resolver) {
+            println!("{} TCP connection error: {}", "⚠".yellow(), e);
+        }
+    }
+}
+
+/// Serve length-prefixed queries on a TCP connection
+fn serve_tcp_connection(mut stream: TcpStream, resolver: &Resolver) -> Result<()> {
+    stream.set_read_timeout(Some(TCP_IDLE_TIMEOUT))?;
+
+    while let Some(message) = read_tcp_message(&mut stream)? {
+        match resolver.handle(&message, true) {
+            Some(response) => write_tcp_message(&mut stream, &response)?,
+            None => break,
+        }
+    }
+
+    Ok(())
+}
+
+/// Read a length-prefixed DNS message, returns None on EOF
+fn read_tcp_message(stream: &mut TcpStream) -> Result<Option<Vec<u8>>> {
+    let mut len_buf = [0u8; 2];
+    if let Err(e) = stream.read_exact(&mut len_buf) {
+        if e.kind() == std::io::ErrorKind::UnexpectedEof {
+            return Ok(None);
+        }
+        return Err(e.into());
+    }
+
+    let mut message = vec![0u8; u16::from_be_bytes(len_buf) as usize];
+    stream.read_exact(&mut message)?;
+    Ok(Some(message))
+}
+
+/// Write a length-prefixed DNS message
+fn write_tcp_message(stream: &mut TcpStream, message: &[u8]) -> Result<()> {
+    stream.write_all(&(message.len() as u16).to_be_bytes())?;
+    stream.write_all(message)?;
+    Ok(())
+}
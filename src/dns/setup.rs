@@ -0,0 +1,195 @@
+//! System resolver integration
+//!
+//! This module writes systemd-resolved or dnsmasq drop-ins that delegate the
+//! TLDs of registered projects to the embedded resolver.
+
+use std::{
+    io::{self, Write},
+    net::SocketAddr,
+    path::Path,
+};
+
+use anyhow::Result;
+use clap::ValueEnum;
+use colored::Colorize;
+
+use super::project_domains;
+use crate::system::privileged;
+
+/// Drop-in location for systemd-resolved
+const RESOLVED_DROPIN: &str = "/etc/systemd/resolved.conf.d/oh-my-dockers.conf";
+
+/// Drop-in location for dnsmasq
+const DNSMASQ_DROPIN: &str = "/etc/dnsmasq.d/oh-my-dockers.conf";
+
+/// Supported system resolvers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ResolverKind {
+    /// systemd-resolved (resolved.conf.d drop-in)
+    SystemdResolved,
+    /// dnsmasq (dnsmasq.d drop-in)
+    Dnsmasq,
+}
+
+impl ResolverKind {
+    /// Path of the drop-in file
+    fn dropin_path(self) -> &'static Path {
+        match self {
+            ResolverKind::SystemdResolved => Path::new(RESOLVED_DROPIN),
+            ResolverKind::Dnsmasq => Path::new(DNSMASQ_DROPIN),
+        }
+    }
+
+    /// Command that applies the drop-in
+    fn restart_command(self) -> &'static str {
+        match self {
+            ResolverKind::SystemdResolved => "sudo systemctl restart systemd-resolved",
+            ResolverKind::Dnsmasq => "sudo systemctl restart dnsmasq",
+        }
+    }
+}
+
+/// Extract the sorted, deduplicated TLDs of project domains
+pub fn project_tlds(domains: &[String]) -> Vec<String> {
+    let mut tlds: Vec<String> = domains
+        .iter()
+        .filter_map(|domain| domain.trim_end_matches('.').rsplit('.').next())
+        .filter(|tld| !tld.is_empty())
+        .map(|tld| tld.to_ascii_lowercase())
+        .collect();
+    tlds.sort();
+    tlds.dedup();
+    tlds
+}
+
+/// Render the drop-in content for a resolver
+pub fn render_dropin(kind: ResolverKind, listen: SocketAddr, tlds: &[String]) -> String {
+    let mut content =
+        String::from("# Managed by oh-my-dockers - delegates project TLDs to 'omd dns serve'\n");
+
+    match kind {
+        ResolverKind::SystemdResolved => {
+            let routing_domains: Vec<String> = tlds.iter().map(|tld| format!("~{}", tld)).collect();
+            content.push_str("[Resolve]\n");
+            content.push_str(&format!("DNS={}\n", listen));
+            content.push_str(&format!("Domains={}\n", routing_domains.join(" ")));
+        }
+        ResolverKind::Dnsmasq => {
+            for tld in tlds {
+                content.push_str(&format!(
+                    "server=/{}/{}#{}\n",
+                    tld,
+                    listen.ip(),
+                    listen.port()
+                ));
+            }
+        }
+    }
+
+    content
+}
+
+/// Write the drop-in for a system resolver
+pub fn install(kind: ResolverKind, listen: SocketAddr, dry_run: bool) -> Result<()> {
+    let tlds = project_tlds(&project_domains()?);
+
+    if tlds.is_empty() {
+        anyhow::bail!("No registered projects found. Run 'omd project up' first.");
+    }
+
+    let path = kind.dropin_path();
+    let content = render_dropin(kind, listen, &tlds);
+
+    println!();
+    println!("{} Drop-in {}:", "ℹ".blue(), path.display());
+    println!();
+    for line in content.lines() {
+        println!("  {}", line);
+    }
+    println!();
+
+    if dry_run {
+        return Ok(());
+    }
+
+    print!("{} Write this file? [Y/n]: ", "?".bright_yellow());
+    io::stdout().flush()?;
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+
+    let trimmed = input.trim();
+    if trimmed.eq_ignore_ascii_case("n") || trimmed.eq_ignore_ascii_case("no") {
+        println!("{} Changes cancelled", "ℹ".blue());
+        return Ok(());
+    }
+
+    privileged::write_file(path, &content)?;
+
+    println!("{} Wrote {}", "✓".green(), path.display());
+    println!();
+    println!("Apply it with: {}", kind.restart_command().bright_white());
+    println!("Re-run this command after registering projects with a new TLD.");
+
+    Ok(())
+}
+
+/// Remove the drop-in for a system resolver
+pub fn uninstall(kind: ResolverKind) -> Result<()> {
+    let path = kind.dropin_path();
+
+    if !path.exists() {
+        println!("{} {} does not exist", "ℹ".blue(), path.display());
+        return Ok(());
+    }
+
+    privileged::remove_file(path)?;
+
+    println!("{} Removed {}", "✓".green(), path.display());
+    println!();
+    println!("Apply it with: {}", kind.restart_command().bright_white());
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_project_tlds() {
+        let domains = vec![
+            "shop.local".to_string(),
+            "api.Test".to_string(),
+            "blog.local".to_string(),
+        ];
+
+        assert_eq!(project_tlds(&domains), vec!["local", "test"]);
+    }
+
+    #[test]
+    fn test_render_dropin_systemd_resolved() {
+        let listen: SocketAddr = "127.0.0.1:53535".parse().unwrap();
+        let tlds = vec!["local".to_string(), "test".to_string()];
+
+        assert_eq!(
+            render_dropin(ResolverKind::SystemdResolved, listen, &tlds),
+            "# Managed by oh-my-dockers - delegates project TLDs to 'omd dns serve'\n\
+             [Resolve]\n\
+             DNS=127.0.0.1:53535\n\
+             Domains=~local ~test\n"
+        );
+    }
+
+    #[test]
+    fn test_render_dropin_dnsmasq() {
+        let listen: SocketAddr = "[::1]:53535".parse().unwrap();
+        let tlds = vec!["local".to_string()];
+
+        assert_eq!(
+            render_dropin(ResolverKind::Dnsmasq, listen, &tlds),
+            "# Managed by oh-my-dockers - delegates project TLDs to 'omd dns serve'\n\
+             server=/local/::1#53535\n"
+        );
+    }
+}
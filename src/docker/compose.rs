@@ -3,7 +3,11 @@
 //! This module handles parsing docker-compose.yml files to extract
 //! service information, port mappings, and network configurations.
 
-use std::{collections::HashMap, fs, path::Path};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
 
 use anyhow::{Context, Result};
 use serde_yaml::Value;
@@ -29,19 +33,99 @@ pub struct ServiceInfo {
     /// Networks this service is connected to
     #[allow(dead_code)]
     pub networks: Vec<String>,
+    /// Compose profiles the service belongs to (empty = always enabled)
+    pub profiles: Vec<String>,
+}
+
+impl ServiceInfo {
+    /// Merge an override of the same service from a later compose file
+    fn merge(&mut self, other: ServiceInfo) {
+        if other.container_name.is_some() {
+            self.container_name = other.container_name;
+        }
+        for port in other.host_ports {
+            if !self.host_ports.contains(&port) {
+                self.host_ports.push(port);
+            }
+        }
+        for port in other.container_ports {
+            if !self.container_ports.contains(&port) {
+                self.container_ports.push(port);
+            }
+        }
+        for network in other.networks {
+            if !self.networks.contains(&network) {
+                self.networks.push(network);
+            }
+        }
+        if !other.profiles.is_empty() {
+            self.profiles = other.profiles;
+        }
+    }
+
+    /// Explicit container name, or the name Docker Compose generates
+    pub fn resolved_container_name(&self, project_name: &str) -> String {
+        self.container_name
+            .clone()
+            .unwrap_or_else(|| format!("{}-{}-1", project_name, self.name))
+    }
 }
 
 impl ComposeInfo {
-    /// Parse a docker-compose.yml file
+    /// Parse a docker-compose.yml file, including the files listed in its
+    /// top-level `include` section
     pub fn parse(path: &Path) -> Result<Self> {
+        let mut services = HashMap::new();
+        let mut visited = Vec::new();
+        Self::parse_into(path, &mut services, &mut visited)?;
+        Ok(Self { services })
+    }
+
+    /// Parse several compose files like `docker compose -f a -f b`, where
+    /// later files override services of earlier ones
+    pub fn parse_files(paths: &[PathBuf]) -> Result<Self> {
+        let mut services: HashMap<String, ServiceInfo> = HashMap::new();
+        for path in paths {
+            let mut file_services = HashMap::new();
+            Self::parse_into(path, &mut file_services, &mut Vec::new())?;
+            for (name, service) in file_services {
+                match services.get_mut(&name) {
+                    Some(existing) => existing.merge(service),
+                    None => {
+                        services.insert(name, service);
+                    }
+                }
+            }
+        }
+        Ok(Self { services })
+    }
+
+    /// Drop services whose Compose profiles are not active
+    pub fn retain_profiles(&mut self, active: &[String]) {
+        self.services.retain(|_, service| {
+            service.profiles.is_empty() || service.profiles.iter().any(|p| active.contains(p))
+        });
+    }
+
+    /// Parse a compose file and add its services (and those of included files)
+    fn parse_into(
+        path: &Path,
+        services: &mut HashMap<String, ServiceInfo>,
+        visited: &mut Vec<PathBuf>,
+    ) -> Result<()> {
+        // Guard against include cycles
+        let canonical = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        if visited.contains(&canonical) {
+            return Ok(());
+        }
+        visited.push(canonical);
+
         let content = fs::read_to_string(path)
             .context(format!("Failed to read docker-compose file: {:?}", path))?;
 
         let yaml: Value =
             serde_yaml::from_str(&content).context("Failed to parse docker-compose YAML")?;
 
-        let mut services = HashMap::new();
-
         if let Some(services_map) = yaml.get("services").and_then(|v| v.as_mapping()) {
             for (service_name, service_config) in services_map {
                 let name = service_name
@@ -58,19 +142,36 @@ impl ComposeInfo {
 
                 let networks = Self::parse_networks(service_config);
 
+                let profiles = service_config
+                    .get("profiles")
+                    .and_then(|v| v.as_sequence())
+                    .map(|list| {
+                        list.iter()
+                            .filter_map(|p| p.as_str())
+                            .map(str::to_string)
+                            .collect()
+                    })
+                    .unwrap_or_default();
+
                 let service_info = ServiceInfo {
                     name: name.clone(),
                     container_name,
                     host_ports,
                     container_ports,
                     networks,
+                    profiles,
                 };
 
                 services.insert(name, service_info);
             }
         }
 
-        Ok(Self { services })
+        let base_dir = path.parent().unwrap_or(Path::new("."));
+        for include in include_paths(&yaml) {
+            Self::parse_into(&base_dir.join(include), services, visited)?;
+        }
+
+        Ok(())
     }
 
     /// Parse port mappings from a service configuration
@@ -125,22 +226,22 @@ impl ComposeInfo {
                     }
                 } else if let Some(port_obj) = port_entry.as_mapping() {
                     // Handle long syntax
-                    if let Some(published) = port_obj.get(&Value::String("published".to_string())) {
+                    if let Some(published) = port_obj.get(Value::String("published".to_string())) {
                         if let Some(port) = published.as_u64() {
                             host_ports.push(port as u16);
-                        } else if let Some(port_str) = published.as_str() {
-                            if let Ok(port) = port_str.parse::<u16>() {
-                                host_ports.push(port);
-                            }
+                        } else if let Some(port_str) = published.as_str()
+                            && let Ok(port) = port_str.parse::<u16>()
+                        {
+                            host_ports.push(port);
                         }
                     }
-                    if let Some(target) = port_obj.get(&Value::String("target".to_string())) {
+                    if let Some(target) = port_obj.get(Value::String("target".to_string())) {
                         if let Some(port) = target.as_u64() {
                             container_ports.push(port as u16);
-                        } else if let Some(port_str) = target.as_str() {
-                            if let Ok(port) = port_str.parse::<u16>() {
-                                container_ports.push(port);
-                            }
+                        } else if let Some(port_str) = target.as_str()
+                            && let Ok(port) = port_str.parse::<u16>()
+                        {
+                            container_ports.push(port);
                         }
                     }
                 }
@@ -190,12 +291,7 @@ impl ComposeInfo {
     pub fn get_all_container_names(&self, project_name: &str) -> Vec<String> {
         self.services
             .values()
-            .map(|service| {
-                service.container_name.clone().unwrap_or_else(|| {
-                    // Default Docker Compose naming: project_service_1
-                    format!("{}-{}-1", project_name, service.name)
-                })
-            })
+            .map(|service| service.resolved_container_name(project_name))
             .collect()
     }
 
@@ -209,6 +305,57 @@ impl ComposeInfo {
     }
 }
 
+/// Compose files and the files they include, recursively
+/// Files that cannot be read or parsed are listed but not followed
+pub fn compose_file_tree(paths: &[PathBuf]) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = Vec::new();
+    let mut pending: Vec<PathBuf> = paths.iter().rev().cloned().collect();
+
+    while let Some(path) = pending.pop() {
+        if files.contains(&path) {
+            continue;
+        }
+        let includes: Vec<PathBuf> = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_yaml::from_str::<Value>(&content).ok())
+            .map(|yaml| {
+                let base_dir = path.parent().unwrap_or(Path::new("."));
+                include_paths(&yaml)
+                    .into_iter()
+                    .map(|include| base_dir.join(include))
+                    .collect()
+            })
+            .unwrap_or_default();
+        files.push(path);
+        pending.extend(includes.into_iter().rev());
+    }
+
+    files
+}
+
+/// Files listed in the top-level `include` section
+/// Supports the short syntax (`- file.yml`) and the long syntax (`- path: ...`)
+pub fn include_paths(yaml: &Value) -> Vec<String> {
+    let Some(includes) = yaml.get("include").and_then(|v| v.as_sequence()) else {
+        return Vec::new();
+    };
+
+    let mut paths = Vec::new();
+    for include in includes {
+        if let Some(path) = include.as_str() {
+            paths.push(path.to_string());
+        } else if let Some(path) = include.get("path") {
+            if let Some(path) = path.as_str() {
+                paths.push(path.to_string());
+            } else if let Some(list) = path.as_sequence() {
+                paths.extend(list.iter().filter_map(|p| p.as_str()).map(str::to_string));
+            }
+        }
+    }
+
+    paths
+}
+
 /// Ensure the network in docker-compose.yml is marked as external.
 /// This prevents Docker Compose from creating a new network with a project prefix.
 pub fn ensure_network_external(path: &Path, network_name: &str) -> Result<bool> {
@@ -410,4 +557,88 @@ networks:
         let modified = ensure_network_external(file.path(), "mynet").unwrap();
         assert!(!modified);
     }
+
+    #[test]
+    fn test_parse_follows_includes() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("docker-compose.yml"),
+            "include:\n  - docker-compose.omd.yml\n  - path: [extra.yml]\nservices:\n  app:\n    image: app:latest\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("docker-compose.omd.yml"),
+            "include:\n  - docker-compose.yml\nservices:\n  redis:\n    image: redis:8.2\n    ports:\n      - \"6380:6379\"\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("extra.yml"),
+            "services:\n  worker:\n    image: worker:1\n",
+        )
+        .unwrap();
+
+        let info = ComposeInfo::parse(&dir.path().join("docker-compose.yml")).unwrap();
+
+        let mut names: Vec<&String> = info.services.keys().collect();
+        names.sort();
+        assert_eq!(names, vec!["app", "redis", "worker"]);
+        assert_eq!(info.get_all_host_ports(), vec![6380]);
+
+        let files = compose_file_tree(&[dir.path().join("docker-compose.yml")]);
+        let names: Vec<_> = files
+            .iter()
+            .map(|file| file.file_name().unwrap().to_str().unwrap())
+            .collect();
+        assert_eq!(
+            names,
+            vec!["docker-compose.yml", "docker-compose.omd.yml", "extra.yml"]
+        );
+    }
+
+    #[test]
+    fn test_parse_files_with_overrides_and_profiles() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path().join("docker-compose.yml");
+        let debug = dir.path().join("docker-compose.debug.yml");
+        fs::write(
+            &base,
+            r#"
+services:
+  api:
+    image: api:latest
+    ports:
+      - "8080:8080"
+  mailpit:
+    image: axllent/mailpit
+    profiles: ["debug"]
+    ports:
+      - "8025:8025"
+"#,
+        )
+        .unwrap();
+        fs::write(
+            &debug,
+            r#"
+services:
+  api:
+    container_name: api-debug
+    ports:
+      - "9229:9229"
+"#,
+        )
+        .unwrap();
+
+        let mut info = ComposeInfo::parse_files(&[base, debug]).unwrap();
+        let api = &info.services["api"];
+        assert_eq!(api.container_name.as_deref(), Some("api-debug"));
+        assert_eq!(api.host_ports, vec![8080, 9229]);
+        assert_eq!(info.get_all_host_ports(), vec![8025, 8080, 9229]);
+
+        info.retain_profiles(&[]);
+        assert!(!info.services.contains_key("mailpit"));
+
+        let mut info = ComposeInfo::parse_files(&[dir.path().join("docker-compose.yml")]).unwrap();
+        info.retain_profiles(&["debug".to_string()]);
+        assert!(info.services.contains_key("mailpit"));
+    }
 }
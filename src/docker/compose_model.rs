@@ -0,0 +1,288 @@
+//! Typed Docker Compose model
+//!
+//! This module describes the subset of the Compose specification that omd
+//! generates. Serializing through serde takes care of YAML quoting, so values
+//! containing `:`, `#` or quotes are always written correctly.
+
+use anyhow::{Context, Result};
+use indexmap::IndexMap;
+use serde::{Serialize, Serializer};
+
+/// A docker-compose file
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ComposeFile {
+    /// Other compose files merged into this one
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+    pub services: IndexMap<String, Service>,
+    #[serde(skip_serializing_if = "IndexMap::is_empty")]
+    pub volumes: IndexMap<String, Volume>,
+    #[serde(skip_serializing_if = "IndexMap::is_empty")]
+    pub networks: IndexMap<String, Network>,
+}
+
+/// A single service
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Service {
+    pub image: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub container_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub restart: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command: Option<Command>,
+    /// Port mappings as "host:container"
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub ports: Vec<String>,
+    #[serde(skip_serializing_if = "IndexMap::is_empty")]
+    pub environment: IndexMap<String, String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub volumes: Vec<String>,
+    #[serde(skip_serializing_if = "IndexMap::is_empty")]
+    pub labels: IndexMap<String, String>,
+    #[serde(skip_serializing_if = "IndexMap::is_empty")]
+    pub depends_on: IndexMap<String, Dependency>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub healthcheck: Option<Healthcheck>,
+    #[serde(skip_serializing_if = "ServiceNetworks::is_empty")]
+    pub networks: ServiceNetworks,
+}
+
+/// Service command: a shell string or an exec-form argument list
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(untagged)]
+pub enum Command {
+    Shell(String),
+    Exec(Vec<String>),
+}
+
+/// Condition of a `depends_on` entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DependencyCondition {
+    ServiceStarted,
+    ServiceHealthy,
+}
+
+/// Long-syntax `depends_on` entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Dependency {
+    pub condition: DependencyCondition,
+}
+
+/// Container healthcheck
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Healthcheck {
+    /// Exec form, starting with CMD or CMD-SHELL
+    pub test: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub interval: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retries: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_period: Option<String>,
+}
+
+/// Networks a service is attached to
+///
+/// Serialized as a plain list unless an attachment sets aliases.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ServiceNetworks(pub IndexMap<String, ServiceNetwork>);
+
+/// Options of a service network attachment
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ServiceNetwork {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+}
+
+impl ServiceNetworks {
+    /// Attach to a network without options
+    pub fn attach(&mut self, network: &str) {
+        self.0.entry(network.to_string()).or_default();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl Serialize for ServiceNetworks {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.0.values().all(|n| n.aliases.is_empty()) {
+            serializer.collect_seq(self.0.keys())
+        } else {
+            self.0.serialize(serializer)
+        }
+    }
+}
+
+/// Top-level volume
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Volume {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub external: Option<bool>,
+}
+
+/// Top-level network
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Network {
+    /// Actual Docker network name (defaults to the key)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub external: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub driver: Option<String>,
+}
+
+impl Network {
+    /// A pre-created network that Compose must not create or prefix
+    pub fn external() -> Self {
+        Self {
+            external: Some(true),
+            ..Default::default()
+        }
+    }
+}
+
+impl ComposeFile {
+    /// Serialize to YAML
+    pub fn to_yaml(&self) -> Result<String> {
+        serde_yaml::to_string(self).context("Failed to serialize docker-compose YAML")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::docker::compose::ComposeInfo;
+
+    fn sample() -> ComposeFile {
+        let mut compose = ComposeFile::default();
+
+        let mut db_networks = ServiceNetworks::default();
+        db_networks.attach("shop-net");
+        db_networks.0.insert(
+            "backend".to_string(),
+            ServiceNetwork {
+                aliases: vec!["database".to_string()],
+            },
+        );
+
+        compose.services.insert(
+            "db".to_string(),
+            Service {
+                image: "postgres:18.1".to_string(),
+                container_name: Some("shop-db".to_string()),
+                ports: vec!["5433:5432".to_string()],
+                environment: IndexMap::from([
+                    ("PASSWORD".to_string(), "p#ss: \"word\"".to_string()),
+                    ("ENABLED".to_string(), "true".to_string()),
+                    ("WORKERS".to_string(), "4".to_string()),
+                ]),
+                volumes: vec!["db_data:/var/lib/postgresql".to_string()],
+                healthcheck: Some(Healthcheck {
+                    test: vec!["CMD-SHELL".to_string(), "pg_isready -U app".to_string()],
+                    interval: Some("5s".to_string()),
+                    retries: Some(10),
+                    ..Default::default()
+                }),
+                networks: db_networks,
+                ..Default::default()
+            },
+        );
+
+        let mut app_networks = ServiceNetworks::default();
+        app_networks.attach("shop-net");
+
+        compose.services.insert(
+            "app".to_string(),
+            Service {
+                image: "shop/app:1.0".to_string(),
+                restart: Some("unless-stopped".to_string()),
+                command: Some(Command::Exec(vec![
+                    "serve".to_string(),
+                    "--listen".to_string(),
+                    ":8080".to_string(),
+                ])),
+                ports: vec!["127.0.0.1:8080:8080".to_string()],
+                labels: IndexMap::from([(
+                    "com.oh-my-dockers.template".to_string(),
+                    "app".to_string(),
+                )]),
+                depends_on: IndexMap::from([(
+                    "db".to_string(),
+                    Dependency {
+                        condition: DependencyCondition::ServiceHealthy,
+                    },
+                )]),
+                networks: app_networks,
+                ..Default::default()
+            },
+        );
+
+        compose
+            .volumes
+            .insert("db_data".to_string(), Volume::default());
+        compose
+            .networks
+            .insert("shop-net".to_string(), Network::external());
+        compose.networks.insert(
+            "backend".to_string(),
+            Network {
+                name: Some("shop-backend".to_string()),
+                ..Default::default()
+            },
+        );
+
+        compose
+    }
+
+    #[test]
+    fn test_round_trip_through_compose_info() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("docker-compose.yml");
+        fs::write(&path, sample().to_yaml().unwrap()).unwrap();
+
+        let info = ComposeInfo::parse(&path).unwrap();
+        assert_eq!(info.services.len(), 2);
+
+        let db = &info.services["db"];
+        assert_eq!(db.container_name.as_deref(), Some("shop-db"));
+        assert_eq!(db.host_ports, vec![5433]);
+        assert_eq!(db.container_ports, vec![5432]);
+        assert_eq!(db.networks, vec!["shop-net", "backend"]);
+
+        let app = &info.services["app"];
+        assert_eq!(app.container_name, None);
+        assert_eq!(app.host_ports, vec![8080]);
+        assert_eq!(app.container_ports, vec![8080]);
+        assert_eq!(app.networks, vec!["shop-net"]);
+    }
+
+    #[test]
+    fn test_values_are_quoted() {
+        let yaml: serde_yaml::Value = serde_yaml::from_str(&sample().to_yaml().unwrap()).unwrap();
+        let db = &yaml["services"]["db"];
+        let app = &yaml["services"]["app"];
+
+        // Strings stay strings, special characters survive
+        assert_eq!(db["environment"]["PASSWORD"], "p#ss: \"word\"");
+        assert_eq!(db["environment"]["ENABLED"], "true");
+        assert_eq!(db["environment"]["WORKERS"], "4");
+
+        assert_eq!(app["depends_on"]["db"]["condition"], "service_healthy");
+        assert_eq!(app["command"][2], ":8080");
+        assert_eq!(db["healthcheck"]["test"][0], "CMD-SHELL");
+        assert_eq!(db["networks"]["backend"]["aliases"][0], "database");
+        assert_eq!(yaml["networks"]["shop-net"]["external"], true);
+        assert_eq!(yaml["networks"]["backend"]["name"], "shop-backend");
+    }
+}
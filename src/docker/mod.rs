@@ -2,7 +2,9 @@
 //!
 //! This module contains functionality for interacting with Docker:
 //! - docker-compose.yml parsing
+//! - Typed compose model for generated files
 //! - Network management
 
 pub mod compose;
+pub mod compose_model;
 pub mod network;
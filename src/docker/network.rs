@@ -7,13 +7,13 @@ use std::process::Command;
 use anyhow::{Context, Result};
 use colored::Colorize;
 
-use crate::caddy::CADDY_CONTAINER_NAME;
+use crate::project::registry::PortRegistry;
 
 /// Create a new Docker network
 pub fn create(name: &str) -> Result<()> {
     // Check if network exists
     let output = Command::new("docker")
-        .args(&["network", "inspect", name])
+        .args(["network", "inspect", name])
         .output()
         .context("Failed to inspect network")?;
 
@@ -26,7 +26,7 @@ pub fn create(name: &str) -> Result<()> {
     } else {
         println!("{} Creating network {}...", "ℹ".blue(), name.bright_white());
         let status = Command::new("docker")
-            .args(&["network", "create", name])
+            .args(["network", "create", name])
             .status()
             .context("Failed to create network")?;
 
@@ -46,7 +46,7 @@ pub fn list() -> Result<()> {
     println!();
 
     let output = Command::new("docker")
-        .args(&[
+        .args([
             "network",
             "ls",
             "--format",
@@ -68,7 +68,7 @@ pub fn list() -> Result<()> {
     }
 
     // Print header
-    println!("  {:<30} {:<15} {}", "NAME", "DRIVER", "SCOPE");
+    println!("  {:<30} {:<15} SCOPE", "NAME", "DRIVER");
     println!("  {}", "-".repeat(60));
 
     // Print networks
@@ -87,12 +87,31 @@ pub fn list() -> Result<()> {
     Ok(())
 }
 
+/// Remove a Docker network unless registered projects still use it
+pub fn remove_unused(name: &str) -> Result<()> {
+    let registry = PortRegistry::load()?;
+    let users: Vec<&str> = registry
+        .network_users(name)
+        .iter()
+        .map(|entry| entry.name.as_str())
+        .collect();
+    if !users.is_empty() {
+        anyhow::bail!(
+            "Network {} is still used by registered projects: {}\n\
+            Remove it from their omd.toml and run 'omd project up', or remove the projects with 'omd project remove'.",
+            name,
+            users.join(", ")
+        );
+    }
+
+    remove(name)
+}
+
 /// Remove a Docker network
-#[allow(dead_code)]
 pub fn remove(name: &str) -> Result<()> {
     // Check if network exists
     let output = Command::new("docker")
-        .args(&["network", "inspect", name])
+        .args(["network", "inspect", name])
         .output()
         .context("Failed to inspect network")?;
 
@@ -107,7 +126,7 @@ pub fn remove(name: &str) -> Result<()> {
 
     println!("{} Removing network {}...", "ℹ".blue(), name.bright_white());
     let status = Command::new("docker")
-        .args(&["network", "rm", name])
+        .args(["network", "rm", name])
         .status()
         .context("Failed to remove network")?;
 
@@ -124,7 +143,7 @@ pub fn remove(name: &str) -> Result<()> {
 pub fn connect(network: &str, container: &str) -> Result<()> {
     // Check if network exists
     let output = Command::new("docker")
-        .args(&["network", "inspect", network])
+        .args(["network", "inspect", network])
         .output()
         .context("Failed to inspect network")?;
 
@@ -134,7 +153,7 @@ pub fn connect(network: &str, container: &str) -> Result<()> {
 
     // Check if container exists
     let container_output = Command::new("docker")
-        .args(&[
+        .args([
             "ps",
             "-a",
             "--filter",
@@ -161,7 +180,7 @@ pub fn connect(network: &str, container: &str) -> Result<()> {
     );
 
     let status = Command::new("docker")
-        .args(&["network", "connect", network, container])
+        .args(["network", "connect", network, container])
         .status()
         .context("Failed to connect container to network")?;
 
@@ -188,37 +207,43 @@ pub fn ensure_network(network: &str) -> Result<()> {
     create(network)
 }
 
-/// Connect Caddy container to a network
-pub fn connect_caddy_to_network(network: &str) -> Result<()> {
-    // Check if Caddy is running
+/// Connect the reverse proxy container to a network
+pub fn connect_proxy_to_network(proxy_container: &str, network: &str) -> Result<()> {
+    // Check if the proxy is running
     let output = Command::new("docker")
-        .args(&[
+        .args([
             "ps",
             "--filter",
-            &format!("name={}", CADDY_CONTAINER_NAME),
+            &format!("name={}", proxy_container),
             "--format",
             "{{.Names}}",
         ])
         .output()
-        .context("Failed to check Caddy status")?;
+        .context("Failed to check proxy status")?;
 
-    let caddy_running = String::from_utf8_lossy(&output.stdout)
+    let proxy_running = String::from_utf8_lossy(&output.stdout)
         .trim()
-        .contains(CADDY_CONTAINER_NAME);
+        .contains(proxy_container);
 
-    if !caddy_running {
+    if !proxy_running {
         println!(
-            "{} Caddy is not running, skipping network connection",
-            "⚠".yellow()
+            "{} {} is not running, skipping network connection",
+            "⚠".yellow(),
+            proxy_container
         );
         return Ok(());
     }
 
-    println!("{} Connecting Caddy to network {}...", "ℹ".blue(), network);
+    println!(
+        "{} Connecting {} to network {}...",
+        "ℹ".blue(),
+        proxy_container,
+        network
+    );
 
     // Try to connect (ignore error if already connected)
     let _ = Command::new("docker")
-        .args(&["network", "connect", network, CADDY_CONTAINER_NAME])
+        .args(["network", "connect", network, proxy_container])
         .output();
 
     Ok(())
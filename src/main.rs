@@ -10,21 +10,36 @@ use clap::{CommandFactory, Parser};
 mod caddy;
 mod cli;
 mod config;
+mod diff;
+mod dns;
 mod docker;
 mod ports;
 mod project;
+mod proxy;
 mod system;
+mod templates;
+mod traefik;
 
 use cli::{
-    CaddyCommands, Cli, Commands, HostsCommands, NetworkCommands, ProjectCommands, ProxyCommands,
+    CaddyCommands, Cli, Commands, ConfigCommands, DnsCommands, HostsCommands, NetworkCommands,
+    ProjectCommands, ProxyCommands, TemplatesCommands,
 };
 
 fn main() -> Result<()> {
-    // Ensure configuration directory exists on startup
-    config::ensure_config_dir()?;
-
     let cli = Cli::parse();
 
+    // Ensure configuration directory exists on startup
+    // Migration commands work on config.toml as it is, so they skip the automatic migration
+    let migrate = !matches!(
+        cli.command,
+        Some(Commands::Config {
+            subcommand: ConfigCommands::Migrate { .. }
+                | ConfigCommands::Backups
+                | ConfigCommands::Rollback { .. }
+        })
+    );
+    config::ensure_config_dir(migrate)?;
+
     let command = match cli.command {
         Some(cmd) => cmd,
         None => {
@@ -35,8 +50,38 @@ fn main() -> Result<()> {
     };
 
     match command {
-        Commands::Init => {
-            project::init::init()?;
+        Commands::Init {
+            name,
+            domain,
+            network,
+            compose_file,
+            services,
+            routes,
+            force,
+            yes,
+        } => {
+            project::init::init(project::init::InitOptions {
+                name,
+                domain,
+                network,
+                compose_file,
+                services,
+                routes,
+                force,
+                yes,
+            })?;
+        }
+        Commands::Add { services, merge } => {
+            project::services::add(&services, merge)?;
+        }
+        Commands::RmService { services, rewrite } => {
+            project::services::remove(&services, rewrite)?;
+        }
+        Commands::Migrate => {
+            project::migration::migrate_project()?;
+        }
+        Commands::Watch { target, restart } => {
+            project::watch::watch(target.project.as_deref(), restart)?;
         }
         Commands::Caddy { subcommand } => match subcommand {
             CaddyCommands::Start => {
@@ -59,6 +104,9 @@ fn main() -> Result<()> {
             NetworkCommands::List => {
                 docker::network::list()?;
             }
+            NetworkCommands::Remove { name } => {
+                docker::network::remove_unused(&name)?;
+            }
         },
         Commands::Proxy { subcommand } => match subcommand {
             ProxyCommands::Add { domain, target } => {
@@ -71,7 +119,19 @@ fn main() -> Result<()> {
                 caddy::proxy::list()?;
             }
             ProxyCommands::Reload => {
-                caddy::proxy::reload()?;
+                proxy::backend()?.reload()?;
+            }
+            ProxyCommands::Start => {
+                proxy::backend()?.start()?;
+            }
+            ProxyCommands::Stop => {
+                proxy::backend()?.stop()?;
+            }
+            ProxyCommands::Restart => {
+                proxy::backend()?.restart()?;
+            }
+            ProxyCommands::Status => {
+                proxy::backend()?.status()?;
             }
         },
         Commands::Ports { network } => {
@@ -85,11 +145,57 @@ fn main() -> Result<()> {
             ProjectCommands::List => {
                 project::commands::list()?;
             }
-            ProjectCommands::Up => {
-                project::commands::up()?;
+            ProjectCommands::Up { profile, selection } => match selection.selection() {
+                Some(selection) => project::bulk::up(&selection)?,
+                None => project::commands::up(profile.as_deref())?,
+            },
+            ProjectCommands::Down { selection } => match selection.selection() {
+                Some(selection) => project::bulk::down(&selection)?,
+                None => project::commands::down()?,
+            },
+            ProjectCommands::Restart {
+                services,
+                selection,
+                project: name,
+            } => match selection.selection() {
+                Some(selection) => project::bulk::restart(&selection)?,
+                None => project::commands::restart(name.as_deref(), &services)?,
+            },
+            ProjectCommands::Logs {
+                services,
+                follow,
+                tail,
+                target,
+            } => {
+                project::passthrough::logs(
+                    target.project.as_deref(),
+                    &services,
+                    follow,
+                    tail.as_deref(),
+                )?;
+            }
+            ProjectCommands::Ps { target } => {
+                project::passthrough::ps(target.project.as_deref())?;
             }
-            ProjectCommands::Down => {
-                project::commands::down()?;
+            ProjectCommands::Exec {
+                service,
+                command,
+                target,
+            } => {
+                project::passthrough::exec(target.project.as_deref(), &service, &command)?;
+            }
+            ProjectCommands::Pull { services, target } => {
+                project::passthrough::pull(target.project.as_deref(), &services)?;
+            }
+            ProjectCommands::Build {
+                services,
+                no_cache,
+                target,
+            } => {
+                project::passthrough::build(target.project.as_deref(), &services, no_cache)?;
+            }
+            ProjectCommands::Status => {
+                project::commands::status()?;
             }
             ProjectCommands::Remove => {
                 project::commands::remove()?;
@@ -102,6 +208,61 @@ fn main() -> Result<()> {
             HostsCommands::Cleanup => {
                 system::hosts::cleanup_all_domains()?;
             }
+            HostsCommands::Diff => {
+                system::hosts::diff_sections(&project::registry::hosts_sections()?)?;
+            }
+            HostsCommands::Sync => {
+                system::hosts::sync_sections(&project::registry::hosts_sections()?)?;
+            }
+            HostsCommands::Backups => {
+                system::hosts::list_backups()?;
+            }
+            HostsCommands::Restore { timestamp } => {
+                system::hosts::restore_backup(timestamp.as_deref())?;
+            }
+        },
+        Commands::Dns { subcommand } => match subcommand {
+            DnsCommands::Serve { listen, upstream } => {
+                dns::serve(listen.as_deref(), upstream.as_deref())?;
+            }
+            DnsCommands::Setup { resolver, dry_run } => {
+                let listen = dns::listen_addr(None)?;
+                dns::setup::install(resolver, listen, dry_run)?;
+            }
+            DnsCommands::Teardown { resolver } => {
+                dns::setup::uninstall(resolver)?;
+            }
+        },
+        Commands::Templates { subcommand } => match subcommand {
+            TemplatesCommands::List => {
+                templates::commands::list()?;
+            }
+            TemplatesCommands::Show { name } => {
+                templates::commands::show(&name)?;
+            }
+        },
+        Commands::Config { subcommand } => match subcommand {
+            ConfigCommands::Validate => {
+                config::commands::validate()?;
+            }
+            ConfigCommands::Get { key } => {
+                config::commands::get(&key)?;
+            }
+            ConfigCommands::Set { key, value } => {
+                config::commands::set(&key, &value)?;
+            }
+            ConfigCommands::Edit => {
+                config::commands::edit()?;
+            }
+            ConfigCommands::Migrate { dry_run } => {
+                config::commands::migrate(dry_run)?;
+            }
+            ConfigCommands::Backups => {
+                config::commands::backups()?;
+            }
+            ConfigCommands::Rollback { backup } => {
+                config::commands::rollback(backup.as_deref())?;
+            }
         },
     }
 
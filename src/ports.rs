@@ -20,7 +20,7 @@ pub fn list() -> Result<()> {
 
     // Get all running containers with port mappings
     let output = Command::new("docker")
-        .args(&["ps", "--format", "{{.Names}}\t{{.Ports}}"])
+        .args(["ps", "--format", "{{.Names}}\t{{.Ports}}"])
         .output()
         .context("Failed to list containers")?;
 
@@ -54,7 +54,7 @@ pub fn list() -> Result<()> {
 
         // Get networks for this container
         let networks_output = Command::new("docker")
-            .args(&[
+            .args([
                 "inspect",
                 &container,
                 "--format",
@@ -64,7 +64,7 @@ pub fn list() -> Result<()> {
             .context("Failed to inspect container")?;
 
         let networks = String::from_utf8_lossy(&networks_output.stdout);
-        let network_list: Vec<&str> = networks.trim().split_whitespace().collect();
+        let network_list: Vec<&str> = networks.split_whitespace().collect();
 
         // Parse port mappings
         if ports_str != "<none>" && !ports_str.is_empty() {
@@ -76,7 +76,7 @@ pub fn list() -> Result<()> {
                     // No network info, use "unknown"
                     network_mappings
                         .entry("unknown".to_string())
-                        .or_insert_with(Vec::new)
+                        .or_default()
                         .push(PortMapping {
                             container: container.clone(),
                             network: "unknown".to_string(),
@@ -88,7 +88,7 @@ pub fn list() -> Result<()> {
                     for network in &network_list {
                         network_mappings
                             .entry(network.to_string())
-                            .or_insert_with(Vec::new)
+                            .or_default()
                             .push(PortMapping {
                                 container: container.clone(),
                                 network: network.to_string(),
@@ -147,7 +147,7 @@ pub fn show(network: &str) -> Result<()> {
 
     // Get all containers in this network
     let output = Command::new("docker")
-        .args(&[
+        .args([
             "network",
             "inspect",
             network,
@@ -162,7 +162,7 @@ pub fn show(network: &str) -> Result<()> {
     }
 
     let containers_str = String::from_utf8_lossy(&output.stdout);
-    let containers: Vec<&str> = containers_str.trim().split_whitespace().collect();
+    let containers: Vec<&str> = containers_str.split_whitespace().collect();
 
     if containers.is_empty() {
         println!("{}", "No containers in this network".yellow());
@@ -180,7 +180,7 @@ pub fn show(network: &str) -> Result<()> {
     for container in containers {
         // Get port mappings for this container
         let ps_output = Command::new("docker")
-            .args(&[
+            .args([
                 "ps",
                 "--filter",
                 &format!("name={}", container),
@@ -240,7 +240,7 @@ fn parse_port_mappings(ports_str: &str) -> Result<Vec<PortMapping>> {
 
                 // Extract local port (remove IP if present)
                 let local_port = if local_part.contains(':') {
-                    local_part.split(':').last().unwrap_or(local_part)
+                    local_part.split(':').next_back().unwrap_or(local_part)
                 } else {
                     local_part
                 };
@@ -253,7 +253,7 @@ fn parse_port_mappings(ports_str: &str) -> Result<Vec<PortMapping>> {
                 };
 
                 let protocol = if internal_part.contains('/') {
-                    internal_part.split('/').last().unwrap_or("tcp")
+                    internal_part.split('/').next_back().unwrap_or("tcp")
                 } else {
                     "tcp"
                 };
@@ -0,0 +1,308 @@
+//! Operations on several projects (--all, --group)
+//!
+//! Projects are resolved through the registry, so each must have been started
+//! once with `omd project up` from its directory. Configuration (registry,
+//! proxy routes, hosts file) is updated one project at a time; the docker
+//! compose commands of a startup stage run in parallel.
+
+use std::{
+    process::{Command, Output},
+    thread,
+};
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+
+use super::{
+    commands::configure,
+    config::{PROJECT_CONFIG_FILE, Project, load_project_at},
+    registry::PortRegistry,
+};
+use crate::config::load_global_config;
+
+/// Which projects a bulk command applies to
+pub enum Selection {
+    /// All registered projects
+    All,
+    /// A group from config.toml
+    Group(String),
+}
+
+/// Result of one project
+enum Outcome {
+    Done,
+    Failed(String),
+    Skipped,
+}
+
+/// Projects of the selection in startup stages
+fn plan(selection: &Selection, registry: &PortRegistry) -> Result<Vec<Vec<String>>> {
+    match selection {
+        Selection::All => {
+            let mut names: Vec<String> = registry
+                .list_projects()
+                .iter()
+                .map(|entry| entry.name.clone())
+                .collect();
+            if names.is_empty() {
+                anyhow::bail!("No registered projects. Run 'omd project up' in a project first.");
+            }
+            names.sort();
+            Ok(vec![names])
+        }
+        Selection::Group(name) => {
+            let global_config = load_global_config()?;
+            let Some(group) = global_config.groups.get(name) else {
+                let available: Vec<&str> =
+                    global_config.groups.keys().map(String::as_str).collect();
+                if available.is_empty() {
+                    anyhow::bail!(
+                        "Group '{}' not found: config.toml defines no [groups]",
+                        name
+                    );
+                }
+                anyhow::bail!(
+                    "Group '{}' not found. Available groups: {}",
+                    name,
+                    available.join(", ")
+                );
+            };
+
+            let unregistered: Vec<&str> = group
+                .projects()
+                .iter()
+                .filter(|project| registry.get_project(project).is_none())
+                .map(String::as_str)
+                .collect();
+            if !unregistered.is_empty() {
+                anyhow::bail!(
+                    "Group '{}' has unregistered projects: {}. Run 'omd project up' in their directories once.",
+                    name,
+                    unregistered.join(", ")
+                );
+            }
+
+            group
+                .stages()
+                .context(format!("Invalid group '{}' in config.toml", name))
+        }
+    }
+}
+
+/// Load a registered project with the profile it was started with
+fn load_registered(name: &str, registry: &PortRegistry) -> Result<Project> {
+    let entry = registry
+        .get_project(name)
+        .context(format!("Project '{}' is not registered", name))?;
+    let mut project = load_project_at(&entry.path.join(PROJECT_CONFIG_FILE))?;
+    if let Some(profile) = &entry.profile {
+        project.select_profile(profile)?;
+    }
+    project.resolve_compose_name(entry.compose_project.as_deref());
+    Ok(project)
+}
+
+/// Run a docker compose command for each project in parallel
+fn compose_all(projects: &[(String, Project)], args: &[&str]) -> Vec<(String, Outcome)> {
+    thread::scope(|scope| {
+        let handles: Vec<_> = projects
+            .iter()
+            .map(|(name, project)| {
+                scope.spawn(move || {
+                    let output = Command::new("docker")
+                        .arg("compose")
+                        .args(project.compose_args())
+                        .args(args)
+                        .current_dir(&project.dir)
+                        .output();
+                    (name.clone(), outcome(output))
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .map(|handle| handle.join().expect("docker compose thread panicked"))
+            .collect()
+    })
+}
+
+fn outcome(output: std::io::Result<Output>) -> Outcome {
+    match output {
+        Ok(output) if output.status.success() => Outcome::Done,
+        Ok(output) => {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let message = stderr
+                .lines()
+                .rev()
+                .find(|line| !line.trim().is_empty())
+                .unwrap_or("docker compose failed")
+                .trim()
+                .to_string();
+            Outcome::Failed(message)
+        }
+        Err(e) => Outcome::Failed(format!("Failed to execute docker compose: {}", e)),
+    }
+}
+
+/// Configure and start the selected projects, stage by stage
+pub fn up(selection: &Selection) -> Result<()> {
+    let registry = PortRegistry::load()?;
+    let stages = plan(selection, &registry)?;
+
+    let mut outcomes: Vec<(String, Outcome)> = Vec::new();
+    for (i, stage) in stages.iter().enumerate() {
+        if outcomes
+            .iter()
+            .any(|(_, outcome)| matches!(outcome, Outcome::Failed(_)))
+        {
+            outcomes.extend(stage.iter().map(|name| (name.clone(), Outcome::Skipped)));
+            continue;
+        }
+
+        if stages.len() > 1 {
+            println!();
+            println!(
+                "{} Stage {}/{}: {}",
+                "ℹ".blue(),
+                i + 1,
+                stages.len(),
+                stage.join(", ")
+            );
+        }
+
+        // Registry, proxy and hosts file are shared: configure one project at a time
+        let mut configured = Vec::new();
+        for name in stage {
+            println!();
+            println!("{}", format!("── {} ──", name).bright_white());
+            let profile = registry
+                .get_project(name)
+                .and_then(|entry| entry.profile.clone());
+            let config_path = registry
+                .get_project(name)
+                .map(|entry| entry.path.join(PROJECT_CONFIG_FILE))
+                .context(format!("Project '{}' is not registered", name))?;
+
+            match configure(&config_path, profile.as_deref()) {
+                Ok((project, _)) => configured.push((name.clone(), project)),
+                Err(e) => outcomes.push((name.clone(), Outcome::Failed(format!("{:#}", e)))),
+            }
+        }
+
+        println!();
+        println!(
+            "{} Starting containers of {}...",
+            "ℹ".blue(),
+            stage.join(", ")
+        );
+        outcomes.extend(compose_all(&configured, &["up", "-d"]));
+    }
+
+    print_summary("Started", &outcomes, true)
+}
+
+/// Stop the selected projects, in reverse startup order
+pub fn down(selection: &Selection) -> Result<()> {
+    run_stages(selection, &["down"], ("Stopping", "Stopped"), true)
+}
+
+/// Restart the containers of the selected projects, in startup order
+pub fn restart(selection: &Selection) -> Result<()> {
+    run_stages(selection, &["restart"], ("Restarting", "Restarted"), false)
+}
+
+/// Run a compose command for each stage (all projects of a stage in parallel)
+/// `verbs` describe the command while running and in the summary
+fn run_stages(
+    selection: &Selection,
+    args: &[&str],
+    verbs: (&str, &str),
+    reverse: bool,
+) -> Result<()> {
+    let registry = PortRegistry::load()?;
+    let mut stages = plan(selection, &registry)?;
+    if reverse {
+        stages.reverse();
+    }
+
+    let mut outcomes: Vec<(String, Outcome)> = Vec::new();
+    for stage in &stages {
+        let mut projects = Vec::new();
+        for name in stage {
+            match load_registered(name, &registry) {
+                Ok(project) => projects.push((name.clone(), project)),
+                Err(e) => outcomes.push((name.clone(), Outcome::Failed(format!("{:#}", e)))),
+            }
+        }
+
+        println!("{} {} {}...", "ℹ".blue(), verbs.0, stage.join(", "));
+        outcomes.extend(compose_all(&projects, args));
+    }
+
+    print_summary(verbs.1, &outcomes, false)
+}
+
+/// Print one line per project, with URL and ports from the registry for started projects
+/// Fails if any project failed
+fn print_summary(verb: &str, outcomes: &[(String, Outcome)], show_access: bool) -> Result<()> {
+    // Reload: `up` registered the projects again
+    let registry = PortRegistry::load()?;
+    let global_config = load_global_config()?;
+    let width = outcomes
+        .iter()
+        .map(|(name, _)| name.len())
+        .max()
+        .unwrap_or(0);
+
+    println!();
+    println!("{}", "Summary:".bright_white());
+    let mut failed = 0;
+    for (name, outcome) in outcomes {
+        let padded = format!("{:<width$}", name, width = width);
+        match outcome {
+            Outcome::Done => {
+                let mut details = Vec::new();
+                if let Some(entry) = registry.get_project(name)
+                    && show_access
+                {
+                    details.push(global_config.project_url(&entry.domain));
+                    if !entry.ports.is_empty() {
+                        let ports: Vec<String> =
+                            entry.ports.iter().map(|port| port.to_string()).collect();
+                        details.push(format!("ports {}", ports.join(", ")));
+                    }
+                }
+                println!(
+                    "  {} {}  {}  {}",
+                    "✓".green(),
+                    padded.bright_white(),
+                    verb,
+                    details.join("  ")
+                );
+            }
+            Outcome::Failed(message) => {
+                failed += 1;
+                println!(
+                    "  {} {}  {}",
+                    "✗".red(),
+                    padded.bright_white(),
+                    message.red()
+                );
+            }
+            Outcome::Skipped => {
+                println!(
+                    "  {} {}  skipped (an earlier stage failed)",
+                    "-".yellow(),
+                    padded.bright_white()
+                );
+            }
+        }
+    }
+
+    if failed > 0 {
+        anyhow::bail!("{} of {} project(s) failed", failed, outcomes.len());
+    }
+
+    Ok(())
+}
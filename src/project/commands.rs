@@ -1,19 +1,27 @@
-//! Project commands (up, down, list)
+//! Project commands (up, down, restart, status, remove, list)
 //!
 //! This module contains the main project management commands.
 
-use std::{env, fs, process::Command};
+use std::{path::Path, process::Command};
 
 use anyhow::{Context, Result};
 use colored::Colorize;
 
-use super::config::load_project_config;
-use super::registry::{PortRegistry, ProjectEntry};
-use crate::caddy;
-use crate::config::{get_config_dir, load_global_config};
-use crate::docker::compose::{ensure_network_external, ComposeInfo};
-use crate::docker::network::{connect_caddy_to_network, ensure_network};
-use crate::system::hosts;
+use super::{
+    compose_override,
+    config::{DefaultKind, Project, load_project, load_project_at, locate_project_config},
+    passthrough,
+    registry::{PortRegistry, ProjectEntry},
+};
+use crate::{
+    config::{GlobalConfig, load_global_config, validate},
+    docker::{
+        compose::ensure_network_external,
+        network::{connect_proxy_to_network, ensure_network},
+    },
+    proxy,
+    system::hosts,
+};
 
 /// List all registered projects
 pub fn list() -> Result<()> {
@@ -37,7 +45,13 @@ pub fn list() -> Result<()> {
         println!("  {} {}", "•".bright_white(), entry.name.bright_white());
         println!("    Path: {}", entry.path.display());
         println!("    Domain: {}", entry.domain);
+        if let Some(profile) = &entry.profile {
+            println!("    Profile: {}", profile);
+        }
         println!("    Network: {}", entry.network);
+        if !entry.shared_networks.is_empty() {
+            println!("    Shared networks: {}", entry.shared_networks.join(", "));
+        }
         if !entry.ports.is_empty() {
             println!("    Ports: {}", format_ports(&entry.ports));
         }
@@ -48,46 +62,120 @@ pub fn list() -> Result<()> {
 }
 
 /// Configure and register a project (run from project directory)
-pub fn up() -> Result<()> {
+pub fn up(profile: Option<&str>) -> Result<()> {
     println!("{}", "Configuring project...".blue());
 
-    // Load project configuration from current directory
-    let mut config = load_project_config()?;
+    let config_path = locate_project_config()?;
+    let (project, global_config) = configure(&config_path, profile)?;
+    let config = &project.config;
+
+    // Start containers
+    println!();
+    println!("{} Starting containers...", "ℹ".blue());
+
+    let status = Command::new("docker")
+        .arg("compose")
+        .args(project.compose_args())
+        .args(["up", "-d"])
+        .current_dir(&project.dir)
+        .status()
+        .context("Failed to execute docker compose")?;
 
-    let current_dir = env::current_dir().context("Failed to get current directory")?;
+    if status.success() {
+        println!("{} Containers started successfully", "✓".green());
+    } else {
+        println!(
+            "{} Failed to start containers (exit code: {:?})",
+            "✗".red(),
+            status.code()
+        );
+    }
+
+    println!();
+    println!(
+        "Access your project at: {}",
+        global_config.project_url(&config.project.domain)
+    );
+    if !config.caddy.routes.is_empty() {
+        println!();
+        println!("Custom routes:");
+        for subdomain in config.caddy.routes.keys() {
+            let domain = format!("{}.{}", subdomain, config.project.domain);
+            println!("  - {}", global_config.project_url(&domain));
+        }
+    }
+
+    Ok(())
+}
+
+/// Validate, register and route the project of an omd.toml, without starting containers
+/// Returns the project (with its generated override) and the global configuration
+pub fn configure(config_path: &Path, profile: Option<&str>) -> Result<(Project, GlobalConfig)> {
+    // Catch invalid values before they reach Docker or the proxy
+    validate::ensure_valid(config_path)?;
+
+    let mut project = load_project_at(config_path)?;
+    if let Some(name) = profile {
+        project.select_profile(name)?;
+    }
+
+    // Keep the Compose project name the containers were started under
+    let mut registry = PortRegistry::load()?;
+    let recorded = registry
+        .get_project(&project.config.project.name)
+        .and_then(|entry| entry.compose_project.clone());
+    project.resolve_compose_name(recorded.as_deref());
 
     // Set project path
-    config.project.path = Some(current_dir.to_string_lossy().to_string());
+    project.config.project.path = Some(project.dir.to_string_lossy().to_string());
+
+    let compose_files = project.compose_files();
+    let Project {
+        config,
+        dir: project_dir,
+        profile,
+        compose_project,
+    } = &project;
 
     println!(
         "{} Project: {}",
         "ℹ".blue(),
         config.project.name.bright_white()
     );
+    if let Some(profile) = profile {
+        println!("{} Profile: {}", "ℹ".blue(), profile.bright_white());
+    }
     println!("{} Domain: {}", "ℹ".blue(), config.project.domain);
     println!("{} Network: {}", "ℹ".blue(), config.network.name);
-
-    // Check for docker-compose file
-    let compose_path = current_dir.join(&config.project.compose_file);
-    if !compose_path.exists() {
-        anyhow::bail!(
-            "docker-compose file not found: {}\n\
-            Please ensure the file exists or update the 'compose_file' setting in omd.toml.",
-            compose_path.display()
+    if let Some(compose_project) = compose_project {
+        println!(
+            "{} Compose project: {} (containers and volumes of an earlier omd)",
+            "ℹ".blue(),
+            compose_project
         );
     }
 
-    // Parse docker-compose file
-    println!("{} Parsing {}...", "ℹ".blue(), config.project.compose_file);
-    let compose_info = ComposeInfo::parse(&compose_path)?;
+    // Check for docker-compose files
+    for file in &compose_files {
+        let compose_path = project_dir.join(file);
+        if !compose_path.exists() {
+            anyhow::bail!(
+                "docker-compose file not found: {}\n\
+                Please ensure the file exists or update the compose file settings in omd.toml.",
+                compose_path.display()
+            );
+        }
+    }
+
+    // Parse docker-compose files
+    println!("{} Parsing {}...", "ℹ".blue(), compose_files.join(", "));
+    let compose_info = project.compose_info()?;
 
-    // Ensure network is marked as external in docker-compose.yml
-    if ensure_network_external(&compose_path, &config.network.name)? {
-        println!(
-            "{} Updated {} to use external network",
-            "✓".green(),
-            config.project.compose_file
-        );
+    // Ensure network is marked as external in the docker-compose files
+    for file in &compose_files {
+        if ensure_network_external(&project_dir.join(file), &config.network.name)? {
+            println!("{} Updated {} to use external network", "✓".green(), file);
+        }
     }
 
     // Get all host ports
@@ -104,7 +192,7 @@ pub fn up() -> Result<()> {
     }
 
     // Get all container names
-    let container_names = compose_info.get_all_container_names(&config.project.name);
+    let container_names = compose_info.get_all_container_names(project.compose_name());
     println!(
         "{} Container names: {}",
         "ℹ".blue(),
@@ -112,7 +200,6 @@ pub fn up() -> Result<()> {
     );
 
     // Check for port conflicts
-    let mut registry = PortRegistry::load()?;
     let conflicts = registry.check_port_conflicts(&config.project.name, &host_ports);
 
     if !conflicts.is_empty() {
@@ -133,70 +220,108 @@ pub fn up() -> Result<()> {
 
     println!("{} No port conflicts", "✓".green());
 
-    // Ensure networks exist
+    // Collect all domains (main domain + custom routes or auto-generated routes)
+    let mut domains = vec![config.project.domain.clone()];
+    domains.extend(
+        proxy::collect_routes(config, &compose_info, project.compose_name())
+            .into_iter()
+            .map(|route| route.domain),
+    );
+
+    // Apply the global defaults through the generated compose override
     let global_config = load_global_config()?;
+    if let Some(written) = compose_override::write_override(&project, &global_config)? {
+        if !written.applied.is_empty() {
+            let kinds: Vec<&str> = written.applied.iter().map(DefaultKind::as_str).collect();
+            println!(
+                "{} Applied defaults ({}) to {} service(s) via {}",
+                "ℹ".blue(),
+                kinds.join(", "),
+                written.services,
+                written.path.display()
+            );
+        }
+        if !written.attached.is_empty() {
+            println!(
+                "{} Attached {} to shared network(s) {}",
+                "ℹ".blue(),
+                written.attached.join(", "),
+                config.network.shared.join(", ")
+            );
+        }
+    }
+
+    // Ensure networks exist
 
     // Create all globally defined networks
-    for (network_name, _network_def) in &global_config.networks {
+    for network_name in global_config.networks.keys() {
         ensure_network(network_name)?;
     }
 
     // Create project network
     ensure_network(&config.network.name)?;
 
-    // Auto-start Caddy if not running
-    caddy::manager::auto_start_if_needed()?;
+    // Create or reuse the networks shared with other projects
+    for network_name in &config.network.shared {
+        ensure_network(network_name)?;
+    }
+
+    // Auto-start the reverse proxy if not running
+    let backend = proxy::backend_for(&global_config);
+    backend.ensure_running()?;
 
-    // Generate Caddy configuration
-    caddy::config::generate_caddy_config(&config, &compose_info)?;
+    // Generate reverse proxy configuration
+    proxy::generate_project_config(
+        backend.as_ref(),
+        config,
+        &compose_info,
+        project.compose_name(),
+    )?;
 
-    // Connect Caddy to project network
-    connect_caddy_to_network(&config.network.name)?;
+    // Connect the reverse proxy to project network
+    connect_proxy_to_network(backend.container_name(), &config.network.name)?;
 
     // Register project in port registry
     let entry = ProjectEntry {
         name: config.project.name.clone(),
-        path: current_dir.clone(),
+        path: project_dir.clone(),
         domain: config.project.domain.clone(),
         network: config.network.name.clone(),
+        shared_networks: config.network.shared.clone(),
         ports: host_ports,
         containers: container_names,
+        addresses: config.hosts.addresses.clone(),
+        domains,
+        aliases: config.hosts.aliases.clone(),
+        profile: profile.clone(),
+        compose_project: Some(project.compose_name().to_string()),
     };
 
+    let hosts_domains = entry.hosts_domains();
     registry.register_project(entry)?;
 
-    // Reload Caddy
-    caddy::proxy::reload()?;
+    // Reload the reverse proxy
+    backend.reload()?;
 
     // Update /etc/hosts with project domains
     println!();
     println!("{} Updating /etc/hosts...", "ℹ".blue());
 
-    // Collect all domains (main domain + custom routes + auto-generated routes)
-    let mut domains = vec![config.project.domain.clone()];
-
-    // Add custom routes
-    for (subdomain, _) in &config.caddy.routes {
-        domains.push(format!("{}.{}", subdomain, config.project.domain));
-    }
-
-    // Add auto-generated routes (if no custom routes)
-    if config.caddy.routes.is_empty() {
-        for (service_name, service_info) in &compose_info.services {
-            // Only add services with container ports (HTTP services)
-            if !service_info.container_ports.is_empty() {
-                domains.push(format!("{}.{}", service_name, config.project.domain));
-            }
-        }
-    }
-
-    if let Err(e) = hosts::add_project_domains(&config.project.name, &domains) {
+    if let Err(e) = hosts::add_project_domains(
+        &config.project.name,
+        &hosts_domains,
+        &config.hosts.addresses,
+    ) {
         // Log error but don't fail the entire operation
         println!(
             "{} Warning: Failed to update /etc/hosts: {}",
             "⚠".yellow(),
             e
         );
+        println!(
+            "  Run {} later to repair the hosts file",
+            "omd hosts sync".bright_white()
+        );
     }
 
     println!();
@@ -206,40 +331,90 @@ pub fn up() -> Result<()> {
         config.project.name.bright_white()
     );
 
-    // Start containers
-    println!();
-    println!("{} Starting containers...", "ℹ".blue());
+    Ok((project, global_config))
+}
+
+/// Load the project with the profile it was started with
+fn load_started_project() -> Result<Project> {
+    let mut project = load_project()?;
+
+    println!(
+        "{} Project: {}",
+        "ℹ".blue(),
+        project.config.project.name.bright_white()
+    );
+
+    let registry = PortRegistry::load()?;
+    let entry = registry.get_project(&project.config.project.name);
+    if let Some(profile) = entry.and_then(|entry| entry.profile.clone()) {
+        println!("{} Profile: {}", "ℹ".blue(), profile.bright_white());
+        project.select_profile(&profile)?;
+    }
+    project.resolve_compose_name(entry.and_then(|entry| entry.compose_project.as_deref()));
+
+    Ok(project)
+}
+
+/// Show the registration and containers of the project (run from project directory)
+pub fn status() -> Result<()> {
+    let project = load_started_project()?;
+    let config = &project.config;
+
+    let registry = PortRegistry::load()?;
+    let Some(entry) = registry.get_project(&config.project.name) else {
+        println!(
+            "{} Project is not registered. Run {} to start it.",
+            "⚠".yellow(),
+            "omd project up".bright_white()
+        );
+        return Ok(());
+    };
 
-    let compose_file = &config.project.compose_file;
+    println!("{} Domain: {}", "ℹ".blue(), entry.domain);
+    println!("{} Network: {}", "ℹ".blue(), entry.network);
+    if let Some(compose_project) = &project.compose_project {
+        println!("{} Compose project: {}", "ℹ".blue(), compose_project);
+    }
+    if !entry.shared_networks.is_empty() {
+        println!(
+            "{} Shared networks: {}",
+            "ℹ".blue(),
+            entry.shared_networks.join(", ")
+        );
+    }
+    if !entry.ports.is_empty() {
+        println!("{} Ports: {}", "ℹ".blue(), format_ports(&entry.ports));
+    }
+    if !entry.aliases.is_empty() {
+        println!("{} Aliases: {}", "ℹ".blue(), entry.aliases.join(", "));
+    }
+    let global_config = load_global_config()?;
+    // Aliases are not routed by the proxy
+    for domain in entry
+        .hosts_domains()
+        .into_iter()
+        .filter(|domain| !entry.aliases.contains(domain))
+    {
+        println!("  - {}", global_config.project_url(&domain));
+    }
+
+    println!();
     let status = Command::new("docker")
-        .args(["compose", "-f", compose_file, "up", "-d"])
-        .current_dir(&current_dir)
+        .arg("compose")
+        .args(project.compose_args())
+        .arg("ps")
+        .current_dir(&project.dir)
         .status()
         .context("Failed to execute docker compose")?;
 
-    if status.success() {
-        println!("{} Containers started successfully", "✓".green());
-    } else {
+    if !status.success() {
         println!(
-            "{} Failed to start containers (exit code: {:?})",
+            "{} Failed to list containers (exit code: {:?})",
             "✗".red(),
             status.code()
         );
     }
 
-    println!();
-    println!(
-        "Access your project at: https://{}",
-        config.project.domain
-    );
-    if !config.caddy.routes.is_empty() {
-        println!();
-        println!("Custom routes:");
-        for (subdomain, _) in &config.caddy.routes {
-            println!("  - https://{}.{}", subdomain, config.project.domain);
-        }
-    }
-
     Ok(())
 }
 
@@ -248,20 +423,14 @@ pub fn down() -> Result<()> {
     println!("{}", "Stopping containers...".blue());
 
     // Load project configuration
-    let config = load_project_config()?;
-    let current_dir = env::current_dir().context("Failed to get current directory")?;
-
-    println!(
-        "{} Project: {}",
-        "ℹ".blue(),
-        config.project.name.bright_white()
-    );
+    let project = load_started_project()?;
 
     // Stop containers
-    let compose_file = &config.project.compose_file;
     let status = Command::new("docker")
-        .args(["compose", "-f", compose_file, "down"])
-        .current_dir(&current_dir)
+        .arg("compose")
+        .args(project.compose_args())
+        .arg("down")
+        .current_dir(&project.dir)
         .status()
         .context("Failed to execute docker compose")?;
 
@@ -278,27 +447,51 @@ pub fn down() -> Result<()> {
     Ok(())
 }
 
-/// Stop containers and remove all project configuration (run from project directory)
-pub fn remove() -> Result<()> {
-    println!("{}", "Removing project...".blue());
-
-    // Load project configuration
-    let config = load_project_config()?;
-    let current_dir = env::current_dir().context("Failed to get current directory")?;
+/// Restart the project's containers, or only the given services
+/// `name` selects a registered project instead of the current directory
+pub fn restart(name: Option<&str>, services: &[String]) -> Result<()> {
+    println!("{}", "Restarting containers...".blue());
 
+    let project = passthrough::resolve(name)?;
     println!(
         "{} Project: {}",
         "ℹ".blue(),
-        config.project.name.bright_white()
+        project.config.project.name.bright_white()
     );
 
+    let mut args = vec!["restart".to_string()];
+    args.extend(services.iter().cloned());
+    let status = passthrough::compose(&project, &args)?;
+
+    if status.success() {
+        println!("{} Containers restarted successfully", "✓".green());
+    } else {
+        println!(
+            "{} Failed to restart containers (exit code: {:?})",
+            "✗".red(),
+            status.code()
+        );
+    }
+
+    Ok(())
+}
+
+/// Stop containers and remove all project configuration (run from project directory)
+pub fn remove() -> Result<()> {
+    println!("{}", "Removing project...".blue());
+
+    // Load project configuration
+    let project = load_started_project()?;
+    let config = &project.config;
+
     // Stop containers first
     println!();
     println!("{} Stopping containers...", "ℹ".blue());
-    let compose_file = &config.project.compose_file;
     let status = Command::new("docker")
-        .args(["compose", "-f", compose_file, "down"])
-        .current_dir(&current_dir)
+        .arg("compose")
+        .args(project.compose_args())
+        .arg("down")
+        .current_dir(&project.dir)
         .status()
         .context("Failed to execute docker compose")?;
 
@@ -312,18 +505,13 @@ pub fn remove() -> Result<()> {
         );
     }
 
-    // Remove Caddy configuration
+    // Remove reverse proxy configuration
     println!();
     println!("{} Removing configuration...", "ℹ".blue());
-    let config_dir = get_config_dir()?;
-    let global_config = load_global_config()?;
-    let caddy_config = config_dir
-        .join(&global_config.global.caddy_projects_dir)
-        .join(format!("{}.caddy", config.project.name));
+    let backend = proxy::backend()?;
 
-    if caddy_config.exists() {
-        fs::remove_file(&caddy_config).context("Failed to remove Caddy configuration")?;
-        println!("{} Removed Caddy configuration", "✓".green());
+    if proxy::remove_project_config(backend.as_ref(), &config.project.name)? {
+        println!("{} Removed {} configuration", "✓".green(), backend.name());
     }
 
     // Unregister from port registry
@@ -342,8 +530,8 @@ pub fn remove() -> Result<()> {
         );
     }
 
-    // Reload Caddy
-    caddy::proxy::reload()?;
+    // Reload the reverse proxy
+    backend.reload()?;
 
     println!();
     println!(
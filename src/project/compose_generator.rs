@@ -1,75 +1,26 @@
 //! Docker Compose file generator
 //!
 //! This module provides functionality to generate docker-compose.yml files
-//! with pre-configured service templates.
+//! from service templates (see `crate::templates`).
 
-use std::fs;
-use std::io::{self, Write};
-use std::path::Path;
+use std::{
+    fs,
+    io::{self, Write},
+    path::Path,
+};
 
 use anyhow::{Context, Result};
 use colored::Colorize;
+use indexmap::IndexMap;
 
 use super::registry::PortRegistry;
-
-/// Service template definition
-#[derive(Debug, Clone)]
-pub struct ServiceTemplate {
-    pub name: &'static str,
-    pub display_name: &'static str,
-    pub image: &'static str,
-    pub default_port: u16,
-    pub container_port: u16,
-    pub environment: &'static [(&'static str, &'static str)],
-    pub volumes: &'static [&'static str],
-}
-
-/// Available service templates
-pub const AVAILABLE_SERVICES: &[ServiceTemplate] = &[
-    // PostgreSQL
-    ServiceTemplate {
-        name: "postgres",
-        display_name: "PostgreSQL",
-        image: "postgres:latest",
-        default_port: 5432,
-        container_port: 5432,
-        environment: &[
-            ("POSTGRES_USER", "postgres"),
-            ("POSTGRES_PASSWORD", "postgres"),
-            ("POSTGRES_DB", "app"),
-        ],
-        volumes: &["postgres_data:/var/lib/postgresql"],
+use crate::{
+    docker::compose_model::{
+        Command, ComposeFile, Dependency, DependencyCondition, Healthcheck, Network, Service,
+        ServiceNetworks,
     },
-    // Redis
-    ServiceTemplate {
-        name: "redis",
-        display_name: "Redis",
-        image: "redis:latest",
-        default_port: 6379,
-        container_port: 6379,
-        environment: &[],
-        volumes: &["redis_data:/data"],
-    },
-    // Kafka (KRaft mode - no Zookeeper required)
-    ServiceTemplate {
-        name: "kafka",
-        display_name: "Kafka (KRaft)",
-        image: "apache/kafka:latest",
-        default_port: 9092,
-        container_port: 9092,
-        environment: &[
-            ("KAFKA_NODE_ID", "1"),
-            ("KAFKA_PROCESS_ROLES", "broker,controller"),
-            ("KAFKA_LISTENERS", "PLAINTEXT://:9092,CONTROLLER://:9093"),
-            ("KAFKA_ADVERTISED_LISTENERS", "PLAINTEXT://localhost:9092"),
-            ("KAFKA_CONTROLLER_LISTENER_NAMES", "CONTROLLER"),
-            ("KAFKA_CONTROLLER_QUORUM_VOTERS", "1@kafka:9093"),
-            ("KAFKA_OFFSETS_TOPIC_REPLICATION_FACTOR", "1"),
-            ("CLUSTER_ID", "omd-kafka-cluster-id-001"),
-        ],
-        volumes: &["kafka_data:/var/lib/kafka/data"],
-    },
-];
+    templates::{ServiceTemplate, StringOrList},
+};
 
 /// Find an available port that doesn't conflict with existing ports
 pub fn find_available_port(desired: u16, used_ports: &[u16]) -> u16 {
@@ -80,15 +31,35 @@ pub fn find_available_port(desired: u16, used_ports: &[u16]) -> u16 {
     port
 }
 
-/// Selected service with resolved port
+/// Selected service with resolved ports
 #[derive(Debug, Clone)]
 pub struct SelectedService {
-    pub template: &'static ServiceTemplate,
-    pub host_port: u16,
+    pub template: ServiceTemplate,
+    /// Host port for each entry of `template.ports`
+    pub host_ports: Vec<u16>,
+    /// `.env` variable for each entry of `template.secrets`
+    pub env_vars: IndexMap<String, String>,
+}
+
+impl SelectedService {
+    /// Select a template; secrets use variables of the same name
+    pub fn new(template: ServiceTemplate, host_ports: Vec<u16>) -> Self {
+        let env_vars = template
+            .secrets
+            .iter()
+            .map(|secret| (secret.clone(), secret.clone()))
+            .collect();
+
+        Self {
+            template,
+            host_ports,
+            env_vars,
+        }
+    }
 }
 
 /// Prompt user to select services from available templates
-pub fn prompt_service_selection() -> Result<Vec<usize>> {
+pub fn prompt_service_selection(templates: &[ServiceTemplate]) -> Result<Vec<usize>> {
     println!();
     println!(
         "{}",
@@ -96,12 +67,12 @@ pub fn prompt_service_selection() -> Result<Vec<usize>> {
     );
     println!();
 
-    for (idx, service) in AVAILABLE_SERVICES.iter().enumerate() {
+    for (idx, template) in templates.iter().enumerate() {
         println!(
             "  [{}] {} (port {})",
             idx + 1,
-            service.display_name.bright_white(),
-            service.default_port
+            template.display_name.bright_white(),
+            template.ports_label()
         );
     }
 
@@ -115,7 +86,7 @@ pub fn prompt_service_selection() -> Result<Vec<usize>> {
     let selections: Vec<usize> = input
         .split_whitespace()
         .filter_map(|s| s.parse::<usize>().ok())
-        .filter(|&n| n >= 1 && n <= AVAILABLE_SERVICES.len())
+        .filter(|&n| n >= 1 && n <= templates.len())
         .map(|n| n - 1) // Convert to 0-based index
         .collect();
 
@@ -123,134 +94,190 @@ pub fn prompt_service_selection() -> Result<Vec<usize>> {
 }
 
 /// Resolve ports for selected services, avoiding conflicts
+/// `reserved_ports` are taken in addition to the ports of registered projects
 pub fn resolve_service_ports(
-    selections: &[usize],
+    templates: &[&ServiceTemplate],
     registry: &PortRegistry,
+    reserved_ports: &[u16],
 ) -> Vec<SelectedService> {
     let mut used_ports = registry.get_all_used_ports();
+    used_ports.extend_from_slice(reserved_ports);
     let mut selected_services = Vec::new();
 
     println!();
     println!("{} Checking port conflicts...", "ℹ".blue());
 
-    for &idx in selections {
-        let template = &AVAILABLE_SERVICES[idx];
-        let desired_port = template.default_port;
-        let host_port = find_available_port(desired_port, &used_ports);
-
-        if host_port != desired_port {
-            println!(
-                "{} Port {} in use, using {} for {}",
-                "⚠".yellow(),
-                desired_port,
-                host_port.to_string().green(),
-                template.display_name
-            );
-        } else {
-            println!(
-                "{} Port {} available for {}",
-                "✓".green(),
-                host_port,
-                template.display_name
-            );
-        }
+    for template in templates {
+        let mut host_ports = Vec::new();
+
+        for port in &template.ports {
+            let desired_port = port.host;
+            let host_port = find_available_port(desired_port, &used_ports);
+
+            if host_port != desired_port {
+                println!(
+                    "{} Port {} in use, using {} for {}",
+                    "⚠".yellow(),
+                    desired_port,
+                    host_port.to_string().green(),
+                    template.display_name
+                );
+            } else {
+                println!(
+                    "{} Port {} available for {}",
+                    "✓".green(),
+                    host_port,
+                    template.display_name
+                );
+            }
 
-        // Add to used ports to avoid conflicts between selected services
-        used_ports.push(host_port);
+            // Add to used ports to avoid conflicts between selected services
+            used_ports.push(host_port);
+            host_ports.push(host_port);
+        }
 
-        selected_services.push(SelectedService {
-            template,
-            host_port,
-        });
+        selected_services.push(SelectedService::new((*template).clone(), host_ports));
     }
 
     selected_services
 }
 
-/// Generate docker-compose.yml content
-pub fn generate_compose_content(
+/// Proxy routes for the HTTP ports of selected services as (subdomain, target)
+pub fn template_routes(project_name: &str, services: &[SelectedService]) -> Vec<(String, String)> {
+    services
+        .iter()
+        .flat_map(|service| {
+            let container_name = format!("{}-{}", project_name, service.template.name);
+            service
+                .template
+                .http_routes()
+                .into_iter()
+                .map(move |(subdomain, port)| (subdomain, format!("{}:{}", container_name, port)))
+        })
+        .collect()
+}
+
+/// Label recording which template a generated service came from
+pub const OMD_TEMPLATE_LABEL: &str = "com.oh-my-dockers.template";
+
+/// Build the compose model for selected services
+///
+/// Template dependencies are only added when the dependency is selected too;
+/// they wait for a healthy dependency when it has a healthcheck.
+pub fn build_compose_file(
     project_name: &str,
     network_name: &str,
     services: &[SelectedService],
-) -> String {
-    let mut content = String::from("# Generated by oh-my-dockers\n");
-    content.push_str("services:\n");
+) -> ComposeFile {
+    let mut compose = ComposeFile::default();
 
     for service in services {
-        content.push_str(&generate_service_block(project_name, network_name, service));
-    }
+        compose.services.insert(
+            service.template.name.clone(),
+            build_service(project_name, network_name, service, services),
+        );
 
-    // Generate volumes section
-    if !services.is_empty() {
-        content.push_str("\nvolumes:\n");
-        for service in services {
-            for volume in service.template.volumes {
-                if let Some(volume_name) = volume.split(':').next() {
-                    content.push_str(&format!("  {}:\n", volume_name));
-                }
-            }
+        for volume_name in service.template.named_volumes() {
+            compose.volumes.entry(volume_name.to_string()).or_default();
         }
     }
 
-    // Generate networks section (external: true to use pre-created network)
-    content.push_str(&format!(
-        "\nnetworks:\n  {}:\n    external: true\n",
-        network_name
-    ));
+    // External network so Compose uses the pre-created network
+    compose
+        .networks
+        .insert(network_name.to_string(), Network::external());
 
-    content
+    compose
 }
 
-/// Generate a single service block
-fn generate_service_block(
+/// Build a single service from its template
+fn build_service(
     project_name: &str,
     network_name: &str,
     service: &SelectedService,
-) -> String {
-    let template = service.template;
-    let mut block = format!("  {}:\n", template.name);
-
-    // Image
-    block.push_str(&format!("    image: {}\n", template.image));
-
-    // Container name
-    block.push_str(&format!(
-        "    container_name: {}-{}\n",
-        project_name, template.name
-    ));
-
-    // Restart policy
-    block.push_str("    restart: unless-stopped\n");
-
-    // Ports
-    block.push_str("    ports:\n");
-    block.push_str(&format!(
-        "      - \"{}:{}\"\n",
-        service.host_port, template.container_port
-    ));
-
-    // Environment variables
-    if !template.environment.is_empty() {
-        block.push_str("    environment:\n");
-        for (key, value) in template.environment {
-            block.push_str(&format!("      {}: {}\n", key, value));
-        }
-    }
+    selected: &[SelectedService],
+) -> Service {
+    let template = &service.template;
+
+    let command = template.command.as_ref().map(|command| match command {
+        StringOrList::String(command) => Command::Shell(command.clone()),
+        StringOrList::List(args) => Command::Exec(args.clone()),
+    });
+
+    let ports = template
+        .ports
+        .iter()
+        .zip(&service.host_ports)
+        .map(|(port, host_port)| format!("{}:{}", host_port, port.container))
+        .collect();
 
-    // Volumes
-    if !template.volumes.is_empty() {
-        block.push_str("    volumes:\n");
-        for volume in template.volumes {
-            block.push_str(&format!("      - {}\n", volume));
-        }
+    let mut labels = template.labels.clone();
+    labels.insert(OMD_TEMPLATE_LABEL.to_string(), template.name.clone());
+
+    let depends_on = template
+        .depends_on
+        .iter()
+        .filter_map(|dependency| {
+            let other = selected.iter().find(|s| &s.template.name == dependency)?;
+            let condition = if other.template.healthcheck.is_some() {
+                DependencyCondition::ServiceHealthy
+            } else {
+                DependencyCondition::ServiceStarted
+            };
+            Some((dependency.clone(), Dependency { condition }))
+        })
+        .collect();
+
+    let healthcheck = template
+        .healthcheck
+        .as_ref()
+        .map(|healthcheck| Healthcheck {
+            test: match &healthcheck.test {
+                StringOrList::String(command) => vec!["CMD-SHELL".to_string(), command.clone()],
+                StringOrList::List(args) => args.clone(),
+            },
+            interval: healthcheck.interval.clone(),
+            timeout: healthcheck.timeout.clone(),
+            retries: healthcheck.retries,
+            start_period: healthcheck.start_period.clone(),
+        });
+
+    // Secrets are interpolated from .env
+    let mut environment = template.environment_with_ports(&service.host_ports);
+    for secret in &template.secrets {
+        let variable = service.env_vars.get(secret).unwrap_or(secret);
+        environment.insert(secret.clone(), format!("${{{}}}", variable));
     }
 
-    // Networks
-    block.push_str("    networks:\n");
-    block.push_str(&format!("      - {}\n", network_name));
+    let mut networks = ServiceNetworks::default();
+    networks.attach(network_name);
+
+    Service {
+        image: template.image.clone(),
+        container_name: Some(format!("{}-{}", project_name, template.name)),
+        restart: Some("unless-stopped".to_string()),
+        command,
+        ports,
+        environment,
+        volumes: template.volumes.clone(),
+        labels,
+        depends_on,
+        healthcheck,
+        networks,
+    }
+}
 
-    block.push('\n');
-    block
+/// Generate docker-compose.yml content
+pub fn generate_compose_content(
+    project_name: &str,
+    network_name: &str,
+    services: &[SelectedService],
+) -> Result<String> {
+    let compose = build_compose_file(project_name, network_name, services);
+    Ok(format!(
+        "# Generated by oh-my-dockers\n{}",
+        compose.to_yaml()?
+    ))
 }
 
 /// Generate docker-compose.yml file
@@ -260,7 +287,7 @@ pub fn generate_compose_file(
     network_name: &str,
     services: &[SelectedService],
 ) -> Result<()> {
-    let content = generate_compose_content(project_name, network_name, services);
+    let content = generate_compose_content(project_name, network_name, services)?;
     fs::write(path, content).context("Failed to write docker-compose.yml")?;
     Ok(())
 }
@@ -268,6 +295,7 @@ pub fn generate_compose_file(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::templates::TemplateCatalog;
 
     #[test]
     fn test_find_available_port() {
@@ -278,32 +306,111 @@ mod tests {
         assert_eq!(find_available_port(6379, &used), 6380);
     }
 
+    fn selected(name: &str, host_ports: Vec<u16>) -> SelectedService {
+        SelectedService::new(
+            TemplateCatalog::builtin().get(name).unwrap().clone(),
+            host_ports,
+        )
+    }
+
     #[test]
     fn test_generate_compose_content() {
-        let services = vec![SelectedService {
-            template: &AVAILABLE_SERVICES[0], // PostgreSQL
-            host_port: 5432,
-        }];
+        let services = vec![selected("postgres", vec![5432])];
 
-        let content = generate_compose_content("myproject", "myproject-net", &services);
+        let content = generate_compose_content("myproject", "myproject-net", &services).unwrap();
 
-        assert!(content.contains("postgres:latest"));
+        assert!(content.contains("postgres:18.1"));
         assert!(content.contains("myproject-postgres"));
         assert!(content.contains("5432:5432"));
         assert!(content.contains("POSTGRES_USER"));
+        assert!(content.contains("pg_isready"));
+        assert!(content.contains("POSTGRES_PASSWORD: ${POSTGRES_PASSWORD}"));
+    }
+
+    #[test]
+    fn test_kafka_advertises_the_resolved_host_port() {
+        let services = vec![selected("kafka", vec![9093])];
+
+        let content = generate_compose_content("myproject", "myproject-net", &services).unwrap();
+
+        assert!(content.contains("9093:9092"));
+        assert!(content.contains("KAFKA_ADVERTISED_LISTENERS: PLAINTEXT://localhost:9093"));
+    }
+
+    #[test]
+    fn test_template_routes() {
+        let services = vec![
+            selected("postgres", vec![5432]),
+            selected("mailpit", vec![1025, 8026]),
+        ];
+
+        assert_eq!(
+            template_routes("shop", &services),
+            vec![("mail".to_string(), "shop-mailpit:8025".to_string())]
+        );
     }
 
     #[test]
     fn test_generate_compose_content_network_external() {
-        let services = vec![SelectedService {
-            template: &AVAILABLE_SERVICES[0],
-            host_port: 5432,
-        }];
+        let services = vec![selected("postgres", vec![5432])];
 
-        let content = generate_compose_content("myproject", "myproject-net", &services);
+        let content = generate_compose_content("myproject", "myproject-net", &services).unwrap();
 
         // Verify network is marked as external
         assert!(content.contains("external: true"));
         assert!(content.contains("myproject-net:"));
     }
+
+    #[test]
+    fn test_generate_compose_content_template_fields() {
+        let content = r#"
+image = "registry.example.com/api:1.4"
+ports = [{ host = 8081, container = 8080 }]
+command = ["serve", "--port", "8080"]
+secrets = ["API_TOKEN"]
+
+[environment]
+GREETING = "hello: world"
+
+[healthcheck]
+test = "curl -f http://localhost:8080/health"
+interval = "10s"
+retries = 3
+"#;
+        let template = ServiceTemplate::parse(content, "api", Default::default()).unwrap();
+        let mut service = SelectedService::new(template, vec![8082]);
+        service
+            .env_vars
+            .insert("API_TOKEN".to_string(), "API_API_TOKEN".to_string());
+        let services = vec![service];
+
+        let content = generate_compose_content("myproject", "myproject-net", &services).unwrap();
+        let parsed: serde_yaml::Value = serde_yaml::from_str(&content).unwrap();
+        let api = &parsed["services"]["api"];
+
+        assert_eq!(api["ports"][0], "8082:8080");
+        assert_eq!(api["command"][1], "--port");
+        assert_eq!(api["environment"]["GREETING"], "hello: world");
+        assert_eq!(api["environment"]["API_TOKEN"], "${API_API_TOKEN}");
+        assert_eq!(api["healthcheck"]["test"][0], "CMD-SHELL");
+        assert_eq!(api["healthcheck"]["retries"], 3);
+        assert!(parsed.get("volumes").is_none());
+        assert_eq!(api["labels"][OMD_TEMPLATE_LABEL], "api");
+    }
+
+    #[test]
+    fn test_depends_on_selected_services_only() {
+        let services = vec![
+            selected("postgres", vec![5432]),
+            selected("pgadmin", vec![5050]),
+        ];
+        let compose = build_compose_file("shop", "shop-net", &services);
+        assert_eq!(
+            compose.services["pgadmin"].depends_on["postgres"].condition,
+            DependencyCondition::ServiceHealthy
+        );
+
+        let compose = build_compose_file("shop", "shop-net", &[selected("pgadmin", vec![5050])]);
+        assert!(compose.services["pgadmin"].depends_on.is_empty());
+    }
 }
@@ -0,0 +1,496 @@
+//! Generated compose override
+//!
+//! `omd project up` writes settings omd manages for a project to
+//! ~/.oh-my-dockers/compose/<project>.override.yml and passes it to docker
+//! compose after the project's own files. The project's compose files are
+//! never modified for this.
+//!
+//! Global [defaults] are only added where a service does not set the value
+//! itself, because later compose files override earlier ones. Shared networks
+//! from `[network] shared` are added to a service's networks and declared as
+//! external.
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use serde_yaml::{Mapping, Value};
+
+use super::config::{DefaultKind, NetworkConfig, Project, ProjectDefaults};
+use crate::config::{DefaultSettings, GlobalConfig};
+
+/// Header of the generated file
+const HEADER: &str = "# Generated by 'omd project up'. Do not edit: changes are overwritten.\n# Configure [defaults] in config.toml, or [defaults] and [network] in omd.toml instead.\n";
+
+/// What a service already sets in the project's compose files
+#[derive(Debug, Default)]
+struct ServiceSettings {
+    env: HashSet<String>,
+    labels: HashSet<String>,
+    restart: bool,
+    logging: bool,
+    limits: bool,
+    /// Declares `networks` (joins only those, not the default network)
+    networks: bool,
+    /// Uses `network_mode` and cannot join networks
+    network_mode: bool,
+}
+
+/// A written override and what it applies
+#[derive(Debug)]
+pub struct Override {
+    pub path: PathBuf,
+    /// Kinds of defaults applied to at least one service
+    pub applied: Vec<DefaultKind>,
+    /// Number of services defaults were applied to
+    pub services: usize,
+    /// Services attached to the shared networks
+    pub attached: Vec<String>,
+}
+
+/// Write the project's override, or remove a stale one if there is nothing to apply
+pub fn write_override(project: &Project, global_config: &GlobalConfig) -> Result<Option<Override>> {
+    let paths: Vec<PathBuf> = project
+        .compose_files()
+        .iter()
+        .map(|file| project.dir.join(file))
+        .collect();
+    let services = read_service_settings(&paths)?;
+    let path = project.override_path()?;
+
+    let (mut content, applied) =
+        build_override(&services, &global_config.defaults, &project.config.defaults);
+    let service_count = content
+        .get("services")
+        .and_then(Value::as_mapping)
+        .map_or(0, Mapping::len);
+    let attached = add_shared_networks(&mut content, &services, &project.config.network)?;
+
+    if content.is_empty() {
+        if path.exists() {
+            fs::remove_file(&path).context(format!("Failed to remove {}", path.display()))?;
+        }
+        return Ok(None);
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).context(format!("Failed to create {}", parent.display()))?;
+    }
+    let yaml = serde_yaml::to_string(&Value::Mapping(content))
+        .context("Failed to serialize compose override")?;
+    fs::write(&path, format!("{}{}", HEADER, yaml))
+        .context(format!("Failed to write {}", path.display()))?;
+
+    Ok(Some(Override {
+        path,
+        applied,
+        services: service_count,
+        attached,
+    }))
+}
+
+/// Collect the settings of each service across the compose files
+fn read_service_settings(paths: &[PathBuf]) -> Result<BTreeMap<String, ServiceSettings>> {
+    let mut services: BTreeMap<String, ServiceSettings> = BTreeMap::new();
+
+    for path in paths {
+        let yaml = read_yaml(path)?;
+        let Some(mapping) = yaml.get("services").and_then(Value::as_mapping) else {
+            continue;
+        };
+
+        for (name, service) in mapping {
+            let Some(name) = name.as_str() else {
+                continue;
+            };
+            let settings = services.entry(name.to_string()).or_default();
+
+            settings
+                .env
+                .extend(variable_names(service.get("environment")));
+            settings
+                .labels
+                .extend(variable_names(service.get("labels")));
+            settings.restart |= service.get("restart").is_some();
+            settings.logging |= service.get("logging").is_some();
+            settings.limits |= ["mem_limit", "cpus", "pids_limit"]
+                .iter()
+                .any(|key| service.get(*key).is_some())
+                || service
+                    .get("deploy")
+                    .and_then(|deploy| deploy.get("resources"))
+                    .and_then(|resources| resources.get("limits"))
+                    .is_some();
+            settings.networks |= service.get("networks").is_some();
+            settings.network_mode |= service.get("network_mode").is_some();
+        }
+    }
+
+    Ok(services)
+}
+
+fn read_yaml(path: &Path) -> Result<Value> {
+    let content = fs::read_to_string(path)
+        .context(format!("Failed to read docker-compose file: {:?}", path))?;
+    serde_yaml::from_str(&content).context(format!("Failed to parse {}", path.display()))
+}
+
+/// Names in an `environment` or `labels` entry
+/// Both the mapping form and the list form (`KEY=value`) are accepted
+fn variable_names(value: Option<&Value>) -> Vec<String> {
+    match value {
+        Some(Value::Mapping(mapping)) => mapping
+            .keys()
+            .filter_map(|key| key.as_str().map(str::to_string))
+            .collect(),
+        Some(Value::Sequence(items)) => items
+            .iter()
+            .filter_map(Value::as_str)
+            .map(|item| item.split('=').next().unwrap_or(item).to_string())
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Build the override content for the services
+/// Returns the document and the kinds of defaults it applies
+fn build_override(
+    services: &BTreeMap<String, ServiceSettings>,
+    defaults: &DefaultSettings,
+    project: &ProjectDefaults,
+) -> (Mapping, Vec<DefaultKind>) {
+    let mut env: Vec<(&str, &str)> = Vec::new();
+    if let Some(timezone) = &defaults.timezone
+        && !defaults.env.contains_key("TZ")
+    {
+        env.push(("TZ", timezone));
+    }
+    env.extend(defaults.env.iter().map(|(k, v)| (k.as_str(), v.as_str())));
+
+    let mut applied = Vec::new();
+    let mut mark = |kind: DefaultKind| {
+        if !applied.contains(&kind) {
+            applied.push(kind);
+        }
+    };
+
+    let mut service_overrides = Mapping::new();
+    for (name, settings) in services {
+        let mut service = Mapping::new();
+
+        if project.applies(DefaultKind::Env, name) {
+            let missing = missing_entries(&env, &settings.env);
+            if !missing.is_empty() {
+                service.insert("environment".into(), Value::Mapping(missing));
+                mark(DefaultKind::Env);
+            }
+        }
+
+        if project.applies(DefaultKind::Labels, name) {
+            let labels: Vec<(&str, &str)> = defaults
+                .labels
+                .iter()
+                .map(|(k, v)| (k.as_str(), v.as_str()))
+                .collect();
+            let missing = missing_entries(&labels, &settings.labels);
+            if !missing.is_empty() {
+                service.insert("labels".into(), Value::Mapping(missing));
+                mark(DefaultKind::Labels);
+            }
+        }
+
+        if let Some(restart) = &defaults.restart
+            && !settings.restart
+            && project.applies(DefaultKind::Restart, name)
+        {
+            service.insert("restart".into(), restart.as_str().into());
+            mark(DefaultKind::Restart);
+        }
+
+        if let Some(logging) = &defaults.logging
+            && !settings.logging
+            && project.applies(DefaultKind::Logging, name)
+        {
+            let mut value = Mapping::new();
+            value.insert("driver".into(), logging.driver.as_str().into());
+            if !logging.options.is_empty() {
+                let options: Mapping = logging
+                    .options
+                    .iter()
+                    .map(|(k, v)| (Value::from(k.as_str()), Value::from(v.as_str())))
+                    .collect();
+                value.insert("options".into(), Value::Mapping(options));
+            }
+            service.insert("logging".into(), Value::Mapping(value));
+            mark(DefaultKind::Logging);
+        }
+
+        if let Some(limits) = &defaults.limits
+            && !settings.limits
+            && project.applies(DefaultKind::Limits, name)
+        {
+            let mut value = Mapping::new();
+            if let Some(cpus) = limits.cpus {
+                value.insert("cpus".into(), cpus.to_string().into());
+            }
+            if let Some(memory) = &limits.memory {
+                value.insert("memory".into(), memory.as_str().into());
+            }
+            if let Some(pids) = limits.pids {
+                value.insert("pids".into(), pids.into());
+            }
+            if !value.is_empty() {
+                let mut resources = Mapping::new();
+                resources.insert("limits".into(), Value::Mapping(value));
+                let mut deploy = Mapping::new();
+                deploy.insert("resources".into(), Value::Mapping(resources));
+                service.insert("deploy".into(), Value::Mapping(deploy));
+                mark(DefaultKind::Limits);
+            }
+        }
+
+        if !service.is_empty() {
+            service_overrides.insert(name.as_str().into(), Value::Mapping(service));
+        }
+    }
+
+    let mut content = Mapping::new();
+    if !service_overrides.is_empty() {
+        content.insert("services".into(), Value::Mapping(service_overrides));
+    }
+    (content, applied)
+}
+
+/// Attach services to the shared networks and declare those networks as external
+/// A service without `networks` is on the project's default network; it is
+/// listed explicitly so joining a shared network does not remove it
+/// Returns the attached services
+fn add_shared_networks(
+    content: &mut Mapping,
+    services: &BTreeMap<String, ServiceSettings>,
+    network: &NetworkConfig,
+) -> Result<Vec<String>> {
+    if network.shared.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let unknown: Vec<&str> = network
+        .shared_services
+        .iter()
+        .filter(|service| !services.contains_key(*service))
+        .map(String::as_str)
+        .collect();
+    if !unknown.is_empty() {
+        anyhow::bail!(
+            "[network] shared_services lists unknown services: {}",
+            unknown.join(", ")
+        );
+    }
+
+    let mut attached = Vec::new();
+    let service_overrides = content
+        .entry("services".into())
+        .or_insert_with(|| Value::Mapping(Mapping::new()));
+    let Some(service_overrides) = service_overrides.as_mapping_mut() else {
+        anyhow::bail!("Invalid compose override: 'services' is not a mapping");
+    };
+
+    for (name, settings) in services {
+        if settings.network_mode || !network.shares(name) {
+            continue;
+        }
+
+        let mut networks = Mapping::new();
+        if !settings.networks {
+            networks.insert("default".into(), Value::Mapping(Mapping::new()));
+        }
+        for shared in &network.shared {
+            networks.insert(shared.as_str().into(), Value::Mapping(Mapping::new()));
+        }
+
+        let service = service_overrides
+            .entry(name.as_str().into())
+            .or_insert_with(|| Value::Mapping(Mapping::new()));
+        if let Some(service) = service.as_mapping_mut() {
+            service.insert("networks".into(), Value::Mapping(networks));
+        }
+        attached.push(name.clone());
+    }
+
+    if service_overrides.is_empty() {
+        content.remove("services");
+        return Ok(attached);
+    }
+
+    let declared: Mapping = network
+        .shared
+        .iter()
+        .map(|shared| {
+            let mut declaration = Mapping::new();
+            declaration.insert("external".into(), true.into());
+            (Value::from(shared.as_str()), Value::Mapping(declaration))
+        })
+        .collect();
+    content.insert("networks".into(), Value::Mapping(declared));
+
+    Ok(attached)
+}
+
+/// Entries whose names are not in `existing`
+fn missing_entries(entries: &[(&str, &str)], existing: &HashSet<String>) -> Mapping {
+    entries
+        .iter()
+        .filter(|(name, _)| !existing.contains(*name))
+        .map(|(name, value)| (Value::from(*name), Value::from(*value)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::LimitDefaults;
+
+    fn settings(yaml: &str) -> BTreeMap<String, ServiceSettings> {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("docker-compose.yml");
+        fs::write(&path, yaml).unwrap();
+        read_service_settings(&[path]).unwrap()
+    }
+
+    fn defaults() -> DefaultSettings {
+        DefaultSettings {
+            timezone: Some("Europe/Berlin".to_string()),
+            env: [("LOG_LEVEL".to_string(), "debug".to_string())].into(),
+            labels: [("team".to_string(), "platform".to_string())].into(),
+            restart: Some("unless-stopped".to_string()),
+            logging: None,
+            limits: Some(LimitDefaults {
+                cpus: Some(1.5),
+                memory: Some("1g".to_string()),
+                pids: None,
+            }),
+        }
+    }
+
+    #[test]
+    fn test_defaults_do_not_replace_service_settings() {
+        let services = settings(
+            r#"
+services:
+  web:
+    image: nginx
+    environment:
+      - TZ=UTC
+    restart: always
+  worker:
+    image: worker
+    labels:
+      team: payments
+    mem_limit: 256m
+"#,
+        );
+
+        let (content, applied) =
+            build_override(&services, &defaults(), &ProjectDefaults::default());
+        let yaml = serde_yaml::to_string(&content).unwrap();
+
+        assert_eq!(
+            yaml,
+            r#"services:
+  web:
+    environment:
+      LOG_LEVEL: debug
+    labels:
+      team: platform
+    deploy:
+      resources:
+        limits:
+          cpus: '1.5'
+          memory: 1g
+  worker:
+    environment:
+      TZ: Europe/Berlin
+      LOG_LEVEL: debug
+    restart: unless-stopped
+"#
+        );
+        assert_eq!(
+            applied,
+            vec![
+                DefaultKind::Env,
+                DefaultKind::Labels,
+                DefaultKind::Limits,
+                DefaultKind::Restart
+            ]
+        );
+    }
+
+    #[test]
+    fn test_project_opt_outs() {
+        let services =
+            settings("services:\n  web:\n    image: nginx\n  db:\n    image: postgres\n");
+
+        let project: ProjectDefaults =
+            toml::from_str("skip = [\"env\", \"limits\"]\nexclude_services = [\"db\"]\n").unwrap();
+        let (content, applied) = build_override(&services, &defaults(), &project);
+        let yaml = serde_yaml::to_string(&content).unwrap();
+        assert_eq!(
+            yaml,
+            "services:\n  web:\n    labels:\n      team: platform\n    restart: unless-stopped\n"
+        );
+        assert_eq!(applied, vec![DefaultKind::Labels, DefaultKind::Restart]);
+
+        let project: ProjectDefaults = toml::from_str("enabled = false\n").unwrap();
+        let (content, applied) = build_override(&services, &defaults(), &project);
+        assert!(content.is_empty());
+        assert!(applied.is_empty());
+    }
+
+    #[test]
+    fn test_shared_networks() {
+        let services = settings(
+            r#"
+services:
+  api:
+    image: api
+  db:
+    image: postgres
+    networks:
+      - backend
+  tool:
+    image: tool
+    network_mode: host
+"#,
+        );
+
+        let network: NetworkConfig =
+            toml::from_str("name = \"app-net\"\nshared = [\"infra-net\"]\n").unwrap();
+        let mut content = Mapping::new();
+        let attached = add_shared_networks(&mut content, &services, &network).unwrap();
+        assert_eq!(attached, vec!["api", "db"]);
+        assert_eq!(
+            serde_yaml::to_string(&content).unwrap(),
+            r#"services:
+  api:
+    networks:
+      default: {}
+      infra-net: {}
+  db:
+    networks:
+      infra-net: {}
+networks:
+  infra-net:
+    external: true
+"#
+        );
+
+        let network: NetworkConfig = toml::from_str(
+            "name = \"app-net\"\nshared = [\"infra-net\"]\nshared_services = [\"cache\"]\n",
+        )
+        .unwrap();
+        assert!(add_shared_networks(&mut Mapping::new(), &services, &network).is_err());
+    }
+}
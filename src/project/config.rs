@@ -2,18 +2,53 @@
 //!
 //! This module handles loading and parsing project-level configuration files.
 
-use std::{collections::HashMap, env, fs, path::Path};
+use std::{
+    collections::HashMap,
+    env, fs,
+    net::IpAddr,
+    path::{Path, PathBuf},
+    process::Command,
+};
 
 use anyhow::{Context, Result};
+use colored::Colorize;
+use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
+use toml_edit::DocumentMut;
+
+use super::migration;
+use crate::{config::get_config_dir, docker::compose::ComposeInfo};
+
+/// Project configuration file name
+pub const PROJECT_CONFIG_FILE: &str = "omd.toml";
+
+/// Current omd.toml format version
+/// Increment this and add a step to `project::migration` when changing the format
+pub const PROJECT_CONFIG_VERSION: u32 = 1;
 
 /// Project configuration from omd.toml
 #[derive(Debug, Deserialize, Serialize)]
 pub struct ProjectConfig {
+    /// Format version (see [`PROJECT_CONFIG_VERSION`])
+    #[serde(default)]
+    pub version: u32,
     pub project: ProjectInfo,
     pub network: NetworkConfig,
     #[serde(default)]
     pub caddy: CaddyConfig,
+    #[serde(default)]
+    pub hosts: HostsConfig,
+    /// Variables in .env generated for each template service
+    ///   [credentials]
+    ///   postgres = ["POSTGRES_PASSWORD"]
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub credentials: IndexMap<String, Vec<String>>,
+    /// Named overrides selected with `omd project up --profile <name>`
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub profiles: IndexMap<String, ProfileConfig>,
+    /// Opt-outs from the [defaults] of config.toml
+    #[serde(default)]
+    pub defaults: ProjectDefaults,
 }
 
 /// Default docker-compose file name
@@ -38,6 +73,20 @@ pub struct ProjectInfo {
 #[derive(Debug, Deserialize, Serialize)]
 pub struct NetworkConfig {
     pub name: String,
+    /// Networks shared with other projects, attached through the compose override
+    ///   shared = ["infra-net"]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub shared: Vec<String>,
+    /// Services attached to the shared networks (empty = all services)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub shared_services: Vec<String>,
+}
+
+impl NetworkConfig {
+    /// Whether a service joins the shared networks
+    pub fn shares(&self, service: &str) -> bool {
+        self.shared_services.is_empty() || self.shared_services.iter().any(|s| s == service)
+    }
 }
 
 /// Caddy configuration for the project
@@ -52,23 +101,391 @@ pub struct CaddyConfig {
     pub routes: HashMap<String, String>,
 }
 
+/// /etc/hosts configuration for the project
+#[derive(Debug, Deserialize, Serialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct HostsConfig {
+    /// Addresses the project domains resolve to
+    /// Defaults to both loopback addresses (127.0.0.1 and ::1)
+    ///   [hosts]
+    ///   addresses = ["192.168.1.20"]
+    #[serde(default)]
+    pub addresses: Vec<IpAddr>,
+    /// Extra host names written with the project domains, e.g. for services
+    /// reached on their host ports
+    ///   aliases = ["shop.test", "db.shop.local"]
+    #[serde(default)]
+    pub aliases: Vec<String>,
+}
+
+/// Which global defaults the project receives
+///   [defaults]
+///   skip = ["limits"]
+///   exclude_services = ["postgres"]
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProjectDefaults {
+    /// Apply the [defaults] of config.toml at all
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Kinds of defaults not applied
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skip: Vec<DefaultKind>,
+    /// Services that receive no defaults
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude_services: Vec<String>,
+}
+
+impl Default for ProjectDefaults {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            skip: Vec::new(),
+            exclude_services: Vec::new(),
+        }
+    }
+}
+
+impl ProjectDefaults {
+    /// Whether a kind of default applies to a service
+    pub fn applies(&self, kind: DefaultKind, service: &str) -> bool {
+        self.enabled
+            && !self.skip.contains(&kind)
+            && !self.exclude_services.iter().any(|s| s == service)
+    }
+}
+
+/// A kind of global default
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DefaultKind {
+    /// Environment variables, including TZ
+    Env,
+    Labels,
+    Restart,
+    Logging,
+    Limits,
+}
+
+impl DefaultKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DefaultKind::Env => "env",
+            DefaultKind::Labels => "labels",
+            DefaultKind::Restart => "restart",
+            DefaultKind::Logging => "logging",
+            DefaultKind::Limits => "limits",
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Overrides of a profile
+///   [profiles.backend]
+///   compose_files = ["docker-compose.yml", "docker-compose.backend.yml"]
+///   compose_profiles = ["backend"]
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct ProfileConfig {
+    /// Replaces [project] domain
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub domain: Option<String>,
+    /// Replace [project] compose_file; later files override earlier ones
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub compose_files: Vec<String>,
+    /// Docker Compose profiles to activate
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub compose_profiles: Vec<String>,
+    /// Replaces [caddy.routes]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub routes: Option<HashMap<String, String>>,
+}
+
 /// Load project configuration from a specific path
+/// Older formats are migrated in memory; the file itself is left unchanged
 pub fn load_project_config_from_path(path: &Path) -> Result<ProjectConfig> {
     let content = fs::read_to_string(path)
         .context(format!("Failed to read project configuration: {:?}", path))?;
+    let mut doc: DocumentMut = content
+        .parse()
+        .context("Failed to parse project configuration")?;
+
+    let version = migration::file_version(&doc)?;
+    if version > PROJECT_CONFIG_VERSION {
+        println!(
+            "{} {} uses format version {}, newer than this omd supports (version {}). \
+             Update omd; settings it does not know may be ignored.",
+            "⚠".yellow(),
+            path.display(),
+            version,
+            PROJECT_CONFIG_VERSION
+        );
+    } else {
+        migration::migrate(&mut doc)?;
+    }
+
+    toml::from_str(&doc.to_string()).context("Failed to parse project configuration")
+}
 
-    toml::from_str(&content).context("Failed to parse project configuration")
+/// A project configuration and the directory of its omd.toml
+///
+/// Relative paths in the configuration (such as `compose_file`) are resolved
+/// against `dir`, which is also the working directory for docker compose.
+#[derive(Debug)]
+pub struct Project {
+    pub config: ProjectConfig,
+    pub dir: PathBuf,
+    /// Active profile (see [`Project::select_profile`])
+    pub profile: Option<String>,
+    /// Compose project name if it is not the omd project name
+    /// (see [`Project::resolve_compose_name`])
+    pub compose_project: Option<String>,
 }
 
-/// Load project configuration from current directory
-pub fn load_project_config() -> Result<ProjectConfig> {
-    let config_path = Path::new("omd.toml");
+impl Project {
+    /// Apply the overrides of a profile to the configuration
+    pub fn select_profile(&mut self, name: &str) -> Result<()> {
+        let Some(profile) = self.config.profiles.get(name).cloned() else {
+            let available: Vec<&str> = self.config.profiles.keys().map(String::as_str).collect();
+            if available.is_empty() {
+                anyhow::bail!("Profile '{}' not found: omd.toml defines no profiles", name);
+            }
+            anyhow::bail!(
+                "Profile '{}' not found in omd.toml. Available profiles: {}",
+                name,
+                available.join(", ")
+            );
+        };
+
+        if let Some(domain) = profile.domain {
+            self.config.project.domain = domain;
+        }
+        if let Some(routes) = profile.routes {
+            self.config.caddy.routes = routes;
+        }
+        self.profile = Some(name.to_string());
+        Ok(())
+    }
+
+    /// Settings of the active profile
+    fn active_profile(&self) -> Option<&ProfileConfig> {
+        self.profile
+            .as_ref()
+            .and_then(|name| self.config.profiles.get(name))
+    }
+
+    /// Compose files of the active profile, or the project's compose file
+    pub fn compose_files(&self) -> Vec<String> {
+        match self.active_profile() {
+            Some(profile) if !profile.compose_files.is_empty() => profile.compose_files.clone(),
+            _ => vec![self.config.project.compose_file.clone()],
+        }
+    }
+
+    /// Docker Compose profiles of the active profile
+    pub fn compose_profiles(&self) -> Vec<String> {
+        self.active_profile()
+            .map(|profile| profile.compose_profiles.clone())
+            .unwrap_or_default()
+    }
+
+    /// Services of the compose files, without those of inactive Compose profiles
+    pub fn compose_info(&self) -> Result<ComposeInfo> {
+        let paths: Vec<PathBuf> = self
+            .compose_files()
+            .iter()
+            .map(|file| self.dir.join(file))
+            .collect();
+        let mut info = ComposeInfo::parse_files(&paths)?;
+        info.retain_profiles(&self.compose_profiles());
+        Ok(info)
+    }
+
+    /// Arguments selecting the Compose project name, the compose files, the
+    /// generated override and the profiles, e.g.
+    /// `-p shop -f docker-compose.yml -f docker-compose.debug.yml --profile debug`
+    pub fn compose_args(&self) -> Vec<String> {
+        let mut args = vec!["-p".to_string(), self.compose_name().to_string()];
+        for file in self.compose_files() {
+            args.push("-f".to_string());
+            args.push(file);
+        }
+        if let Ok(path) = self.override_path()
+            && path.exists()
+        {
+            args.push("-f".to_string());
+            args.push(path.to_string_lossy().to_string());
+        }
+        for profile in self.compose_profiles() {
+            args.push("--profile".to_string());
+            args.push(profile);
+        }
+        args
+    }
+
+    /// Compose project name, which also names the containers omd routes to
+    pub fn compose_name(&self) -> &str {
+        self.compose_project
+            .as_deref()
+            .unwrap_or(&self.config.project.name)
+    }
+
+    /// Choose the Compose project name
+    ///
+    /// The name recorded in the registry is kept. Projects registered without
+    /// one keep the name docker compose chose itself if it still has
+    /// containers or volumes, so their named volumes stay attached; otherwise
+    /// the omd project name is used.
+    pub fn resolve_compose_name(&mut self, recorded: Option<&str>) {
+        let name = match recorded {
+            Some(name) => name.to_string(),
+            None => match self.legacy_compose_name() {
+                Some(legacy) if self.compose_project_in_use(&legacy) => legacy,
+                _ => self.config.project.name.clone(),
+            },
+        };
+        self.compose_project = (name != self.config.project.name).then_some(name);
+    }
+
+    /// Whether docker has containers of this project, or volumes, under a
+    /// Compose project name
+    fn compose_project_in_use(&self, compose_name: &str) -> bool {
+        let project_filter = format!("label=com.docker.compose.project={}", compose_name);
+        let mut containers = vec!["ps", "-a", "--filter", &project_filter];
+        let working_dir_filter = self
+            .compose_files()
+            .first()
+            .and_then(|file| fs::canonicalize(self.dir.join(file).parent()?).ok())
+            .map(|dir| {
+                format!(
+                    "label=com.docker.compose.project.working_dir={}",
+                    dir.display()
+                )
+            });
+        if let Some(filter) = &working_dir_filter {
+            containers.extend(["--filter", filter]);
+        }
+        containers.extend(["--format", "{{.Names}}"]);
+        let volumes = [
+            "volume",
+            "ls",
+            "--filter",
+            &project_filter,
+            "--format",
+            "{{.Name}}",
+        ];
+
+        [&containers[..], &volumes[..]].iter().any(|args| {
+            Command::new("docker")
+                .args(*args)
+                .output()
+                .is_ok_and(|output| {
+                    output.status.success() && !output.stdout.trim_ascii().is_empty()
+                })
+        })
+    }
+
+    /// Compose project name docker compose chose before omd passed `-p`: the
+    /// top-level `name` of the first compose file, or else the name of that
+    /// file's directory, normalized like docker compose does
+    /// None if it is the omd project name
+    pub fn legacy_compose_name(&self) -> Option<String> {
+        let first = self.dir.join(self.compose_files().first()?);
+        let declared = fs::read_to_string(&first)
+            .ok()
+            .and_then(|content| serde_yaml::from_str::<serde_yaml::Value>(&content).ok())
+            .and_then(|yaml| yaml.get("name")?.as_str().map(str::to_string));
+        let name = match declared {
+            Some(name) => name,
+            None => {
+                let dir = first.parent()?;
+                let dir = fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf());
+                dir.file_name()?.to_string_lossy().to_string()
+            }
+        };
+
+        let normalized: String = name
+            .to_lowercase()
+            .chars()
+            .filter(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || *c == '-' || *c == '_')
+            .collect();
+        let normalized = normalized.trim_start_matches(['-', '_']).to_string();
+
+        (!normalized.is_empty() && normalized != self.config.project.name).then_some(normalized)
+    }
+
+    /// Path of the compose override generated by `omd project up`
+    pub fn override_path(&self) -> Result<PathBuf> {
+        Ok(get_config_dir()?
+            .join("compose")
+            .join(format!("{}.override.yml", self.config.project.name)))
+    }
+
+    /// Path of the project's omd.toml
+    pub fn config_path(&self) -> PathBuf {
+        self.dir.join(PROJECT_CONFIG_FILE)
+    }
+
+    /// Path of the project's docker-compose file
+    pub fn compose_path(&self) -> PathBuf {
+        self.dir.join(&self.config.project.compose_file)
+    }
+}
 
-    if !config_path.exists() {
-        anyhow::bail!("No omd.toml found in current directory. Run 'omd init' to create one.");
+/// Find omd.toml in `start` or one of its parent directories
+///
+/// Like git, the search stops at the repository root (a directory containing
+/// `.git`) or at the filesystem root.
+pub fn find_project_config(start: &Path) -> Option<PathBuf> {
+    for dir in start.ancestors() {
+        let candidate = dir.join(PROJECT_CONFIG_FILE);
+        if candidate.is_file() {
+            return Some(candidate);
+        }
+        if dir.join(".git").exists() {
+            break;
+        }
     }
+    None
+}
+
+/// Find omd.toml from the current directory and print which one is used
+pub fn locate_project_config() -> Result<PathBuf> {
+    let current_dir = env::current_dir().context("Failed to get current directory")?;
+
+    let Some(path) = find_project_config(&current_dir) else {
+        anyhow::bail!(
+            "No omd.toml found in {} or its parent directories. Run 'omd init' to create one.",
+            current_dir.display()
+        );
+    };
+
+    println!("{} Using {}", "ℹ".blue(), path.display());
+    Ok(path)
+}
 
-    load_project_config_from_path(config_path)
+/// Load the project whose omd.toml is at `path`
+pub fn load_project_at(path: &Path) -> Result<Project> {
+    let config = load_project_config_from_path(path)?;
+    let dir = path
+        .parent()
+        .context("omd.toml has no parent directory")?
+        .to_path_buf();
+
+    Ok(Project {
+        config,
+        dir,
+        profile: None,
+        compose_project: None,
+    })
+}
+
+/// Load the project containing the current directory
+pub fn load_project() -> Result<Project> {
+    load_project_at(&locate_project_config()?)
 }
 
 /// Get the current directory name (for default project naming)
@@ -104,9 +521,15 @@ api = "bff:8080"
 "#;
         let config: ProjectConfig = toml::from_str(toml_str).unwrap();
         println!("Correct format - Routes: {:?}", config.caddy.routes);
-        
-        assert!(!config.caddy.routes.is_empty(), "Routes should not be empty");
-        assert_eq!(config.caddy.routes.get("api"), Some(&"bff:8080".to_string()));
+
+        assert!(
+            !config.caddy.routes.is_empty(),
+            "Routes should not be empty"
+        );
+        assert_eq!(
+            config.caddy.routes.get("api"),
+            Some(&"bff:8080".to_string())
+        );
     }
 
     #[test]
@@ -126,10 +549,197 @@ api = "bff:8080"
 "#;
         let result: Result<ProjectConfig, _> = toml::from_str(toml_str);
         println!("Wrong format result: {:?}", result);
-        
+
         // Should fail with unknown field error, guiding user to correct format
         assert!(result.is_err(), "Should fail with unknown field 'api'");
         let err_msg = result.unwrap_err().to_string();
-        assert!(err_msg.contains("unknown field"), "Error should mention unknown field");
+        assert!(
+            err_msg.contains("unknown field"),
+            "Error should mention unknown field"
+        );
+    }
+
+    #[test]
+    fn test_parse_hosts_addresses() {
+        let toml_str = r#"
+[project]
+name = "sapphire"
+domain = "sapphire.local"
+
+[network]
+name = "sapphire-net"
+
+[hosts]
+addresses = ["192.168.1.20", "fd00::20"]
+"#;
+        let config: ProjectConfig = toml::from_str(toml_str).unwrap();
+
+        assert_eq!(config.hosts.addresses.len(), 2);
+        assert!(config.hosts.addresses[1].is_ipv6());
+
+        let invalid = toml_str.replace("192.168.1.20", "not-an-ip");
+        assert!(toml::from_str::<ProjectConfig>(&invalid).is_err());
+    }
+
+    #[test]
+    fn test_find_project_config_in_parents() {
+        let root = tempfile::tempdir().unwrap();
+        let repo = root.path().join("repo");
+        let nested = repo.join("frontend").join("src");
+        fs::create_dir_all(&nested).unwrap();
+        fs::create_dir(repo.join(".git")).unwrap();
+
+        // Not found above the repository root
+        fs::write(root.path().join(PROJECT_CONFIG_FILE), "").unwrap();
+        assert_eq!(find_project_config(&nested), None);
+
+        // Found from a subdirectory
+        fs::write(repo.join(PROJECT_CONFIG_FILE), "").unwrap();
+        assert_eq!(
+            find_project_config(&nested),
+            Some(repo.join(PROJECT_CONFIG_FILE))
+        );
+
+        // The nearest one wins
+        fs::write(nested.join(PROJECT_CONFIG_FILE), "").unwrap();
+        assert_eq!(
+            find_project_config(&nested),
+            Some(nested.join(PROJECT_CONFIG_FILE))
+        );
+    }
+
+    #[test]
+    fn test_select_profile() {
+        let toml_str = r#"
+[project]
+name = "shop"
+domain = "shop.local"
+
+[network]
+name = "shop-net"
+
+[caddy.routes]
+app = "shop-web:3000"
+
+[profiles.backend]
+domain = "api.shop.local"
+compose_files = ["docker-compose.yml", "docker-compose.backend.yml"]
+compose_profiles = ["backend"]
+
+[profiles.backend.routes]
+api = "shop-api:8080"
+
+[profiles.debug]
+compose_profiles = ["debug"]
+"#;
+        let load = || Project {
+            config: toml::from_str(toml_str).unwrap(),
+            dir: PathBuf::from("/srv/shop"),
+            profile: None,
+            compose_project: None,
+        };
+
+        let project = load();
+        assert_eq!(
+            project.compose_args(),
+            vec!["-p", "shop", "-f", "docker-compose.yml"]
+        );
+
+        let mut project = load();
+        project.select_profile("backend").unwrap();
+        assert_eq!(project.config.project.domain, "api.shop.local");
+        assert_eq!(project.config.caddy.routes.len(), 1);
+        assert!(project.config.caddy.routes.contains_key("api"));
+        assert_eq!(
+            project.compose_args(),
+            vec![
+                "-p",
+                "shop",
+                "-f",
+                "docker-compose.yml",
+                "-f",
+                "docker-compose.backend.yml",
+                "--profile",
+                "backend"
+            ]
+        );
+
+        // Unset overrides keep the base values
+        let mut project = load();
+        project.select_profile("debug").unwrap();
+        assert_eq!(project.config.project.domain, "shop.local");
+        assert!(project.config.caddy.routes.contains_key("app"));
+        assert_eq!(
+            project.compose_args(),
+            vec![
+                "-p",
+                "shop",
+                "-f",
+                "docker-compose.yml",
+                "--profile",
+                "debug"
+            ]
+        );
+
+        let err = load().select_profile("full").unwrap_err().to_string();
+        assert!(err.contains("backend, debug"));
+    }
+
+    #[test]
+    fn test_legacy_compose_name() {
+        let dir = tempfile::tempdir().unwrap();
+        let project_dir = dir.path().join("My Shop");
+        fs::create_dir(&project_dir).unwrap();
+        fs::write(
+            project_dir.join("docker-compose.yml"),
+            "services:\n  web:\n    image: nginx\n",
+        )
+        .unwrap();
+
+        let load = |name: &str| {
+            Project {
+            config: toml::from_str(&format!(
+                "[project]\nname = \"{}\"\ndomain = \"shop.local\"\n\n[network]\nname = \"shop-net\"\n",
+                name
+            ))
+            .unwrap(),
+            dir: project_dir.clone(),
+            profile: None,
+            compose_project: None,
+        }
+        };
+
+        // Directory name, lowercased without invalid characters
+        assert_eq!(
+            load("shop").legacy_compose_name().as_deref(),
+            Some("myshop")
+        );
+        assert_eq!(load("myshop").legacy_compose_name(), None);
+
+        // A top-level name in the compose file wins
+        fs::write(
+            project_dir.join("docker-compose.yml"),
+            "name: shop\nservices:\n  web:\n    image: nginx\n",
+        )
+        .unwrap();
+        assert_eq!(load("shop").legacy_compose_name(), None);
+        assert_eq!(
+            load("shop-v2").legacy_compose_name().as_deref(),
+            Some("shop")
+        );
+
+        // A recorded name is kept, the project name needs no override
+        let mut project = load("shop-v2");
+        project.resolve_compose_name(Some("shop"));
+        assert_eq!(project.compose_name(), "shop");
+        assert_eq!(project.compose_args()[..2], ["-p", "shop"]);
+        project.resolve_compose_name(Some("shop-v2"));
+        assert_eq!(project.compose_project, None);
+        assert_eq!(project.compose_name(), "shop-v2");
+
+        // Without an old name there is nothing to look up
+        let mut project = load("shop");
+        project.resolve_compose_name(None);
+        assert_eq!(project.compose_name(), "shop");
     }
 }
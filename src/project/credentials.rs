@@ -0,0 +1,390 @@
+//! Generated credentials for template services
+//!
+//! Secrets declared by templates are generated once per project and written
+//! to the `.env` file next to the compose file, which Docker Compose reads for
+//! `${VAR}` interpolation. omd.toml records which variables belong to which
+//! service under `[credentials]`.
+
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use indexmap::IndexMap;
+use rand::{Rng, distr::Alphanumeric};
+use toml_edit::{Array, DocumentMut, Item, Table};
+
+use super::compose_generator::SelectedService;
+
+/// Environment file read by Docker Compose
+pub const ENV_FILE: &str = ".env";
+
+/// Length of generated secrets
+const SECRET_LENGTH: usize = 24;
+
+/// Generate a random alphanumeric secret
+/// Alphanumeric values need no quoting in .env files or connection strings
+pub fn generate_secret() -> String {
+    rand::rng()
+        .sample_iter(&Alphanumeric)
+        .take(SECRET_LENGTH)
+        .map(char::from)
+        .collect()
+}
+
+/// Parse the KEY=VALUE lines of a .env file
+pub fn parse_env(content: &str) -> IndexMap<String, String> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let line = line.strip_prefix("export ").unwrap_or(line);
+            let (key, value) = line.split_once('=')?;
+            let value = value.trim();
+            let value = value
+                .strip_prefix('"')
+                .and_then(|v| v.strip_suffix('"'))
+                .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+                .unwrap_or(value);
+            Some((key.trim().to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+/// Choose the .env variables for the secrets of selected services
+///
+/// A secret uses its own name unless that variable is recorded for another
+/// service, in which case the service name is used as prefix
+/// (e.g. MARIADB_MYSQL_PASSWORD).
+pub fn assign_variables(
+    services: &mut [SelectedService],
+    recorded: &IndexMap<String, Vec<String>>,
+) {
+    let mut taken: HashSet<String> = recorded
+        .iter()
+        .filter(|(service, _)| !services.iter().any(|s| &&s.template.name == service))
+        .flat_map(|(_, variables)| variables.iter().cloned())
+        .collect();
+
+    for service in services.iter_mut() {
+        let prefix = service
+            .template
+            .name
+            .to_uppercase()
+            .replace(['-', '.'], "_");
+
+        for (secret, variable) in service.env_vars.iter_mut() {
+            *variable = if taken.contains(secret) {
+                format!("{}_{}", prefix, secret)
+            } else {
+                secret.clone()
+            };
+            taken.insert(variable.clone());
+        }
+    }
+}
+
+/// Write missing secrets of selected services to the .env file in `dir`
+///
+/// Existing values are kept, so a re-added service still matches the data in
+/// its volume. Returns the variables of each service to record in omd.toml.
+pub fn write_env_file(
+    dir: &Path,
+    services: &[SelectedService],
+) -> Result<IndexMap<String, Vec<String>>> {
+    let path = dir.join(ENV_FILE);
+    let existing = if path.exists() {
+        fs::read_to_string(&path).context(format!("Failed to read {}", path.display()))?
+    } else {
+        String::new()
+    };
+    let values = parse_env(&existing);
+
+    let mut content = existing.clone();
+    let mut variables = IndexMap::new();
+    let mut generated = Vec::new();
+
+    for service in services {
+        if service.env_vars.is_empty() {
+            continue;
+        }
+
+        let missing: Vec<&String> = service
+            .env_vars
+            .values()
+            .filter(|variable| !values.contains_key(*variable))
+            .collect();
+        if !missing.is_empty() {
+            if !content.is_empty() && !content.ends_with('\n') {
+                content.push('\n');
+            }
+            content.push_str(&format!("# {} (generated by omd)\n", service.template.name));
+            for variable in missing {
+                content.push_str(&format!("{}={}\n", variable, generate_secret()));
+                generated.push(variable.clone());
+            }
+        }
+
+        variables.insert(
+            service.template.name.clone(),
+            service.env_vars.values().cloned().collect(),
+        );
+    }
+
+    if content != existing {
+        fs::write(&path, &content).context(format!("Failed to write {}", path.display()))?;
+        restrict_permissions(&path)?;
+        println!(
+            "{} Generated credentials in {}: {}",
+            "✓".green(),
+            ENV_FILE.bright_white(),
+            generated.join(", ")
+        );
+    }
+
+    Ok(variables)
+}
+
+/// Make the .env file readable by the owner only
+#[cfg(unix)]
+fn restrict_permissions(path: &Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::set_permissions(path, fs::Permissions::from_mode(0o600))
+        .context(format!("Failed to set permissions of {}", path.display()))
+}
+
+#[cfg(not(unix))]
+fn restrict_permissions(_path: &Path) -> Result<()> {
+    Ok(())
+}
+
+/// Find the root of the git repository containing `dir`
+fn find_git_root(dir: &Path) -> Option<PathBuf> {
+    let dir = dir.canonicalize().ok()?;
+    dir.ancestors()
+        .find(|ancestor| ancestor.join(".git").exists())
+        .map(Path::to_path_buf)
+}
+
+/// Ask git whether the .env file in `dir` is ignored
+/// Returns None when git is unavailable or fails
+fn git_ignores_env(dir: &Path) -> Option<bool> {
+    let status = Command::new("git")
+        .args(["check-ignore", "-q", ENV_FILE])
+        .current_dir(dir)
+        .status()
+        .ok()?;
+
+    match status.code() {
+        Some(0) => Some(true),
+        Some(1) => Some(false),
+        _ => None,
+    }
+}
+
+/// Add the .env file to `.gitignore` in `dir` when it is inside a git
+/// repository and not ignored yet
+/// Returns true if .gitignore was modified
+pub fn ensure_gitignored(dir: &Path) -> Result<bool> {
+    if find_git_root(dir).is_none() || git_ignores_env(dir) == Some(true) {
+        return Ok(false);
+    }
+
+    let path = dir.join(".gitignore");
+    let mut content = if path.exists() {
+        fs::read_to_string(&path).context(format!("Failed to read {}", path.display()))?
+    } else {
+        String::new()
+    };
+
+    let listed = content
+        .lines()
+        .map(str::trim)
+        .any(|line| line == ENV_FILE || line == format!("/{}", ENV_FILE));
+    if listed {
+        return Ok(false);
+    }
+
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+    content.push_str(&format!("# Generated credentials (omd)\n{}\n", ENV_FILE));
+    fs::write(&path, content).context(format!("Failed to write {}", path.display()))?;
+
+    println!(
+        "{} Added {} to {}",
+        "✓".green(),
+        ENV_FILE,
+        ".gitignore".bright_white()
+    );
+    Ok(true)
+}
+
+/// Generate the credentials of selected services into the .env file in `dir`
+/// Returns the variables of each service to record in omd.toml
+pub fn setup(
+    dir: &Path,
+    services: &mut [SelectedService],
+    recorded: &IndexMap<String, Vec<String>>,
+) -> Result<IndexMap<String, Vec<String>>> {
+    if services.iter().all(|s| s.env_vars.is_empty()) {
+        return Ok(IndexMap::new());
+    }
+
+    assign_variables(services, recorded);
+    let variables = write_env_file(dir, services)?;
+    ensure_gitignored(dir)?;
+
+    Ok(variables)
+}
+
+/// Record service variables in the `[credentials]` table of omd.toml content
+/// Formatting and comments of the rest of the file are preserved
+pub fn record(content: &str, variables: &IndexMap<String, Vec<String>>) -> Result<String> {
+    let mut doc: DocumentMut = content.parse().context("Failed to parse omd.toml")?;
+
+    if !doc.contains_key("credentials") {
+        let mut table = Table::new();
+        table
+            .decor_mut()
+            .set_prefix("\n# Variables in .env that belong to each service (managed by omd)\n");
+        doc.insert("credentials", Item::Table(table));
+    }
+    let table = doc["credentials"]
+        .as_table_mut()
+        .context("[credentials] in omd.toml must be a table")?;
+
+    for (service, names) in variables {
+        let array: Array = names.iter().map(String::as_str).collect();
+        table.insert(service, toml_edit::value(array));
+    }
+
+    Ok(doc.to_string())
+}
+
+/// Remove services from the `[credentials]` table of omd.toml content
+/// Returns None if none of them was recorded
+pub fn forget(content: &str, services: &[String]) -> Result<Option<String>> {
+    let mut doc: DocumentMut = content.parse().context("Failed to parse omd.toml")?;
+
+    let Some(table) = doc.get_mut("credentials").and_then(Item::as_table_mut) else {
+        return Ok(None);
+    };
+
+    let mut modified = false;
+    for service in services {
+        modified |= table.remove(service).is_some();
+    }
+    if table.is_empty() {
+        doc.remove("credentials");
+    }
+
+    Ok(modified.then(|| doc.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::templates::TemplateCatalog;
+
+    fn selected(name: &str) -> SelectedService {
+        SelectedService::new(
+            TemplateCatalog::builtin().get(name).unwrap().clone(),
+            Vec::new(),
+        )
+    }
+
+    #[test]
+    fn test_parse_env() {
+        let values = parse_env("# comment\nA=1\nexport B = \"two words\"\nC='x=y'\n\ninvalid\n");
+
+        assert_eq!(values.len(), 3);
+        assert_eq!(values["A"], "1");
+        assert_eq!(values["B"], "two words");
+        assert_eq!(values["C"], "x=y");
+    }
+
+    #[test]
+    fn test_assign_variables_prefixes_taken_names() {
+        let mut services = vec![selected("postgres"), selected("pgadmin")];
+        let recorded = IndexMap::from([
+            (
+                "legacy-db".to_string(),
+                vec!["POSTGRES_PASSWORD".to_string()],
+            ),
+            ("postgres".to_string(), vec!["OLD".to_string()]),
+        ]);
+
+        assign_variables(&mut services, &recorded);
+
+        assert_eq!(
+            services[0].env_vars["POSTGRES_PASSWORD"],
+            "POSTGRES_POSTGRES_PASSWORD"
+        );
+        assert_eq!(
+            services[1].env_vars["PGADMIN_DEFAULT_PASSWORD"],
+            "PGADMIN_DEFAULT_PASSWORD"
+        );
+    }
+
+    #[test]
+    fn test_write_env_file_keeps_existing_values() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join(ENV_FILE), "POSTGRES_PASSWORD=kept").unwrap();
+
+        let services = vec![selected("postgres"), selected("mysql"), selected("redis")];
+        let variables = write_env_file(dir.path(), &services).unwrap();
+
+        assert_eq!(variables.len(), 2);
+        assert_eq!(
+            variables["mysql"],
+            vec!["MYSQL_ROOT_PASSWORD", "MYSQL_PASSWORD"]
+        );
+
+        let values = parse_env(&fs::read_to_string(dir.path().join(ENV_FILE)).unwrap());
+        assert_eq!(values["POSTGRES_PASSWORD"], "kept");
+        assert_eq!(values["MYSQL_PASSWORD"].len(), SECRET_LENGTH);
+        assert_ne!(values["MYSQL_PASSWORD"], values["MYSQL_ROOT_PASSWORD"]);
+    }
+
+    #[test]
+    fn test_ensure_gitignored() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(!ensure_gitignored(dir.path()).unwrap());
+        assert!(!dir.path().join(".gitignore").exists());
+
+        fs::create_dir(dir.path().join(".git")).unwrap();
+        fs::write(dir.path().join(".gitignore"), "target").unwrap();
+        assert!(ensure_gitignored(dir.path()).unwrap());
+        assert!(!ensure_gitignored(dir.path()).unwrap());
+
+        let content = fs::read_to_string(dir.path().join(".gitignore")).unwrap();
+        assert!(content.starts_with("target\n"));
+        assert_eq!(content.matches(".env").count(), 1);
+    }
+
+    #[test]
+    fn test_record_and_forget_preserve_comments() {
+        let content = "# My project\n[project]\nname = \"shop\" # inline\n";
+        let variables = IndexMap::from([(
+            "postgres".to_string(),
+            vec!["POSTGRES_PASSWORD".to_string()],
+        )]);
+
+        let recorded = record(content, &variables).unwrap();
+        assert!(recorded.starts_with(content));
+        assert!(recorded.contains("postgres = [\"POSTGRES_PASSWORD\"]"));
+
+        assert_eq!(forget(&recorded, &["redis".to_string()]).unwrap(), None);
+        let forgotten = forget(&recorded, &["postgres".to_string()])
+            .unwrap()
+            .unwrap();
+        assert_eq!(forgotten, content);
+    }
+}
@@ -4,169 +4,292 @@
 //! and optionally generating a docker-compose.yml file.
 
 use std::{
-    fs,
+    env, fs,
     io::{self, Write},
     path::Path,
 };
 
 use anyhow::{Context, Result};
 use colored::Colorize;
+use indexmap::IndexMap;
+use toml_edit::{DocumentMut, Item, Table, value};
+
+use super::{
+    compose_generator::{
+        generate_compose_file, prompt_service_selection, resolve_service_ports, template_routes,
+    },
+    config::{PROJECT_CONFIG_VERSION, get_current_dir_name},
+    credentials,
+    registry::PortRegistry,
+};
+use crate::{
+    config::validate,
+    docker::compose::{ComposeInfo, ServiceInfo},
+    templates::{ServiceTemplate, TemplateCatalog},
+};
+
+/// Options for `omd init`
+/// Values that are not given are prompted for, or defaulted with `yes`
+#[derive(Debug, Default)]
+pub struct InitOptions {
+    pub name: Option<String>,
+    pub domain: Option<String>,
+    pub network: Option<String>,
+    pub compose_file: Option<String>,
+    /// Templates for a new compose file (an empty list creates none)
+    pub services: Option<Vec<String>>,
+    /// Routes as (subdomain, container:port)
+    pub routes: Vec<(String, String)>,
+    /// Overwrite an existing omd.toml without asking
+    pub force: bool,
+    /// Use defaults instead of prompting
+    pub yes: bool,
+}
+
+/// Parse a `--route` value: subdomain=container:port
+pub fn parse_route(value: &str) -> Result<(String, String), String> {
+    let (subdomain, target) = value
+        .split_once('=')
+        .ok_or_else(|| format!("expected SUBDOMAIN=CONTAINER:PORT, got '{}'", value))?;
+    let (subdomain, target) = (subdomain.trim(), target.trim());
+
+    if !validate::is_valid_domain(subdomain) {
+        return Err(format!(
+            "invalid subdomain '{}' ({})",
+            subdomain,
+            DOMAIN.hint.to_lowercase()
+        ));
+    }
+
+    let valid_target = target
+        .rsplit_once(':')
+        .is_some_and(|(container, port)| !container.is_empty() && port.parse::<u16>().is_ok());
+    if !valid_target {
+        return Err(format!(
+            "expected CONTAINER:PORT as target, got '{}'",
+            target
+        ));
+    }
+
+    Ok((subdomain.to_string(), target.to_string()))
+}
+
+/// What `omd project up` accepts for a value written by init
+struct Rule {
+    valid: fn(&str) -> bool,
+    hint: &'static str,
+    suggest: fn(&str) -> String,
+}
 
-use super::compose_generator::{
-    generate_compose_file, prompt_service_selection, resolve_service_ports,
+const PROJECT_NAME: Rule = Rule {
+    valid: validate::is_valid_project_name,
+    hint: "Use lowercase letters, digits, '-' and '_'",
+    suggest: validate::suggest_name,
+};
+
+/// Domains and route subdomains
+const DOMAIN: Rule = Rule {
+    valid: validate::is_valid_domain,
+    hint: "Use lowercase letters, digits, '-' and '.'",
+    suggest: validate::suggest_domain,
+};
+
+const NETWORK_NAME: Rule = Rule {
+    valid: validate::is_valid_network_name,
+    hint: "Use letters, digits, '_', '.' and '-'",
+    suggest: validate::suggest_name,
 };
-use super::config::get_current_dir_name;
-use super::registry::PortRegistry;
 
 /// Initialize a new omd.toml configuration in the current directory
-pub fn init() -> Result<()> {
+pub fn init(options: InitOptions) -> Result<()> {
     let config_path = Path::new("omd.toml");
 
-    if config_path.exists() {
+    if config_path.exists() && !options.force {
+        if options.yes {
+            anyhow::bail!(
+                "omd.toml already exists in current directory. Use --force to overwrite it."
+            );
+        }
+
         println!(
             "{} {} already exists in current directory",
             "⚠".yellow(),
             "omd.toml".bright_white()
         );
-        print!("Overwrite? [y/N]: ");
-        io::stdout().flush()?;
-
-        let mut input = String::new();
-        io::stdin().read_line(&mut input)?;
-
-        if !input.trim().eq_ignore_ascii_case("y") {
+        if !confirm("Overwrite? [y/N]: ")? {
             println!("{}", "Aborted".yellow());
             return Ok(());
         }
     }
 
     // Get default values
-    let default_name = get_current_dir_name().unwrap_or_else(|_| "my-project".to_string());
+    let default_name = get_current_dir_name()
+        .map(|name| validate::suggest_name(&name))
+        .ok()
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "my-project".to_string());
 
     // Interactive prompts
     println!("{}", "Creating omd.toml configuration...".blue());
     println!();
 
-    let project_name = prompt_with_default("Project name", &default_name)?;
-    let domain = prompt_with_default("Domain", &format!("{}.local", project_name))?;
-    let network = prompt_with_default("Network name", &format!("{}-net", project_name))?;
-    let compose_file = prompt_with_default("Docker Compose file", "docker-compose.yml")?;
+    let project_name = value_or_prompt(
+        options.name,
+        "Project name",
+        &default_name,
+        options.yes,
+        Some(&PROJECT_NAME),
+    )?;
+    let domain = value_or_prompt(
+        options.domain,
+        "Domain",
+        &format!("{}.local", validate::suggest_domain(&project_name)),
+        options.yes,
+        Some(&DOMAIN),
+    )?;
+    let network = value_or_prompt(
+        options.network,
+        "Network name",
+        &format!("{}-net", project_name),
+        options.yes,
+        Some(&NETWORK_NAME),
+    )?;
+    let compose_file = value_or_prompt(
+        options.compose_file,
+        "Docker Compose file",
+        "docker-compose.yml",
+        options.yes,
+        None,
+    )?;
 
     // Check if compose file exists
     let compose_path = Path::new(&compose_file);
+    let compose_existed = compose_path.exists();
     let mut compose_created = false;
+    let mut routes: Vec<(String, String)> = Vec::new();
+    let mut credentials = IndexMap::new();
 
-    if !compose_path.exists() {
-        println!();
-        println!(
-            "{} {} does not exist",
-            "ℹ".blue(),
-            compose_file.bright_white()
+    if compose_existed && options.services.as_ref().is_some_and(|s| !s.is_empty()) {
+        anyhow::bail!(
+            "{} already exists, so --services cannot generate it.\n\
+            Add services to it with 'omd add' after 'omd init'.",
+            compose_file
         );
-        print!("Create docker-compose.yml with common services? [Y/n]: ");
-        io::stdout().flush()?;
-
-        let mut confirm = String::new();
-        io::stdin().read_line(&mut confirm)?;
-
-        let should_create = confirm.trim().is_empty()
-            || confirm.trim().eq_ignore_ascii_case("y")
-            || confirm.trim().eq_ignore_ascii_case("yes");
-
-        if should_create {
-            // Show service selection
-            let selections = prompt_service_selection()?;
-
-            if !selections.is_empty() {
-                // Load registry to check port conflicts
-                let registry = PortRegistry::load().unwrap_or_default();
-
-                // Resolve ports for selected services
-                let selected_services = resolve_service_ports(&selections, &registry);
+    }
 
-                // Generate docker-compose.yml
-                generate_compose_file(compose_path, &project_name, &network, &selected_services)?;
+    if compose_existed {
+        // Propose routes for the services of the existing compose file
+        let compose_info = ComposeInfo::parse(compose_path)?;
+        let registry = PortRegistry::load().unwrap_or_default();
+        warn_port_conflicts(&project_name, &compose_info, &registry);
 
-                let service_names: Vec<&str> = selected_services
-                    .iter()
-                    .map(|s| s.template.display_name)
-                    .collect();
+        if options.routes.is_empty() && !options.yes {
+            routes = prompt_routes(&project_name, &domain, &compose_info)?;
+        }
+    } else {
+        let current_dir = env::current_dir().context("Failed to get current directory")?;
+        let catalog = TemplateCatalog::load(&current_dir)?;
 
+        let templates: Vec<&ServiceTemplate> = match &options.services {
+            Some(names) => lookup_templates(&catalog, names)?,
+            None if options.yes => Vec::new(),
+            None => {
                 println!();
                 println!(
-                    "{} Created {} with: {}",
-                    "✓".green(),
-                    compose_file.bright_white(),
-                    service_names.join(", ")
-                );
-                compose_created = true;
-            } else {
-                println!(
-                    "{} No services selected, skipping docker-compose.yml creation",
-                    "ℹ".blue()
+                    "{} {} does not exist",
+                    "ℹ".blue(),
+                    compose_file.bright_white()
                 );
+                if confirm("Create docker-compose.yml with common services? [Y/n]: ")? {
+                    prompt_service_selection(catalog.templates())?
+                        .into_iter()
+                        .map(|idx| &catalog.templates()[idx])
+                        .collect()
+                } else {
+                    Vec::new()
+                }
             }
+        };
+
+        if !templates.is_empty() {
+            // Load registry to check port conflicts
+            let registry = PortRegistry::load().unwrap_or_default();
+
+            // Resolve ports for selected services
+            let mut selected_services = resolve_service_ports(&templates, &registry, &[]);
+
+            // Secrets go to the .env file next to the compose file
+            let compose_dir = compose_path
+                .parent()
+                .filter(|dir| !dir.as_os_str().is_empty())
+                .unwrap_or(Path::new("."));
+            credentials =
+                credentials::setup(compose_dir, &mut selected_services, &IndexMap::new())?;
+
+            // Generate docker-compose.yml
+            generate_compose_file(compose_path, &project_name, &network, &selected_services)?;
+
+            let service_names: Vec<&str> = selected_services
+                .iter()
+                .map(|s| s.template.display_name.as_str())
+                .collect();
+
+            println!();
+            println!(
+                "{} Created {} with: {}",
+                "✓".green(),
+                compose_file.bright_white(),
+                service_names.join(", ")
+            );
+            compose_created = true;
+
+            // Route the HTTP ports declared by the templates (admin UIs etc.)
+            routes = template_routes(&project_name, &selected_services);
+        } else {
+            println!(
+                "{} No services selected, skipping docker-compose.yml creation",
+                "ℹ".blue()
+            );
         }
     }
 
-    // Ask about Caddy routes configuration
-    println!();
-    print!("Do you want to configure Caddy routes now? [y/N]: ");
-    io::stdout().flush()?;
-    let mut input = String::new();
-    io::stdin().read_line(&mut input)?;
-    let configure_routes = input.trim().eq_ignore_ascii_case("y");
-
-    // Generate config content
-    let mut config_content = format!(
-        r#"# oh-my-dockers Project Configuration
-# See https://github.com/your-repo/oh-my-dockers for more information
-
-[project]
-# Project name (used for container naming)
-name = "{}"
-
-# Domain for this project
-domain = "{}"
-"#,
-        project_name, domain
-    );
-
-    // Only add compose_file if it's not the default
-    if compose_file != "docker-compose.yml" {
-        config_content.push_str(&format!(
-            r#"
-# Path to docker-compose file (relative to project directory)
-compose_file = "{}"
-"#,
-            compose_file
-        ));
+    // Routes given as flags replace template routes with the same subdomain
+    for (subdomain, target) in &options.routes {
+        match routes.iter_mut().find(|(s, _)| s == subdomain) {
+            Some(route) => route.1 = target.clone(),
+            None => routes.push((subdomain.clone(), target.clone())),
+        }
     }
-
-    config_content.push_str(&format!(
-        r#"
-[network]
-# Docker network name for this project
-name = "{}"
-"#,
-        network
-    ));
-
-    if configure_routes {
-        config_content.push_str(
-            r#"
-# Custom Caddy routes
-# Format: subdomain = "container_name:port"
-# Example:
-#   [caddy.routes]
-#   api = "backend:3000"
-#   app = "frontend:80"
-#   admin = "admin:8080"
-[caddy.routes]
-"#,
+    for (subdomain, target) in &routes {
+        println!(
+            "{} Route: {}.{} -> {}",
+            "ℹ".blue(),
+            subdomain.bright_white(),
+            domain,
+            target
         );
     }
 
+    // Ask about Caddy routes configuration (templates may already provide routes)
+    let configure_routes = if routes.is_empty() && !options.yes && !compose_existed {
+        println!();
+        confirm("Do you want to configure Caddy routes now? [y/N]: ")?
+    } else {
+        false
+    };
+
+    let mut config_content = render_config(
+        &project_name,
+        &domain,
+        &compose_file,
+        &network,
+        &routes,
+        configure_routes,
+    );
+    if !credentials.is_empty() {
+        config_content = credentials::record(&config_content, &credentials)?;
+    }
+
     // Write config file
     fs::write(config_path, config_content).context("Failed to write omd.toml")?;
 
@@ -191,6 +314,280 @@ name = "{}"
     Ok(())
 }
 
+/// Warn about host ports of the compose file that registered projects use
+fn warn_port_conflicts(project_name: &str, compose_info: &ComposeInfo, registry: &PortRegistry) {
+    let conflicts = registry.check_port_conflicts(project_name, &compose_info.get_all_host_ports());
+    if conflicts.is_empty() {
+        return;
+    }
+
+    println!();
+    for (port, project) in conflicts {
+        println!(
+            "{} Port {} is already used by project {}",
+            "⚠".yellow(),
+            port,
+            project.bright_white()
+        );
+    }
+    println!("  Change the host ports in the compose file before running omd project up.");
+}
+
+/// Services that can be routed (with container ports), sorted by name
+fn routable_services(compose_info: &ComposeInfo) -> Vec<&ServiceInfo> {
+    let mut services: Vec<&ServiceInfo> = compose_info
+        .services
+        .values()
+        .filter(|service| !service.container_ports.is_empty())
+        .collect();
+    services.sort_by(|a, b| a.name.cmp(&b.name));
+    services
+}
+
+/// Let the user pick a subdomain and port for each routable service
+fn prompt_routes(
+    project_name: &str,
+    domain: &str,
+    compose_info: &ComposeInfo,
+) -> Result<Vec<(String, String)>> {
+    let services = routable_services(compose_info);
+    if services.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    println!();
+    println!("{} Services with container ports:", "ℹ".blue());
+    for service in &services {
+        let ports: Vec<String> = service
+            .container_ports
+            .iter()
+            .map(|p| p.to_string())
+            .collect();
+        println!(
+            "  {} (ports {})",
+            service.name.bright_white(),
+            ports.join(", ")
+        );
+    }
+    println!();
+    if !confirm("Configure Caddy routes for these services? [Y/n]: ")? {
+        return Ok(Vec::new());
+    }
+
+    println!(
+        "{}",
+        "Enter a subdomain for each service ('-' to skip):".blue()
+    );
+    let mut routes: Vec<(String, String)> = Vec::new();
+    for service in services {
+        println!();
+        let default_subdomain = validate::suggest_domain(&service.name);
+        let subdomain = loop {
+            let input =
+                prompt_with_default(&format!("  {} subdomain", service.name), &default_subdomain)?;
+            if input == "-" || validate::is_valid_domain(&input) {
+                break input;
+            }
+            println!("{} {}", "⚠".yellow(), DOMAIN.hint);
+        };
+        if subdomain == "-" {
+            continue;
+        }
+        if routes.iter().any(|(s, _)| s == &subdomain) {
+            println!(
+                "{} {}.{} is already routed, skipping {}",
+                "⚠".yellow(),
+                subdomain,
+                domain,
+                service.name
+            );
+            continue;
+        }
+
+        let default_port = service.container_ports[0].to_string();
+        let port = loop {
+            let input = prompt_with_default(&format!("  {} port", service.name), &default_port)?;
+            match input.parse::<u16>() {
+                Ok(port) => break port,
+                Err(_) => println!("{} Enter a port number", "⚠".yellow()),
+            }
+        };
+
+        let target = format!("{}:{}", service.resolved_container_name(project_name), port);
+        routes.push((subdomain, target));
+    }
+
+    Ok(routes)
+}
+
+/// Use a value given as flag, otherwise prompt (or take the default with `yes`)
+/// Prompts repeat until the answer follows `rule`; other values must follow it
+fn value_or_prompt(
+    value: Option<String>,
+    prompt: &str,
+    default: &str,
+    yes: bool,
+    rule: Option<&Rule>,
+) -> Result<String> {
+    let value = match value {
+        Some(value) => value,
+        None if yes => default.to_string(),
+        None => loop {
+            let input = prompt_with_default(prompt, default)?;
+            match rule {
+                Some(rule) if !(rule.valid)(&input) => {
+                    println!("{} {}", "⚠".yellow(), rule.hint)
+                }
+                _ => return Ok(input),
+            }
+        },
+    };
+
+    if let Some(rule) = rule.filter(|rule| !(rule.valid)(&value)) {
+        let suggestion = (rule.suggest)(&value);
+        if suggestion.is_empty() || !(rule.valid)(&suggestion) {
+            anyhow::bail!(
+                "Invalid {} '{}'. {}.",
+                prompt.to_lowercase(),
+                value,
+                rule.hint
+            );
+        }
+        anyhow::bail!(
+            "Invalid {} '{}'. {} (e.g. '{}').",
+            prompt.to_lowercase(),
+            value,
+            rule.hint,
+            suggestion
+        );
+    }
+    Ok(value)
+}
+
+/// Build omd.toml for the values chosen during init
+/// Values go through toml_edit so that they are quoted and escaped
+fn render_config(
+    project_name: &str,
+    domain: &str,
+    compose_file: &str,
+    network: &str,
+    routes: &[(String, String)],
+    routes_placeholder: bool,
+) -> String {
+    let mut doc = DocumentMut::new();
+    doc.insert("version", value(i64::from(PROJECT_CONFIG_VERSION)));
+    set_comment(
+        &mut doc,
+        "version",
+        "# oh-my-dockers Project Configuration\n\
+        # See https://github.com/your-repo/oh-my-dockers for more information\n\
+        \n\
+        # Format version of this file (upgrade with 'omd migrate')\n",
+    );
+
+    let mut project = Table::new();
+    project.decor_mut().set_prefix("\n");
+    project.insert("name", value(project_name));
+    set_comment(
+        &mut project,
+        "name",
+        "# Project name (used for container naming)\n",
+    );
+    project.insert("domain", value(domain));
+    set_comment(&mut project, "domain", "\n# Domain for this project\n");
+    // Only add compose_file if it's not the default
+    if compose_file != "docker-compose.yml" {
+        project.insert("compose_file", value(compose_file));
+        set_comment(
+            &mut project,
+            "compose_file",
+            "\n# Path to docker-compose file (relative to project directory)\n",
+        );
+    }
+    doc.insert("project", Item::Table(project));
+
+    let mut network_table = Table::new();
+    network_table.decor_mut().set_prefix("\n");
+    network_table.insert("name", value(network));
+    set_comment(
+        &mut network_table,
+        "name",
+        "# Docker network name for this project\n",
+    );
+    doc.insert("network", Item::Table(network_table));
+
+    if routes_placeholder || !routes.is_empty() {
+        let mut routes_table = Table::new();
+        if routes.is_empty() {
+            routes_table.decor_mut().set_prefix(
+                "\n# Custom Caddy routes\n\
+                # Format: subdomain = \"container_name:port\"\n\
+                # Example:\n\
+                #   [caddy.routes]\n\
+                #   api = \"backend:3000\"\n\
+                #   app = \"frontend:80\"\n\
+                #   admin = \"admin:8080\"\n",
+            );
+        } else {
+            routes_table
+                .decor_mut()
+                .set_prefix("\n# Caddy routes\n# Format: subdomain = \"container_name:port\"\n");
+        }
+        for (subdomain, target) in routes {
+            routes_table.insert(subdomain, value(target.as_str()));
+        }
+
+        let mut caddy = Table::new();
+        caddy.set_implicit(true);
+        caddy.insert("routes", Item::Table(routes_table));
+        doc.insert("caddy", Item::Table(caddy));
+    }
+
+    doc.to_string()
+}
+
+/// Put comment lines above a key of a table
+fn set_comment(table: &mut Table, key: &str, comment: &str) {
+    if let Some(mut key) = table.key_mut(key) {
+        key.leaf_decor_mut().set_prefix(comment);
+    }
+}
+
+/// Look up templates by name (ignoring empty and repeated names)
+fn lookup_templates<'a>(
+    catalog: &'a TemplateCatalog,
+    names: &[String],
+) -> Result<Vec<&'a ServiceTemplate>> {
+    let mut templates: Vec<&ServiceTemplate> = Vec::new();
+    for name in names.iter().map(|n| n.trim()).filter(|n| !n.is_empty()) {
+        let template = catalog.get(name).with_context(|| {
+            format!(
+                "Template '{}' not found. Run 'omd templates list' to see available templates.",
+                name
+            )
+        })?;
+        if !templates.iter().any(|t| t.name == template.name) {
+            templates.push(template);
+        }
+    }
+    Ok(templates)
+}
+
+/// Ask a yes/no question; `[Y/n]` in the prompt makes yes the default
+fn confirm(prompt: &str) -> Result<bool> {
+    print!("{}", prompt);
+    io::stdout().flush()?;
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+
+    let input = input.trim();
+    if input.is_empty() {
+        return Ok(prompt.contains("[Y/n]"));
+    }
+    Ok(input.eq_ignore_ascii_case("y") || input.eq_ignore_ascii_case("yes"))
+}
+
 /// Prompt user for input with a default value
 fn prompt_with_default(prompt: &str, default: &str) -> Result<String> {
     print!("{} [{}]: ", prompt, default.bright_black());
@@ -206,3 +603,119 @@ fn prompt_with_default(prompt: &str, default: &str) -> Result<String> {
         Ok(trimmed.to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project::config::ProjectConfig;
+
+    #[test]
+    fn test_parse_route() {
+        assert_eq!(
+            parse_route("api=bff:8080"),
+            Ok(("api".to_string(), "bff:8080".to_string()))
+        );
+        assert!(parse_route("api").is_err());
+        assert!(parse_route("api=bff").is_err());
+        assert!(parse_route("api=bff:http").is_err());
+        assert!(parse_route("a pi=bff:8080").is_err());
+        assert!(parse_route("API=bff:8080").is_err());
+        assert!(parse_route("-api=bff:8080").is_err());
+    }
+
+    #[test]
+    fn test_value_or_prompt() {
+        let value = |v: &str, rule: &Rule| {
+            value_or_prompt(Some(v.to_string()), "Project name", "", true, Some(rule))
+        };
+        assert_eq!(value("shop_api", &PROJECT_NAME).unwrap(), "shop_api");
+        let err = value("My Shop", &PROJECT_NAME).unwrap_err().to_string();
+        assert!(err.contains("(e.g. 'my-shop')"), "{}", err);
+        assert!(value("Shop.Local", &DOMAIN).is_err());
+        assert!(value("shop.local", &DOMAIN).is_ok());
+
+        // The default is checked as well when prompts are skipped
+        assert!(value_or_prompt(None, "Domain", "Shop", true, Some(&DOMAIN)).is_err());
+        assert_eq!(
+            value_or_prompt(None, "Docker Compose file", "a b.yml", true, None).unwrap(),
+            "a b.yml"
+        );
+    }
+
+    #[test]
+    fn test_render_config() {
+        let routes = vec![
+            ("api".to_string(), "shop-api:3000".to_string()),
+            ("admin.v2".to_string(), "shop-admin:80".to_string()),
+        ];
+        let content = render_config(
+            "shop",
+            "shop.local",
+            "docker\\compose \"dev\".yml",
+            "shop-net",
+            &routes,
+            false,
+        );
+        assert_eq!(
+            content,
+            format!(
+                r#"# oh-my-dockers Project Configuration
+# See https://github.com/your-repo/oh-my-dockers for more information
+
+# Format version of this file (upgrade with 'omd migrate')
+version = {}
+
+[project]
+# Project name (used for container naming)
+name = "shop"
+
+# Domain for this project
+domain = "shop.local"
+
+# Path to docker-compose file (relative to project directory)
+compose_file = 'docker\compose "dev".yml'
+
+[network]
+# Docker network name for this project
+name = "shop-net"
+
+# Caddy routes
+# Format: subdomain = "container_name:port"
+[caddy.routes]
+api = "shop-api:3000"
+"admin.v2" = "shop-admin:80"
+"#,
+                PROJECT_CONFIG_VERSION
+            )
+        );
+
+        let config: ProjectConfig = toml::from_str(&content).unwrap();
+        assert_eq!(config.project.compose_file, "docker\\compose \"dev\".yml");
+        assert_eq!(config.caddy.routes["admin.v2"], "shop-admin:80");
+
+        // An empty [caddy.routes] table is written for the user to fill in
+        let content = render_config("shop", "shop.local", "docker-compose.yml", "n", &[], true);
+        assert!(!content.contains("compose_file"));
+        assert!(content.ends_with("#   admin = \"admin:8080\"\n[caddy.routes]\n"));
+        assert!(!content.contains("[caddy]"));
+    }
+
+    #[test]
+    fn test_routable_services() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("docker-compose.yml");
+        fs::write(
+            &path,
+            "services:\n  web:\n    image: nginx\n    ports:\n      - \"8080:80\"\n  api:\n    image: api\n    container_name: shop-api\n    ports:\n      - \"3000\"\n  worker:\n    image: worker\n",
+        )
+        .unwrap();
+
+        let compose_info = ComposeInfo::parse(&path).unwrap();
+        let services = routable_services(&compose_info);
+
+        let names: Vec<&str> = services.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["api", "web"]);
+        assert_eq!(services[0].resolved_container_name("shop"), "shop-api");
+        assert_eq!(services[1].resolved_container_name("shop"), "shop-web-1");
+    }
+}
@@ -0,0 +1,205 @@
+//! Project configuration migration (omd.toml)
+//!
+//! omd.toml files are checked into project repositories, so they are
+//! migrated with toml_edit: comments and formatting survive an upgrade.
+//! Files older than [`PROJECT_CONFIG_VERSION`] are upgraded in memory when
+//! loaded; `omd migrate` writes the upgrade back to disk.
+
+use std::fs;
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use toml_edit::{DocumentMut, Item, RawString, value};
+
+use super::config::{PROJECT_CONFIG_VERSION, locate_project_config};
+
+/// Format version of an omd.toml document
+/// Files written before versioning have no `version` field (version 0)
+pub fn file_version(doc: &DocumentMut) -> Result<u32> {
+    match doc.get("version") {
+        None => Ok(0),
+        Some(item) => item
+            .as_integer()
+            .and_then(|v| u32::try_from(v).ok())
+            .context("'version' in omd.toml must be a non-negative integer"),
+    }
+}
+
+/// Upgrade a document to the current format version
+/// Returns a description of each applied step
+pub fn migrate(doc: &mut DocumentMut) -> Result<Vec<String>> {
+    let mut version = file_version(doc)?;
+    if version > PROJECT_CONFIG_VERSION {
+        anyhow::bail!(
+            "omd.toml uses format version {}, but this omd only supports up to version {}. \
+             Update omd to use this file.",
+            version,
+            PROJECT_CONFIG_VERSION
+        );
+    }
+
+    let mut steps = Vec::new();
+    while version < PROJECT_CONFIG_VERSION {
+        steps.push(migrate_from_version(doc, version)?);
+        version += 1;
+    }
+
+    Ok(steps)
+}
+
+/// Apply migration from a specific version to the next version
+fn migrate_from_version(doc: &mut DocumentMut, from_version: u32) -> Result<String> {
+    match from_version {
+        0 => migrate_v0_to_v1(doc),
+        // Add more migrations here as needed:
+        // 1 => migrate_v1_to_v2(doc),
+        _ => anyhow::bail!("No migration from omd.toml version {}", from_version),
+    }
+}
+
+/// Migration from version 0 (no version field) to version 1
+///
+/// Changes:
+/// - Add version field
+fn migrate_v0_to_v1(doc: &mut DocumentMut) -> Result<String> {
+    set_version(doc, 1);
+    Ok("v0 -> v1: added version field".to_string())
+}
+
+/// Set the version field, adding it below the file's header comment
+fn set_version(doc: &mut DocumentMut, version: u32) {
+    if let Some(item) = doc.get_mut("version").and_then(|item| item.as_value_mut()) {
+        let decor = item.decor().clone();
+        *item = (version as i64).into();
+        *item.decor_mut() = decor;
+        return;
+    }
+
+    // A comment at the top of the file belongs to the first table header;
+    // move the part before the first blank line above the new field
+    let first_table = doc
+        .iter()
+        .filter_map(|(key, item)| Some((key.to_string(), item.as_table()?.position()?)))
+        .min_by_key(|(_, position)| *position)
+        .map(|(key, _)| key);
+    let mut header = String::new();
+    if let Some(table) = first_table
+        .and_then(|key| doc.get_mut(&key))
+        .and_then(Item::as_table_mut)
+    {
+        let prefix = table
+            .decor()
+            .prefix()
+            .and_then(RawString::as_str)
+            .unwrap_or("")
+            .to_string();
+        let rest = match prefix.split_once("\n\n") {
+            Some((top, rest)) if !top.is_empty() => {
+                header = format!("{}\n\n", top);
+                rest
+            }
+            _ => prefix.as_str(),
+        };
+        table
+            .decor_mut()
+            .set_prefix(format!("\n{}", rest.trim_start_matches('\n')));
+    }
+
+    doc.insert("version", value(version as i64));
+    if let Some(mut key) = doc.key_mut("version") {
+        key.leaf_decor_mut().set_prefix(header);
+    }
+}
+
+/// Upgrade the project's omd.toml to the current format version
+pub fn migrate_project() -> Result<()> {
+    let path = locate_project_config()?;
+    let content = fs::read_to_string(&path).context(format!("Failed to read {:?}", path))?;
+    let mut doc: DocumentMut = content
+        .parse()
+        .context("Failed to parse project configuration")?;
+
+    let version = file_version(&doc)?;
+    if version == PROJECT_CONFIG_VERSION {
+        println!(
+            "{} omd.toml is already at version {}",
+            "✓".green(),
+            PROJECT_CONFIG_VERSION
+        );
+        return Ok(());
+    }
+
+    let steps = migrate(&mut doc)?;
+    println!(
+        "{} Migrating omd.toml: v{} -> v{}",
+        "ℹ".blue(),
+        version,
+        PROJECT_CONFIG_VERSION
+    );
+    for step in steps {
+        println!("  {} {}", "+".green(), step);
+    }
+
+    fs::write(&path, doc.to_string()).context("Failed to write omd.toml")?;
+    println!(
+        "{} omd.toml migrated to v{}",
+        "✓".green(),
+        PROJECT_CONFIG_VERSION
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrate_v0_keeps_comments() {
+        let content = r#"# oh-my-dockers Project Configuration
+# See the manual
+
+[project]
+# Project name
+name = "myapp"
+domain = "myapp.local"
+
+[network]
+name = "myapp-net"
+"#;
+        let mut doc: DocumentMut = content.parse().unwrap();
+        assert_eq!(file_version(&doc).unwrap(), 0);
+
+        let steps = migrate(&mut doc).unwrap();
+        assert_eq!(steps.len(), 1);
+        assert_eq!(file_version(&doc).unwrap(), PROJECT_CONFIG_VERSION);
+        assert_eq!(
+            doc.to_string(),
+            r#"# oh-my-dockers Project Configuration
+# See the manual
+
+version = 1
+
+[project]
+# Project name
+name = "myapp"
+domain = "myapp.local"
+
+[network]
+name = "myapp-net"
+"#
+        );
+
+        // Already current: nothing to do
+        assert!(migrate(&mut doc).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_migrate_refuses_newer_version() {
+        let mut doc: DocumentMut = "version = 99\n".parse().unwrap();
+        assert!(migrate(&mut doc).is_err());
+
+        let doc: DocumentMut = "version = \"1\"\n".parse().unwrap();
+        assert!(file_version(&doc).is_err());
+    }
+}
@@ -5,10 +5,21 @@
 //! - Project registry
 //! - Project initialization
 //! - Project up/down commands
+//! - Docker compose passthrough commands (logs, ps, exec, ...)
 //! - Docker Compose file generation
+//! - Adding and removing template services
+//! - Generated credentials (.env)
+//! - Watch mode (omd watch)
 
+pub mod bulk;
 pub mod commands;
 pub mod compose_generator;
+pub mod compose_override;
 pub mod config;
+pub mod credentials;
 pub mod init;
+pub mod migration;
+pub mod passthrough;
 pub mod registry;
+pub mod services;
+pub mod watch;
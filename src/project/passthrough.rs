@@ -0,0 +1,136 @@
+//! Docker compose commands for a project (logs, ps, exec, restart, pull, build)
+//!
+//! The project is the one containing the current directory, or a registered
+//! project selected by name. docker compose gets the same compose files,
+//! generated override, profiles and Compose project name as `omd project up`, and
+//! runs in the project directory.
+//!
+//! Nothing is printed besides the output of docker compose, so the output can
+//! be piped (`omd project exec db -- pg_dump shop > dump.sql`).
+
+use std::{
+    env,
+    io::IsTerminal,
+    process::{Command, ExitStatus},
+};
+
+use anyhow::{Context, Result};
+
+use super::{
+    config::{PROJECT_CONFIG_FILE, Project, find_project_config, load_project_at},
+    registry::PortRegistry,
+};
+
+/// Load a registered project by name, or the project containing the current
+/// directory, with the profile it was started with
+pub fn resolve(name: Option<&str>) -> Result<Project> {
+    let registry = PortRegistry::load()?;
+
+    let mut project = match name {
+        Some(name) => {
+            let entry = registry.get_project(name).context(format!(
+                "Project '{}' is not registered. Run 'omd project up' in its directory first.",
+                name
+            ))?;
+            load_project_at(&entry.path.join(PROJECT_CONFIG_FILE))?
+        }
+        None => {
+            let current_dir = env::current_dir().context("Failed to get current directory")?;
+            let path = find_project_config(&current_dir).context(format!(
+                "No omd.toml found in {} or its parent directories. \
+                 Run from a project directory or pass --project <name>.",
+                current_dir.display()
+            ))?;
+            load_project_at(&path)?
+        }
+    };
+
+    let entry = registry.get_project(&project.config.project.name);
+    if let Some(profile) = entry.and_then(|entry| entry.profile.clone()) {
+        project.select_profile(&profile)?;
+    }
+    project.resolve_compose_name(entry.and_then(|entry| entry.compose_project.as_deref()));
+
+    Ok(project)
+}
+
+/// Show the logs of the project's services (all services if none are given)
+pub fn logs(
+    name: Option<&str>,
+    services: &[String],
+    follow: bool,
+    tail: Option<&str>,
+) -> Result<()> {
+    let mut args = vec!["logs".to_string()];
+    if follow {
+        args.push("--follow".to_string());
+    }
+    if let Some(tail) = tail {
+        args.push("--tail".to_string());
+        args.push(tail.to_string());
+    }
+    args.extend(services.iter().cloned());
+
+    run(&resolve(name)?, &args)
+}
+
+/// List the project's containers
+pub fn ps(name: Option<&str>) -> Result<()> {
+    run(&resolve(name)?, &["ps".to_string()])
+}
+
+/// Run a command in a running service container
+pub fn exec(name: Option<&str>, service: &str, command: &[String]) -> Result<()> {
+    let mut args = vec!["exec".to_string()];
+    // docker compose allocates a TTY by default, which fails when input is piped
+    if !std::io::stdin().is_terminal() || !std::io::stdout().is_terminal() {
+        args.push("-T".to_string());
+    }
+    args.push(service.to_string());
+    args.extend(command.iter().cloned());
+
+    run(&resolve(name)?, &args)
+}
+
+/// Pull the images of the project's services (all services if none are given)
+pub fn pull(name: Option<&str>, services: &[String]) -> Result<()> {
+    let mut args = vec!["pull".to_string()];
+    args.extend(services.iter().cloned());
+
+    run(&resolve(name)?, &args)
+}
+
+/// Build the images of the project's services (all services if none are given)
+pub fn build(name: Option<&str>, services: &[String], no_cache: bool) -> Result<()> {
+    let mut args = vec!["build".to_string()];
+    if no_cache {
+        args.push("--no-cache".to_string());
+    }
+    args.extend(services.iter().cloned());
+
+    run(&resolve(name)?, &args)
+}
+
+/// Run docker compose for the project, failing with its exit code
+fn run(project: &Project, args: &[String]) -> Result<()> {
+    let status = compose(project, args)?;
+    if !status.success() {
+        anyhow::bail!(
+            "docker compose {} failed (exit code: {:?})",
+            args[0],
+            status.code()
+        );
+    }
+    Ok(())
+}
+
+/// Run docker compose for the project with inherited input and output
+pub fn compose(project: &Project, args: &[String]) -> Result<ExitStatus> {
+    Command::new("docker")
+        .arg("compose")
+        .args(project.compose_args())
+        .args(args)
+        .current_dir(&project.dir)
+        .status()
+        .context("Failed to execute docker compose")
+}
@@ -3,12 +3,17 @@
 //! This module manages the registry of projects and their port allocations.
 //! The registry is stored as a JSON file in the configuration directory.
 
-use std::{collections::HashMap, fs, path::PathBuf};
+use std::{
+    collections::HashMap,
+    fs,
+    net::IpAddr,
+    path::{Path, PathBuf},
+};
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 
-use crate::config::get_config_dir;
+use crate::{config::get_config_dir, system::hosts::DesiredSection};
 
 /// Represents a registered project with its port allocations
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -21,10 +26,48 @@ pub struct ProjectEntry {
     pub domain: String,
     /// Network name
     pub network: String,
+    /// Networks shared with other projects (`[network] shared`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub shared_networks: Vec<String>,
     /// List of host ports this project occupies
     pub ports: Vec<u16>,
     /// List of container names
     pub containers: Vec<String>,
+    /// Addresses the project domains resolve to (empty = loopback defaults)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub addresses: Vec<IpAddr>,
+    /// Project domain and route domains
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub domains: Vec<String>,
+    /// Extra host names from `[hosts] aliases`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+    /// Profile selected with `omd project up --profile`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    /// Compose project name the containers were started under (None for
+    /// projects registered before it was recorded)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compose_project: Option<String>,
+}
+
+impl ProjectEntry {
+    /// Domains the project should have in the hosts file: the project and
+    /// route domains, then the aliases
+    /// Entries registered before domains were recorded fall back to the project domain
+    pub fn hosts_domains(&self) -> Vec<String> {
+        let mut domains = if self.domains.is_empty() {
+            vec![self.domain.clone()]
+        } else {
+            self.domains.clone()
+        };
+        for alias in &self.aliases {
+            if !domains.contains(alias) {
+                domains.push(alias.clone());
+            }
+        }
+        domains
+    }
 }
 
 /// Port registry that tracks all registered projects
@@ -44,13 +87,16 @@ impl PortRegistry {
 
     /// Load the port registry from disk
     pub fn load() -> Result<Self> {
-        let registry_path = Self::get_registry_path()?;
+        Self::load_from(&Self::get_registry_path()?)
+    }
 
+    /// Load a port registry from a file (empty if it does not exist)
+    pub fn load_from(registry_path: &Path) -> Result<Self> {
         if !registry_path.exists() {
             return Ok(Self::new());
         }
 
-        let content = fs::read_to_string(&registry_path).context("Failed to read registry file")?;
+        let content = fs::read_to_string(registry_path).context("Failed to read registry file")?;
 
         serde_json::from_str(&content).context("Failed to parse registry file")
     }
@@ -67,7 +113,7 @@ impl PortRegistry {
     }
 
     /// Get the path to the registry file
-    fn get_registry_path() -> Result<PathBuf> {
+    pub fn get_registry_path() -> Result<PathBuf> {
         let config_dir = get_config_dir()?;
         Ok(config_dir.join("registry.json"))
     }
@@ -132,6 +178,16 @@ impl PortRegistry {
         projects
     }
 
+    /// Projects using a network, as their own network or a shared one
+    pub fn network_users(&self, network: &str) -> Vec<&ProjectEntry> {
+        self.list_projects()
+            .into_iter()
+            .filter(|entry| {
+                entry.network == network || entry.shared_networks.iter().any(|n| n == network)
+            })
+            .collect()
+    }
+
     /// Check if a project is registered by name
     #[allow(dead_code)]
     pub fn is_registered(&self, project_name: &str) -> bool {
@@ -145,6 +201,20 @@ impl PortRegistry {
     }
 }
 
+/// Desired hosts sections of all registered projects
+pub fn hosts_sections() -> Result<Vec<DesiredSection>> {
+    let registry = PortRegistry::load()?;
+    Ok(registry
+        .list_projects()
+        .into_iter()
+        .map(|entry| DesiredSection {
+            project_name: entry.name.clone(),
+            domains: entry.hosts_domains(),
+            addresses: entry.addresses.clone(),
+        })
+        .collect())
+}
+
 impl Default for PortRegistry {
     fn default() -> Self {
         Self::new()
@@ -165,8 +235,14 @@ mod tests {
             path: PathBuf::from("/path/to/project-a"),
             domain: "project-a.local".to_string(),
             network: "project-a-net".to_string(),
+            shared_networks: vec![],
             ports: vec![5432, 6379, 8080],
             containers: vec!["project-a-postgres".to_string()],
+            addresses: vec![],
+            domains: vec![],
+            aliases: vec![],
+            profile: None,
+            compose_project: None,
         };
         registry.projects.insert(entry1.name.clone(), entry1);
 
@@ -190,8 +266,14 @@ mod tests {
             path: PathBuf::from("/path/to/test"),
             domain: "test.local".to_string(),
             network: "test-net".to_string(),
+            shared_networks: vec![],
             ports: vec![5432],
             containers: vec!["test-postgres".to_string()],
+            addresses: vec![],
+            domains: vec![],
+            aliases: vec![],
+            profile: None,
+            compose_project: None,
         };
 
         registry.projects.insert(entry.name.clone(), entry);
@@ -203,4 +285,54 @@ mod tests {
         assert_eq!(retrieved.name, "test-project");
         assert_eq!(retrieved.ports.len(), 1);
     }
+
+    #[test]
+    fn test_network_users() {
+        let mut registry = PortRegistry::new();
+        for (name, shared) in [("shop", vec!["infra-net"]), ("blog", vec![])] {
+            registry.projects.insert(
+                name.to_string(),
+                ProjectEntry {
+                    name: name.to_string(),
+                    path: PathBuf::from(format!("/path/to/{}", name)),
+                    domain: format!("{}.local", name),
+                    network: format!("{}-net", name),
+                    shared_networks: shared.into_iter().map(String::from).collect(),
+                    ports: vec![],
+                    containers: vec![],
+                    addresses: vec![],
+                    domains: vec![],
+                    aliases: vec![],
+                    profile: None,
+                    compose_project: None,
+                },
+            );
+        }
+
+        let users: Vec<&str> = registry
+            .network_users("infra-net")
+            .iter()
+            .map(|entry| entry.name.as_str())
+            .collect();
+        assert_eq!(users, vec!["shop"]);
+        assert_eq!(registry.network_users("blog-net").len(), 1);
+        assert!(registry.network_users("other-net").is_empty());
+    }
+
+    #[test]
+    fn test_hosts_domains() {
+        let mut entry: ProjectEntry = serde_json::from_str(
+            r#"{"name": "shop", "path": "/srv/shop", "domain": "shop.local",
+                "network": "shop-net", "ports": [], "containers": []}"#,
+        )
+        .unwrap();
+        assert_eq!(entry.hosts_domains(), vec!["shop.local"]);
+
+        entry.domains = vec!["shop.local".to_string(), "api.shop.local".to_string()];
+        entry.aliases = vec!["shop.test".to_string(), "api.shop.local".to_string()];
+        assert_eq!(
+            entry.hosts_domains(),
+            vec!["shop.local", "api.shop.local", "shop.test"]
+        );
+    }
 }
@@ -0,0 +1,845 @@
+//! Template services in existing projects (add, rm-service)
+//!
+//! Services generated from templates go to the omd-managed include file next
+//! to the project's compose file (docker-compose.omd.yml), which the compose
+//! file pulls in with `include`, or with `--merge` into the compose file itself.
+//!
+//! The project's compose file is only rewritten through serde_yaml when a
+//! service has to be merged into or removed from it, which drops comments and
+//! anchors; files that have them are refused unless the rewrite is requested.
+//! The `include` entry is added and removed as a line edit.
+
+use std::{collections::HashSet, fs, path::Path};
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde_yaml::{Mapping, Value};
+
+use super::{
+    compose_generator::{build_compose_file, resolve_service_ports, template_routes},
+    config::{Project, load_project, load_project_at},
+    credentials,
+    registry::{PortRegistry, ProjectEntry},
+};
+use crate::{
+    docker::compose::{ComposeInfo, include_paths},
+    proxy,
+    templates::{ServiceTemplate, TemplateCatalog},
+};
+
+/// omd-managed compose file (next to the project's compose file)
+pub const MANAGED_COMPOSE_FILE: &str = "docker-compose.omd.yml";
+
+/// Header written to the managed compose file
+const MANAGED_HEADER: &str =
+    "# Managed by oh-my-dockers (omd add / omd rm-service). Manual edits may be lost.\n";
+
+/// Add template services to the project in the current directory
+/// `merge` writes them into the project's compose file instead of the include file
+pub fn add(names: &[String], merge: bool) -> Result<()> {
+    let project = load_project()?;
+    let config = &project.config;
+    let compose_path = project.compose_path();
+    let managed_path = compose_path.with_file_name(MANAGED_COMPOSE_FILE);
+
+    if !compose_path.exists() {
+        anyhow::bail!(
+            "docker-compose file not found: {}\n\
+            Run 'omd init' to create one with services.",
+            compose_path.display()
+        );
+    }
+
+    // Look up templates (ignoring repeated names)
+    let catalog = TemplateCatalog::load(&project.dir)?;
+    let mut templates: Vec<&ServiceTemplate> = Vec::new();
+    for name in names {
+        let template = catalog.get(name).with_context(|| {
+            format!(
+                "Template '{}' not found. Run 'omd templates list' to see available templates.",
+                name
+            )
+        })?;
+        if !templates.iter().any(|t| t.name == template.name) {
+            templates.push(template);
+        }
+    }
+
+    // Never overwrite services that already exist
+    let compose_info = ComposeInfo::parse(&compose_path)?;
+    let existing: Vec<&str> = templates
+        .iter()
+        .filter(|t| compose_info.services.contains_key(&t.name))
+        .map(|t| t.name.as_str())
+        .collect();
+    if !existing.is_empty() {
+        anyhow::bail!(
+            "Refusing to overwrite existing service(s): {}\n\
+            Remove them first with 'omd rm-service {}'.",
+            existing.join(", "),
+            existing.join(" ")
+        );
+    }
+
+    // Check the compose file can be edited before writing anything
+    let compose_content = read_compose_text(&compose_path)?;
+    let included = if merge {
+        ensure_rewritable(
+            &compose_path,
+            &compose_content,
+            "Run without --merge to add the services to the include file instead.",
+        )?;
+        None
+    } else {
+        add_include_text(&compose_content, MANAGED_COMPOSE_FILE).context(format!(
+            "Add '{}' to the include list of {} by hand, then run the command again",
+            MANAGED_COMPOSE_FILE, config.project.compose_file
+        ))?
+    };
+
+    let mut registry = PortRegistry::load()?;
+    let mut selected =
+        resolve_service_ports(&templates, &registry, &compose_info.get_all_host_ports());
+
+    // Secrets go to the .env file next to the compose file
+    let compose_dir = compose_path.parent().unwrap_or(&project.dir);
+    let variables = credentials::setup(compose_dir, &mut selected, &config.credentials)?;
+
+    let generated = serde_yaml::to_value(build_compose_file(
+        &config.project.name,
+        &config.network.name,
+        &selected,
+    ))
+    .context("Failed to serialize generated services")?;
+
+    let target_path = if merge { &compose_path } else { &managed_path };
+    let mut target = if target_path.exists() {
+        read_compose(target_path)?
+    } else {
+        Value::Mapping(Mapping::new())
+    };
+    merge_generated(&mut target, &generated);
+    write_compose(target_path, &target, !merge)?;
+
+    println!();
+    for service in &selected {
+        println!(
+            "{} Added {} to {}",
+            "✓".green(),
+            service.template.display_name.bright_white(),
+            target_path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+        );
+    }
+
+    if let Some(content) = included {
+        fs::write(&compose_path, content).context(format!(
+            "Failed to write docker-compose file: {:?}",
+            compose_path
+        ))?;
+        println!(
+            "{} Included {} from {}",
+            "✓".green(),
+            MANAGED_COMPOSE_FILE,
+            config.project.compose_file
+        );
+    }
+
+    if !variables.is_empty() {
+        let config_path = project.config_path();
+        let content = fs::read_to_string(&config_path).context("Failed to read omd.toml")?;
+        fs::write(&config_path, credentials::record(&content, &variables)?)
+            .context("Failed to write omd.toml")?;
+        println!(
+            "{} Recorded credentials in {}",
+            "✓".green(),
+            "omd.toml".bright_white()
+        );
+    }
+
+    // Routes are not added automatically because custom routes replace the
+    // auto-generated ones
+    let routes = template_routes(&config.project.name, &selected);
+    if !routes.is_empty() {
+        println!();
+        println!(
+            "{} To route the web UIs, add to [caddy.routes] in omd.toml:",
+            "ℹ".blue()
+        );
+        for (subdomain, target) in &routes {
+            println!("  {} = \"{}\"", subdomain, target);
+        }
+    }
+
+    update_registry(&project, &mut registry)?;
+
+    println!();
+    println!(
+        "Run {} to start the new services",
+        "omd project up".bright_white()
+    );
+
+    Ok(())
+}
+
+/// Remove services from the project in the current directory
+/// `rewrite` allows rewriting a compose file that has comments or anchors
+pub fn remove(names: &[String], rewrite: bool) -> Result<()> {
+    let project = load_project()?;
+    let config = &project.config;
+    let compose_path = project.compose_path();
+    let managed_path = compose_path.with_file_name(MANAGED_COMPOSE_FILE);
+
+    let main_content = read_compose_text(&compose_path)?;
+    let mut main = parse_compose(&main_content)?;
+    let mut managed = if managed_path.exists() {
+        Some(read_compose(&managed_path)?)
+    } else {
+        None
+    };
+
+    // Check all names before changing anything
+    let mut in_main = Vec::new();
+    for name in names {
+        let in_managed = managed.as_ref().is_some_and(|doc| has_service(doc, name));
+        if !in_managed {
+            if !has_service(&main, name) {
+                anyhow::bail!(
+                    "Service '{}' not found in {}",
+                    name,
+                    config.project.compose_file
+                );
+            }
+            in_main.push(name.as_str());
+        }
+    }
+    if !in_main.is_empty() && !rewrite {
+        ensure_rewritable(
+            &compose_path,
+            &main_content,
+            "Remove the service(s) by hand, or pass --rewrite to rewrite the file anyway.",
+        )?;
+    }
+
+    let mut main_modified = false;
+    for name in names {
+        let removed_from_managed = managed
+            .as_mut()
+            .is_some_and(|doc| remove_service(doc, name));
+        let file_name = if removed_from_managed {
+            MANAGED_COMPOSE_FILE
+        } else {
+            main_modified |= remove_service(&mut main, name);
+            config.project.compose_file.as_str()
+        };
+
+        println!(
+            "{} Removed {} from {}",
+            "✓".green(),
+            name.bright_white(),
+            file_name
+        );
+    }
+
+    // Services that still depend on removed ones
+    for doc in std::iter::once(&main).chain(managed.as_ref()) {
+        for (service, dependency) in dependents(doc, names) {
+            println!(
+                "{} Service {} still depends on {}",
+                "⚠".yellow(),
+                service.bright_white(),
+                dependency
+            );
+        }
+    }
+
+    // The compose file is rewritten if a service was removed from it, or
+    // else only loses the include entry of an emptied managed file
+    let mut main_text = None;
+    if let Some(doc) = &managed {
+        if service_names(doc).is_empty() {
+            if main_modified {
+                remove_include(&mut main, MANAGED_COMPOSE_FILE);
+            } else {
+                main_text = remove_include_text(&main_content, MANAGED_COMPOSE_FILE)?;
+            }
+            fs::remove_file(&managed_path)
+                .context(format!("Failed to remove {}", managed_path.display()))?;
+            println!(
+                "{} Removed {} (no services left)",
+                "✓".green(),
+                MANAGED_COMPOSE_FILE
+            );
+        } else {
+            write_compose(&managed_path, doc, true)?;
+        }
+    }
+
+    if main_modified {
+        write_compose(&compose_path, &main, false)?;
+    } else if let Some(content) = main_text {
+        fs::write(&compose_path, content).context(format!(
+            "Failed to write docker-compose file: {:?}",
+            compose_path
+        ))?;
+    }
+
+    // Values stay in .env so a re-added service matches its existing volume
+    let config_path = project.config_path();
+    let content = fs::read_to_string(&config_path).context("Failed to read omd.toml")?;
+    if let Some(content) = credentials::forget(&content, names)? {
+        fs::write(&config_path, content).context("Failed to write omd.toml")?;
+        println!(
+            "{} Removed credentials from {} (values are kept in {})",
+            "✓".green(),
+            "omd.toml".bright_white(),
+            credentials::ENV_FILE
+        );
+    }
+
+    let mut registry = PortRegistry::load()?;
+    update_registry(&project, &mut registry)?;
+
+    println!();
+    println!(
+        "{} Containers of removed services keep running until {} or {}",
+        "ℹ".blue(),
+        "omd project down".bright_white(),
+        "docker compose up -d --remove-orphans".bright_white()
+    );
+
+    Ok(())
+}
+
+/// Refresh the registry entry of a registered project from its compose files
+fn update_registry(project: &Project, registry: &mut PortRegistry) -> Result<()> {
+    let Some(entry) = registry.get_project(&project.config.project.name).cloned() else {
+        println!(
+            "{} Project is not registered yet; ports are recorded on {}",
+            "ℹ".blue(),
+            "omd project up".bright_white()
+        );
+        return Ok(());
+    };
+
+    // Re-read omd.toml and use the profile the project was started with
+    let mut project = load_project_at(&project.config_path())?;
+    if let Some(profile) = &entry.profile {
+        project.select_profile(profile)?;
+    }
+    project.resolve_compose_name(entry.compose_project.as_deref());
+    let config = &project.config;
+
+    let compose_info = project.compose_info()?;
+    let mut domains = vec![config.project.domain.clone()];
+    domains.extend(
+        proxy::collect_routes(config, &compose_info, project.compose_name())
+            .into_iter()
+            .map(|route| route.domain),
+    );
+
+    registry.register_project(ProjectEntry {
+        ports: compose_info.get_all_host_ports(),
+        containers: compose_info.get_all_container_names(project.compose_name()),
+        domains,
+        shared_networks: config.network.shared.clone(),
+        ..entry
+    })?;
+
+    println!("{} Updated project registry", "✓".green());
+    Ok(())
+}
+
+/// Read a compose file as YAML (an empty file is an empty mapping)
+fn read_compose(path: &Path) -> Result<Value> {
+    parse_compose(&read_compose_text(path)?)
+}
+
+fn read_compose_text(path: &Path) -> Result<String> {
+    fs::read_to_string(path).context(format!("Failed to read docker-compose file: {:?}", path))
+}
+
+/// Parse compose YAML (an empty document is an empty mapping)
+fn parse_compose(content: &str) -> Result<Value> {
+    let yaml: Value =
+        serde_yaml::from_str(content).context("Failed to parse docker-compose YAML")?;
+
+    Ok(if yaml.is_null() {
+        Value::Mapping(Mapping::new())
+    } else {
+        yaml
+    })
+}
+
+/// Refuse to rewrite a compose file whose comments or anchors would be lost
+fn ensure_rewritable(path: &Path, content: &str, hint: &str) -> Result<()> {
+    if has_comments_or_anchors(content) {
+        anyhow::bail!(
+            "{} has comments or YAML anchors, which rewriting it would drop.\n{}",
+            path.display(),
+            hint
+        );
+    }
+    Ok(())
+}
+
+/// Check YAML text for comments and anchors/aliases outside quoted strings
+/// Errs on the side of reporting them (e.g. `#` inside block scalars)
+fn has_comments_or_anchors(content: &str) -> bool {
+    content.lines().any(|line| {
+        let mut quote = None;
+        let mut prev = ' ';
+        let mut chars = line.chars().peekable();
+        while let Some(c) = chars.next() {
+            match quote {
+                Some(q) if c == q => quote = None,
+                Some(_) => {}
+                None => {
+                    let token_start = prev.is_whitespace() || "[{,".contains(prev);
+                    match c {
+                        '#' if prev.is_whitespace() => return true,
+                        '&' | '*'
+                            if token_start && chars.peek().is_some_and(|n| n.is_alphanumeric()) =>
+                        {
+                            return true;
+                        }
+                        '\'' | '"' if token_start || prev == ':' => quote = Some(c),
+                        _ => {}
+                    }
+                }
+            }
+            prev = c;
+        }
+        false
+    })
+}
+
+/// Write a compose file, with the managed header for the include file
+fn write_compose(path: &Path, yaml: &Value, managed: bool) -> Result<()> {
+    let mut content = if managed {
+        MANAGED_HEADER.to_string()
+    } else {
+        String::new()
+    };
+    content.push_str(&serde_yaml::to_string(yaml).context("Failed to serialize compose YAML")?);
+
+    fs::write(path, content).context(format!("Failed to write docker-compose file: {:?}", path))
+}
+
+/// Get or create a top-level mapping section
+fn section_mut<'a>(doc: &'a mut Value, key: &str) -> Option<&'a mut Mapping> {
+    let root = doc.as_mapping_mut()?;
+    let entry = root
+        .entry(Value::String(key.to_string()))
+        .or_insert(Value::Mapping(Mapping::new()));
+    if entry.is_null() {
+        *entry = Value::Mapping(Mapping::new());
+    }
+    entry.as_mapping_mut()
+}
+
+/// Merge generated services, volumes and networks into a compose document
+/// Existing entries are kept
+fn merge_generated(doc: &mut Value, generated: &Value) {
+    for key in ["services", "volumes", "networks"] {
+        let Some(source) = generated.get(key).and_then(|v| v.as_mapping()) else {
+            continue;
+        };
+        let Some(target) = section_mut(doc, key) else {
+            continue;
+        };
+
+        for (name, value) in source {
+            if !target.contains_key(name) {
+                target.insert(name.clone(), value.clone());
+            }
+        }
+    }
+}
+
+/// Service names of a compose document
+fn service_names(doc: &Value) -> Vec<String> {
+    doc.get("services")
+        .and_then(|v| v.as_mapping())
+        .map(|services| {
+            services
+                .keys()
+                .filter_map(|k| k.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Check if a compose document defines a service
+fn has_service(doc: &Value, name: &str) -> bool {
+    service_names(doc).iter().any(|s| s == name)
+}
+
+/// Named volumes referenced by a service
+fn service_volumes(service: &Value) -> Vec<String> {
+    let Some(volumes) = service.get("volumes").and_then(|v| v.as_sequence()) else {
+        return Vec::new();
+    };
+
+    volumes
+        .iter()
+        .filter_map(|volume| match volume {
+            Value::String(s) => s.split_once(':').map(|(source, _)| source.to_string()),
+            other => other
+                .get("source")
+                .and_then(|v| v.as_str())
+                .map(str::to_string),
+        })
+        .collect()
+}
+
+/// Remove a service and the named volumes only it used
+/// Returns true if the service existed
+fn remove_service(doc: &mut Value, name: &str) -> bool {
+    let Some(services) = doc.get_mut("services").and_then(|v| v.as_mapping_mut()) else {
+        return false;
+    };
+    let Some(removed) = services.remove(name) else {
+        return false;
+    };
+
+    let still_used: HashSet<String> = services.values().flat_map(service_volumes).collect();
+
+    if let Some(volumes) = doc.get_mut("volumes").and_then(|v| v.as_mapping_mut()) {
+        for volume in service_volumes(&removed) {
+            if !still_used.contains(&volume) {
+                volumes.remove(volume.as_str());
+            }
+        }
+        if volumes.is_empty()
+            && let Some(root) = doc.as_mapping_mut()
+        {
+            root.remove("volumes");
+        }
+    }
+
+    true
+}
+
+/// Services that depend on any of the given services as (service, dependency)
+fn dependents(doc: &Value, names: &[String]) -> Vec<(String, String)> {
+    let Some(services) = doc.get("services").and_then(|v| v.as_mapping()) else {
+        return Vec::new();
+    };
+
+    let mut result = Vec::new();
+    for (service, config) in services {
+        let Some(service) = service.as_str() else {
+            continue;
+        };
+        let depends_on: Vec<String> = match config.get("depends_on") {
+            Some(Value::Sequence(list)) => list
+                .iter()
+                .filter_map(|v| v.as_str().map(str::to_string))
+                .collect(),
+            Some(Value::Mapping(map)) => map
+                .keys()
+                .filter_map(|k| k.as_str().map(str::to_string))
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        for dependency in depends_on {
+            if names.contains(&dependency) {
+                result.push((service.to_string(), dependency));
+            }
+        }
+    }
+
+    result
+}
+
+/// Top-level `include` key of compose text as (line index, value after the colon)
+fn find_include_line(lines: &[&str]) -> Option<(usize, String)> {
+    lines.iter().enumerate().find_map(|(i, line)| {
+        let value = line.strip_prefix("include:")?;
+        let value = match value.find(" #") {
+            Some(comment) => &value[..comment],
+            None => value,
+        };
+        Some((i, value.trim().to_string()))
+    })
+}
+
+/// Lines of the block-style `include` list starting after line `start`
+/// Returns the end (exclusive) and the indentation of the entries
+fn include_block(lines: &[&str], start: usize) -> (usize, String) {
+    let mut end = start + 1;
+    let mut indent = None;
+    for (i, line) in lines.iter().enumerate().skip(start + 1) {
+        let trimmed = line.trim_start();
+        let is_entry = trimmed.starts_with("- ") || trimmed == "-";
+        if !trimmed.is_empty() && !line.starts_with([' ', '\t']) && !is_entry {
+            break;
+        }
+        if is_entry && indent.is_none() {
+            indent = Some(line[..line.len() - trimmed.len()].to_string());
+        }
+        if !trimmed.is_empty() {
+            end = i + 1;
+        }
+    }
+    (end, indent.unwrap_or_else(|| "  ".to_string()))
+}
+
+/// Check that an edited compose text only differs in the include list
+fn check_include_edit(before: &str, after: &str, file: &str, included: bool) -> Result<()> {
+    let mut before = parse_compose(before)?;
+    let mut after = parse_compose(after)?;
+    let ok = include_paths(&after).iter().any(|p| p == file) == included && {
+        remove_include(&mut before, file);
+        remove_include(&mut after, file);
+        before == after
+    };
+    if !ok {
+        anyhow::bail!("Failed to edit the include list of the compose file");
+    }
+    Ok(())
+}
+
+/// Add a file to the top-level `include` list of compose text, leaving the
+/// other lines as they are
+/// Returns None if the file is already included
+fn add_include_text(content: &str, file: &str) -> Result<Option<String>> {
+    if include_paths(&parse_compose(content)?)
+        .iter()
+        .any(|p| p == file)
+    {
+        return Ok(None);
+    }
+
+    let lines: Vec<&str> = content.lines().collect();
+    let edited = match find_include_line(&lines) {
+        // Append a new list
+        None => {
+            let mut edited = content.to_string();
+            if !edited.is_empty() {
+                if !edited.ends_with('\n') {
+                    edited.push('\n');
+                }
+                edited.push('\n');
+            }
+            edited.push_str(&format!("include:\n  - {}\n", file));
+            edited
+        }
+        // Add an entry after the last one of the list
+        Some((start, value)) if value.is_empty() => {
+            let (end, indent) = include_block(&lines, start);
+            let mut edited: Vec<String> = lines.iter().map(|l| l.to_string()).collect();
+            edited.insert(end, format!("{}- {}", indent, file));
+            edited.join("\n") + "\n"
+        }
+        // Add an entry to a flow list: `include: [a.yml]`
+        Some((start, value)) if value.starts_with('[') && value.ends_with(']') => {
+            let inner = value[1..value.len() - 1].trim();
+            let list = if inner.is_empty() {
+                format!("[{}]", file)
+            } else {
+                format!("[{}, {}]", inner, file)
+            };
+            let mut edited: Vec<String> = lines.iter().map(|l| l.to_string()).collect();
+            edited[start] = lines[start].replacen(&value, &list, 1);
+            edited.join("\n") + "\n"
+        }
+        Some(_) => anyhow::bail!("The include list of the compose file has an unknown format"),
+    };
+
+    check_include_edit(content, &edited, file, true)?;
+    Ok(Some(edited))
+}
+
+/// Remove a file (short syntax) from the top-level `include` list of compose
+/// text, leaving the other lines as they are
+/// Returns None if the file is not included
+fn remove_include_text(content: &str, file: &str) -> Result<Option<String>> {
+    if !include_paths(&parse_compose(content)?)
+        .iter()
+        .any(|p| p == file)
+    {
+        return Ok(None);
+    }
+
+    let is_file = |entry: &str| entry.trim().trim_matches(['"', '\'']) == file;
+    let lines: Vec<&str> = content.lines().collect();
+    let mut edited: Vec<String> = lines.iter().map(|l| l.to_string()).collect();
+    match find_include_line(&lines) {
+        Some((start, value)) if value.is_empty() => {
+            let (end, _) = include_block(&lines, start);
+            let entries: Vec<usize> = (start + 1..end)
+                .filter(|&i| lines[i].trim_start().starts_with('-'))
+                .collect();
+            if entries.len() == 1 {
+                // Drop the whole list, and the blank line that separated it
+                // at the end of the file
+                let mut from = start;
+                if end == lines.len() && from > 0 && lines[from - 1].trim().is_empty() {
+                    from -= 1;
+                }
+                edited.drain(from..end);
+            } else if let Some(&i) = entries
+                .iter()
+                .find(|&&i| is_file(lines[i].trim_start().trim_start_matches('-')))
+            {
+                edited.remove(i);
+            }
+        }
+        Some((start, value)) if value.starts_with('[') && value.ends_with(']') => {
+            let entries: Vec<&str> = value[1..value.len() - 1]
+                .split(',')
+                .map(str::trim)
+                .filter(|entry| !entry.is_empty() && !is_file(entry))
+                .collect();
+            if entries.is_empty() {
+                edited.remove(start);
+            } else {
+                edited[start] =
+                    lines[start].replacen(&value, &format!("[{}]", entries.join(", ")), 1);
+            }
+        }
+        _ => {}
+    }
+
+    let edited = if edited.is_empty() {
+        String::new()
+    } else {
+        edited.join("\n") + "\n"
+    };
+    check_include_edit(content, &edited, file, false)
+        .context(format!("Remove '{}' from its include list by hand", file))?;
+    Ok(Some(edited))
+}
+
+/// Remove a file (short syntax) from the top-level `include` list
+/// Returns true if the document was modified
+fn remove_include(doc: &mut Value, file: &str) -> bool {
+    let Some(list) = doc.get_mut("include").and_then(|v| v.as_sequence_mut()) else {
+        return false;
+    };
+
+    let before = list.len();
+    list.retain(|v| v.as_str() != Some(file));
+    let modified = list.len() != before;
+
+    if list.is_empty()
+        && let Some(root) = doc.as_mapping_mut()
+    {
+        root.remove("include");
+    }
+
+    modified
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn yaml(content: &str) -> Value {
+        serde_yaml::from_str(content).unwrap()
+    }
+
+    #[test]
+    fn test_merge_generated_keeps_existing_entries() {
+        let mut doc = yaml(
+            "services:\n  app:\n    image: app:1\nnetworks:\n  shop-net:\n    external: true\n",
+        );
+        let generated = yaml(
+            "services:\n  redis:\n    image: redis:8.2\n    volumes:\n      - redis_data:/data\nvolumes:\n  redis_data:\nnetworks:\n  shop-net:\n    external: false\n",
+        );
+
+        merge_generated(&mut doc, &generated);
+
+        assert_eq!(service_names(&doc), vec!["app", "redis"]);
+        assert!(
+            doc["volumes"]
+                .as_mapping()
+                .unwrap()
+                .contains_key("redis_data")
+        );
+        assert_eq!(doc["networks"]["shop-net"]["external"], true);
+    }
+
+    #[test]
+    fn test_remove_service_prunes_unused_volumes() {
+        let mut doc = yaml(
+            "services:\n  app:\n    image: app:1\n    volumes:\n      - shared:/shared\n    depends_on:\n      - redis\n  redis:\n    image: redis:8.2\n    volumes:\n      - redis_data:/data\n      - shared:/shared\nvolumes:\n  redis_data:\n  shared:\n",
+        );
+
+        assert!(remove_service(&mut doc, "redis"));
+        assert!(!remove_service(&mut doc, "redis"));
+
+        assert_eq!(service_names(&doc), vec!["app"]);
+        let volumes = doc["volumes"].as_mapping().unwrap();
+        assert!(!volumes.contains_key("redis_data"));
+        assert!(volumes.contains_key("shared"));
+
+        assert_eq!(
+            dependents(&doc, &["redis".to_string()]),
+            vec![("app".to_string(), "redis".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_has_comments_or_anchors() {
+        assert!(!has_comments_or_anchors(
+            "services:\n  app:\n    image: app:1\n    command: [\"sh\", \"-c\", \"ls *.txt # all\"]\n    environment:\n      URL: http://app/#top\n"
+        ));
+        assert!(has_comments_or_anchors(
+            "# Shop\nservices:\n  app:\n    image: app:1\n"
+        ));
+        assert!(has_comments_or_anchors(
+            "services:\n  app:\n    image: app:1 # pinned\n"
+        ));
+        assert!(has_comments_or_anchors(
+            "x-env: &env\n  TZ: UTC\nservices:\n  app:\n    environment: *env\n"
+        ));
+    }
+
+    #[test]
+    fn test_include_text_edits() {
+        let file = MANAGED_COMPOSE_FILE;
+
+        // A new list is appended, the rest of the file is left as it is
+        let original = "# Shop\nservices:\n  app: &app\n    image: app:1 # pinned\n";
+        let added = add_include_text(original, file).unwrap().unwrap();
+        assert_eq!(added, format!("{}\ninclude:\n  - {}\n", original, file));
+        assert_eq!(add_include_text(&added, file).unwrap(), None);
+        assert_eq!(
+            remove_include_text(&added, file).unwrap().as_deref(),
+            Some(original)
+        );
+        assert_eq!(remove_include_text(original, file).unwrap(), None);
+
+        // Entries are added to and removed from an existing list
+        let original = "include:\n- db.yml # database\n\nservices:\n  app:\n    image: app:1\n";
+        let added = add_include_text(original, file).unwrap().unwrap();
+        assert_eq!(
+            added,
+            format!(
+                "include:\n- db.yml # database\n- {}\n\nservices:\n  app:\n    image: app:1\n",
+                file
+            )
+        );
+        assert_eq!(
+            remove_include_text(&added, file).unwrap().as_deref(),
+            Some(original)
+        );
+
+        let original = "include: [db.yml]\nservices: {}\n";
+        let added = add_include_text(original, file).unwrap().unwrap();
+        assert_eq!(
+            added,
+            format!("include: [db.yml, {}]\nservices: {{}}\n", file)
+        );
+        assert_eq!(
+            remove_include_text(&added, file).unwrap().as_deref(),
+            Some(original)
+        );
+    }
+}
@@ -0,0 +1,533 @@
+//! Watch mode (omd watch)
+//!
+//! Watches omd.toml and the project's compose files (including the files
+//! they include) and, on change, re-runs only the steps of `omd project up`
+//! whose result changed: compose parsing, the proxy route file and reload,
+//! the registry and the hosts file. Containers are left alone unless
+//! `--restart` is given.
+//!
+//! Directories are watched rather than files, because editors often save by
+//! writing a new file and renaming it over the old one.
+
+use std::{
+    collections::BTreeSet,
+    fs,
+    net::IpAddr,
+    path::{Path, PathBuf},
+    sync::mpsc::{Receiver, RecvTimeoutError, channel},
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+use super::{
+    compose_override,
+    config::{Project, load_project_at},
+    passthrough,
+    registry::{PortRegistry, ProjectEntry},
+};
+use crate::{
+    config::{load_global_config, validate},
+    docker::compose::{ComposeInfo, compose_file_tree},
+    proxy::{self, Route},
+    system::hosts,
+};
+
+/// Quiet period after a change before acting, so a burst of events from one
+/// save is handled once
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// What `omd project up` derives from the watched files
+#[derive(Debug, Default, PartialEq)]
+struct Snapshot {
+    routes: Vec<Route>,
+    /// Project domain and route domains
+    domains: Vec<String>,
+    aliases: Vec<String>,
+    addresses: Vec<IpAddr>,
+    ports: Vec<u16>,
+    containers: Vec<String>,
+}
+
+impl Snapshot {
+    fn new(project: &Project, compose_info: &ComposeInfo) -> Self {
+        let config = &project.config;
+        let routes = proxy::collect_routes(config, compose_info, project.compose_name());
+        let mut domains = vec![config.project.domain.clone()];
+        domains.extend(routes.iter().map(|route| route.domain.clone()));
+
+        Self {
+            routes,
+            domains,
+            aliases: config.hosts.aliases.clone(),
+            addresses: config.hosts.addresses.clone(),
+            ports: compose_info.get_all_host_ports(),
+            containers: compose_info.get_all_container_names(project.compose_name()),
+        }
+    }
+}
+
+/// The watched files of a project: omd.toml and the compose files
+struct WatchedFiles {
+    config: PathBuf,
+    compose: Vec<PathBuf>,
+}
+
+impl WatchedFiles {
+    fn of(project: &Project) -> Self {
+        let paths: Vec<PathBuf> = project
+            .compose_files()
+            .iter()
+            .map(|file| project.dir.join(file))
+            .collect();
+        Self {
+            config: normalize(&project.config_path()),
+            compose: compose_file_tree(&paths)
+                .iter()
+                .map(|p| normalize(p))
+                .collect(),
+        }
+    }
+
+    fn contains(&self, path: &Path) -> bool {
+        self.config == path || self.compose.iter().any(|file| file == path)
+    }
+
+    /// Directories to watch
+    fn dirs(&self) -> BTreeSet<PathBuf> {
+        std::iter::once(&self.config)
+            .chain(&self.compose)
+            .filter_map(|path| path.parent().map(Path::to_path_buf))
+            .collect()
+    }
+}
+
+/// Path with a canonical directory part, so event paths and configured paths
+/// compare equal (the file itself may not exist between a delete and a rename)
+fn normalize(path: &Path) -> PathBuf {
+    match (path.parent(), path.file_name()) {
+        (Some(dir), Some(name)) => fs::canonicalize(dir)
+            .unwrap_or_else(|_| dir.to_path_buf())
+            .join(name),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// Watch a project and apply changes until interrupted
+/// `name` selects a registered project instead of the current directory
+pub fn watch(name: Option<&str>, restart: bool) -> Result<()> {
+    let project = passthrough::resolve(name)?;
+    let project_name = project.config.project.name.clone();
+    if PortRegistry::load()?.get_project(&project_name).is_none() {
+        anyhow::bail!(
+            "Project '{}' is not registered. Run 'omd project up' first, then 'omd watch'.",
+            project_name
+        );
+    }
+
+    let config_path = project.config_path();
+    let mut snapshot = Snapshot::new(&project, &project.compose_info()?);
+    let mut files = WatchedFiles::of(&project);
+
+    let (tx, rx) = channel();
+    let mut watcher = notify::recommended_watcher(tx).context("Failed to start file watcher")?;
+    let mut dirs = BTreeSet::new();
+    watch_dirs(&mut watcher, &mut dirs, &files)?;
+
+    println!(
+        "{} Watching {} (Ctrl+C to stop)",
+        "ℹ".blue(),
+        project_name.bright_white()
+    );
+    for file in std::iter::once(&files.config).chain(&files.compose) {
+        println!("  {}", display_path(file, &project.dir));
+    }
+    if restart {
+        println!(
+            "{} Containers are updated with 'docker compose up -d' when compose files change",
+            "ℹ".blue()
+        );
+    }
+
+    loop {
+        let changed = next_changes(&rx, &files)?;
+        let config_changed = changed.contains(&files.config);
+        let compose_changed = changed.iter().any(|path| *path != files.config);
+
+        println!();
+        let names: Vec<String> = changed
+            .iter()
+            .map(|path| display_path(path, &project.dir))
+            .collect();
+        println!(
+            "{} [{}] Changed: {}",
+            "ℹ".blue(),
+            chrono::Local::now().format("%H:%M:%S"),
+            names.join(", ")
+        );
+
+        match apply(
+            &project_name,
+            &config_path,
+            &snapshot,
+            config_changed,
+            compose_changed && restart,
+        ) {
+            Ok((project, next)) => {
+                snapshot = next;
+                files = WatchedFiles::of(&project);
+                watch_dirs(&mut watcher, &mut dirs, &files)?;
+                if compose_changed && !restart {
+                    println!(
+                        "{} Containers were not changed. Run {} to apply compose changes.",
+                        "ℹ".blue(),
+                        "omd project up".bright_white()
+                    );
+                }
+            }
+            Err(e) => {
+                println!("{} {:#}", "✗".red(), e);
+                println!("{} Waiting for the next change...", "ℹ".blue());
+            }
+        }
+    }
+}
+
+/// Start watching directories that are not watched yet
+fn watch_dirs(
+    watcher: &mut RecommendedWatcher,
+    watched: &mut BTreeSet<PathBuf>,
+    files: &WatchedFiles,
+) -> Result<()> {
+    for dir in files.dirs() {
+        if watched.contains(&dir) {
+            continue;
+        }
+        watcher
+            .watch(&dir, RecursiveMode::NonRecursive)
+            .context(format!("Failed to watch {}", dir.display()))?;
+        watched.insert(dir);
+    }
+    Ok(())
+}
+
+/// Wait for changes to the watched files and return them once events stop
+fn next_changes(
+    rx: &Receiver<notify::Result<Event>>,
+    files: &WatchedFiles,
+) -> Result<Vec<PathBuf>> {
+    let mut changed: BTreeSet<PathBuf> = BTreeSet::new();
+    loop {
+        let received = if changed.is_empty() {
+            rx.recv().map_err(|_| RecvTimeoutError::Disconnected)
+        } else {
+            rx.recv_timeout(DEBOUNCE)
+        };
+
+        match received {
+            Ok(Ok(event)) => {
+                if matches!(event.kind, EventKind::Access(_)) {
+                    continue;
+                }
+                changed.extend(
+                    event
+                        .paths
+                        .iter()
+                        .map(|path| normalize(path))
+                        .filter(|path| files.contains(path)),
+                );
+            }
+            Ok(Err(e)) => println!("{} File watcher error: {}", "⚠".yellow(), e),
+            Err(RecvTimeoutError::Timeout) => return Ok(changed.into_iter().collect()),
+            Err(RecvTimeoutError::Disconnected) => anyhow::bail!("File watcher stopped"),
+        }
+    }
+}
+
+/// Re-read the project and re-run the steps whose result changed
+/// Returns the reloaded project and the new snapshot
+fn apply(
+    project_name: &str,
+    config_path: &Path,
+    previous: &Snapshot,
+    config_changed: bool,
+    up: bool,
+) -> Result<(Project, Snapshot)> {
+    if config_changed {
+        validate::ensure_valid(config_path)?;
+    }
+
+    let mut registry = PortRegistry::load()?;
+    let entry = registry
+        .get_project(project_name)
+        .cloned()
+        .context(format!(
+            "Project '{}' is no longer registered",
+            project_name
+        ))?;
+
+    let mut project = load_project_at(config_path)?;
+    if project.config.project.name != project_name {
+        anyhow::bail!(
+            "The project name changed to '{}'. Stop 'omd watch' and run 'omd project up'.",
+            project.config.project.name
+        );
+    }
+    if let Some(profile) = &entry.profile {
+        project.select_profile(profile)?;
+    }
+    project.resolve_compose_name(entry.compose_project.as_deref());
+
+    let compose_info = project.compose_info()?;
+    let snapshot = Snapshot::new(&project, &compose_info);
+
+    // Keep the override in line with the compose files for the next start
+    let global_config = load_global_config()?;
+    compose_override::write_override(&project, &global_config)?;
+
+    let changes = describe_changes(previous, &snapshot);
+    if changes.is_empty() {
+        println!("{} No changes to routes, domains or ports", "✓".green());
+    }
+    for change in &changes {
+        let (sign, rest) = change.split_at(1);
+        let sign = match sign {
+            "+" => sign.green(),
+            "-" => sign.red(),
+            _ => sign.yellow(),
+        };
+        println!("  {}{}", sign, rest);
+    }
+
+    if snapshot.ports != previous.ports {
+        for (port, other) in registry.check_port_conflicts(project_name, &snapshot.ports) {
+            println!(
+                "{} Port {} is already used by project {}",
+                "⚠".yellow(),
+                port.to_string().red(),
+                other.bright_white()
+            );
+        }
+    }
+
+    if snapshot.routes != previous.routes || snapshot.domains != previous.domains {
+        let backend = proxy::backend_for(&global_config);
+        proxy::generate_project_config(
+            backend.as_ref(),
+            &project.config,
+            &compose_info,
+            project.compose_name(),
+        )?;
+        if backend.is_running() {
+            backend.reload()?;
+        } else {
+            println!(
+                "{} {} is not running; the routes apply when it starts",
+                "⚠".yellow(),
+                backend.container_name()
+            );
+        }
+    }
+
+    let entry = ProjectEntry {
+        ports: snapshot.ports.clone(),
+        containers: snapshot.containers.clone(),
+        domains: snapshot.domains.clone(),
+        aliases: snapshot.aliases.clone(),
+        addresses: snapshot.addresses.clone(),
+        ..entry
+    };
+    let hosts_domains = entry.hosts_domains();
+    if !changes.is_empty() {
+        registry.register_project(entry)?;
+    }
+
+    if snapshot.domains != previous.domains
+        || snapshot.aliases != previous.aliases
+        || snapshot.addresses != previous.addresses
+    {
+        println!("{} Updating hosts file...", "ℹ".blue());
+        if let Err(e) =
+            hosts::add_project_domains(project_name, &hosts_domains, &snapshot.addresses)
+        {
+            println!(
+                "{} Warning: Failed to update hosts file: {}",
+                "⚠".yellow(),
+                e
+            );
+        }
+    }
+
+    if up {
+        println!("{} Updating containers...", "ℹ".blue());
+        let status = passthrough::compose(&project, &["up".to_string(), "-d".to_string()])?;
+        if !status.success() {
+            anyhow::bail!("docker compose up failed (exit code: {:?})", status.code());
+        }
+        println!("{} Containers updated", "✓".green());
+    }
+
+    Ok((project, snapshot))
+}
+
+/// One line per changed route, domain, port set, address set or container,
+/// starting with `+` (added), `-` (removed) or `~` (changed)
+fn describe_changes(old: &Snapshot, new: &Snapshot) -> Vec<String> {
+    let mut changes = Vec::new();
+
+    for route in &new.routes {
+        match old.routes.iter().find(|r| r.domain == route.domain) {
+            None => changes.push(format!("+ route {} -> {}", route.domain, route.target)),
+            Some(previous) if previous.target != route.target => changes.push(format!(
+                "~ route {}: {} -> {}",
+                route.domain, previous.target, route.target
+            )),
+            Some(_) => {}
+        }
+    }
+    for route in &old.routes {
+        if !new.routes.iter().any(|r| r.domain == route.domain) {
+            changes.push(format!("- route {} -> {}", route.domain, route.target));
+        }
+    }
+
+    // Route domains are described with their routes
+    let route_domains: Vec<&String> = old
+        .routes
+        .iter()
+        .chain(&new.routes)
+        .map(|route| &route.domain)
+        .collect();
+    for domain in &new.domains {
+        if !old.domains.contains(domain) && !route_domains.contains(&domain) {
+            changes.push(format!("+ domain {}", domain));
+        }
+    }
+    for domain in &old.domains {
+        if !new.domains.contains(domain) && !route_domains.contains(&domain) {
+            changes.push(format!("- domain {}", domain));
+        }
+    }
+
+    for alias in &new.aliases {
+        if !old.aliases.contains(alias) {
+            changes.push(format!("+ alias {}", alias));
+        }
+    }
+    for alias in &old.aliases {
+        if !new.aliases.contains(alias) {
+            changes.push(format!("- alias {}", alias));
+        }
+    }
+
+    if old.ports != new.ports {
+        changes.push(format!(
+            "~ ports: {} -> {}",
+            join(&old.ports),
+            join(&new.ports)
+        ));
+    }
+    if old.addresses != new.addresses {
+        changes.push(format!(
+            "~ addresses: {} -> {}",
+            join(&old.addresses),
+            join(&new.addresses)
+        ));
+    }
+
+    for container in &new.containers {
+        if !old.containers.contains(container) {
+            changes.push(format!("+ container {}", container));
+        }
+    }
+    for container in &old.containers {
+        if !new.containers.contains(container) {
+            changes.push(format!("- container {}", container));
+        }
+    }
+
+    changes
+}
+
+fn join<T: ToString>(items: &[T]) -> String {
+    if items.is_empty() {
+        return "none".to_string();
+    }
+    items
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Path relative to the project directory when inside it
+fn display_path(path: &Path, project_dir: &Path) -> String {
+    let project_dir = fs::canonicalize(project_dir).unwrap_or_else(|_| project_dir.to_path_buf());
+    path.strip_prefix(&project_dir)
+        .unwrap_or(path)
+        .display()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn route(domain: &str, target: &str) -> Route {
+        Route {
+            domain: domain.to_string(),
+            target: target.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_describe_changes() {
+        let old = Snapshot {
+            routes: vec![
+                route("api.shop.local", "shop-api-1:8080"),
+                route("web.shop.local", "shop-web-1:3000"),
+            ],
+            domains: vec![
+                "shop.local".to_string(),
+                "api.shop.local".to_string(),
+                "web.shop.local".to_string(),
+            ],
+            aliases: vec!["shop.test".to_string()],
+            addresses: vec![],
+            ports: vec![5432],
+            containers: vec!["shop-api-1".to_string(), "shop-web-1".to_string()],
+        };
+        let new = Snapshot {
+            routes: vec![
+                route("api.shop.local", "shop-api-1:9090"),
+                route("admin.shop.local", "shop-admin-1:80"),
+            ],
+            domains: vec![
+                "shop.local".to_string(),
+                "api.shop.local".to_string(),
+                "admin.shop.local".to_string(),
+            ],
+            aliases: vec!["db.shop.local".to_string()],
+            addresses: vec![],
+            ports: vec![5432, 6379],
+            containers: vec!["shop-api-1".to_string(), "shop-admin-1".to_string()],
+        };
+
+        assert_eq!(
+            describe_changes(&old, &new),
+            vec![
+                "~ route api.shop.local: shop-api-1:8080 -> shop-api-1:9090",
+                "+ route admin.shop.local -> shop-admin-1:80",
+                "- route web.shop.local -> shop-web-1:3000",
+                "+ alias db.shop.local",
+                "- alias shop.test",
+                "~ ports: 5432 -> 5432, 6379",
+                "+ container shop-admin-1",
+                "- container shop-web-1",
+            ]
+        );
+        assert!(describe_changes(&new, &new).is_empty());
+    }
+}
@@ -0,0 +1,141 @@
+//! Local certificate management
+//!
+//! This module generates mkcert certificates for project domains and decides
+//! which TLS mode the proxy backends should render.
+
+use std::{fs, path::Path, process::Command};
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+
+use super::TlsMode;
+use crate::config::{GlobalConfig, get_config_dir};
+
+/// Resolve the TLS mode for a project domain
+///
+/// When HTTPS is enabled, a project certificate (main domain + wildcard) is
+/// generated with mkcert if it does not exist yet. If that fails, the proxy's
+/// internal certificate is used instead.
+pub fn resolve_tls_mode(domain: &str, global_config: &GlobalConfig) -> Result<TlsMode> {
+    if !global_config.global.enable_https {
+        return Ok(TlsMode::Disabled);
+    }
+
+    let config_dir = get_config_dir()?;
+    let certs_dir = config_dir.join(&global_config.global.caddy_certs_dir);
+    let cert_name = domain.replace('.', "_");
+    let cert_file = certs_dir.join(format!("{}.crt", cert_name));
+    let key_file = certs_dir.join(format!("{}.key", cert_name));
+
+    if (!cert_file.exists() || !key_file.exists())
+        && let Err(e) = generate_project_certificate(domain, &cert_file, &key_file)
+    {
+        println!(
+            "{} Failed to generate project certificate: {}",
+            "⚠".yellow(),
+            e
+        );
+        println!("{} Falling back to internal certificate", "ℹ".blue());
+    }
+
+    if cert_file.exists() && key_file.exists() {
+        // Use project certificate (works for all subdomains)
+        Ok(TlsMode::Certificate(cert_name))
+    } else {
+        Ok(TlsMode::Internal)
+    }
+}
+
+/// Generate mkcert certificate for a project (main domain + wildcard)
+fn generate_project_certificate(
+    base_domain: &str,
+    cert_file: &Path,
+    key_file: &Path,
+) -> Result<()> {
+    println!(
+        "{} Generating mkcert certificate for {} and *.{}...",
+        "ℹ".blue(),
+        base_domain.bright_white(),
+        base_domain
+    );
+
+    // Check if mkcert is available
+    let mkcert_path = Command::new("which")
+        .arg("mkcert")
+        .output()
+        .ok()
+        .and_then(|output| {
+            if output.status.success() {
+                String::from_utf8(output.stdout)
+                    .ok()
+                    .map(|s| s.trim().to_string())
+            } else {
+                None
+            }
+        });
+
+    let mkcert = mkcert_path.as_deref().unwrap_or("mkcert");
+
+    // Generate certificate with both main domain and wildcard
+    let wildcard = format!("*.{}", base_domain);
+    let output = Command::new(mkcert)
+        .arg(base_domain)
+        .arg(&wildcard)
+        .output()
+        .context("Failed to run mkcert. Make sure mkcert is installed.")?;
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("mkcert failed: {}", error);
+    }
+
+    // Find the generated certificate files
+    // mkcert generates files like: domain+1.pem and domain+1-key.pem
+    let current_dir = std::env::current_dir()?;
+    let base_domain_str = base_domain.to_string();
+
+    let mut cert_files: Vec<_> = current_dir
+        .read_dir()?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            name.contains(&base_domain_str) && name.ends_with(".pem") && !name.contains("-key")
+        })
+        .collect();
+
+    let mut key_files: Vec<_> = current_dir
+        .read_dir()?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            name.contains(&base_domain_str) && name.ends_with("-key.pem")
+        })
+        .collect();
+
+    cert_files.sort_by_key(|e| e.metadata().ok().and_then(|m| m.modified().ok()));
+    key_files.sort_by_key(|e| e.metadata().ok().and_then(|m| m.modified().ok()));
+
+    if cert_files.is_empty() || key_files.is_empty() {
+        anyhow::bail!("Failed to find generated certificate files");
+    }
+
+    let latest_cert = cert_files.last().unwrap();
+    let latest_key = key_files.last().unwrap();
+
+    // Copy certificate files to target location
+    fs::copy(latest_cert.path(), cert_file).context("Failed to copy certificate file")?;
+    fs::copy(latest_key.path(), key_file).context("Failed to copy key file")?;
+
+    // Clean up temporary files
+    let _ = fs::remove_file(latest_cert.path());
+    let _ = fs::remove_file(latest_key.path());
+
+    println!(
+        "{} Certificate generated: {} and {}",
+        "✓".green(),
+        cert_file.display(),
+        key_file.display()
+    );
+
+    Ok(())
+}
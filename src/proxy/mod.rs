@@ -0,0 +1,314 @@
+//! Reverse proxy backend abstraction
+//!
+//! This module defines the `ProxyBackend` trait implemented by every supported
+//! reverse proxy, together with the backend-independent route model:
+//! - Route collection from omd.toml and docker-compose.yml
+//! - Rendering and writing per-project route files
+//! - Backend selection from config.toml
+
+pub mod certs;
+
+use std::{fs, path::PathBuf};
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    caddy::backend::CaddyBackend,
+    config::{GlobalConfig, load_global_config},
+    docker::compose::ComposeInfo,
+    project::config::ProjectConfig,
+    traefik::backend::TraefikBackend,
+};
+
+/// Reverse proxy implementation selected in config.toml
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProxyKind {
+    #[default]
+    Caddy,
+    Traefik,
+}
+
+/// A single host -> upstream route
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Route {
+    /// Fully qualified domain (e.g. api.myproject.local)
+    pub domain: String,
+    /// Upstream target as container:port
+    pub target: String,
+}
+
+/// TLS handling for a rendered site
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TlsMode {
+    /// Serve plain HTTP only
+    Disabled,
+    /// Use the mkcert certificate stored as /certs/<name>.crt and /certs/<name>.key
+    Certificate(String),
+    /// Let the proxy issue its own local certificate
+    Internal,
+}
+
+/// Everything a backend needs to render the routes of one project
+#[derive(Debug, Clone)]
+pub struct SiteConfig {
+    /// Project name (also used for the route file name)
+    pub name: String,
+    /// Base domain of the project
+    pub domain: String,
+    /// Routes sorted by domain
+    pub routes: Vec<Route>,
+    /// TLS mode shared by all routes
+    pub tls: TlsMode,
+    /// Port the sites listen on when it is not 80/443 (Caddy high-port mode)
+    pub port: Option<u16>,
+}
+
+/// Common interface for reverse proxy implementations
+pub trait ProxyBackend {
+    /// Human readable backend name
+    fn name(&self) -> &'static str;
+
+    /// Name of the proxy container
+    fn container_name(&self) -> &str;
+
+    /// Directory that holds the generated per-project route files
+    fn routes_dir(&self) -> Result<PathBuf>;
+
+    /// File name of the route file for a project
+    fn routes_file_name(&self, project_name: &str) -> String;
+
+    /// Render the route file content for a project
+    fn render_routes(&self, site: &SiteConfig) -> String;
+
+    /// Start the proxy container
+    fn start(&self) -> Result<()>;
+
+    /// Stop the proxy container
+    fn stop(&self) -> Result<()>;
+
+    /// Restart the proxy container
+    fn restart(&self) -> Result<()> {
+        self.stop()?;
+        self.start()
+    }
+
+    /// Make the running proxy pick up changed route files
+    fn reload(&self) -> Result<()>;
+
+    /// Print the proxy status
+    fn status(&self) -> Result<()>;
+
+    /// Check if the proxy container is running
+    fn is_running(&self) -> bool;
+
+    /// Start the proxy if it is not running (called from project up)
+    fn ensure_running(&self) -> Result<()> {
+        if self.is_running() {
+            return Ok(());
+        }
+
+        println!();
+        println!("{} {} is not running", "ℹ".blue(), self.name());
+        println!("{} Starting {} automatically...", "ℹ".blue(), self.name());
+        println!();
+
+        self.start()
+    }
+}
+
+/// Create the backend selected in a global configuration
+pub fn backend_for(global_config: &GlobalConfig) -> Box<dyn ProxyBackend> {
+    match global_config.global.proxy_backend {
+        ProxyKind::Caddy => Box::new(CaddyBackend::new(global_config)),
+        ProxyKind::Traefik => Box::new(TraefikBackend),
+    }
+}
+
+/// Load config.toml and create the selected backend
+pub fn backend() -> Result<Box<dyn ProxyBackend>> {
+    let global_config = load_global_config()?;
+    Ok(backend_for(&global_config))
+}
+
+/// Collect the routes of a project
+///
+/// Custom `[caddy.routes]` take precedence; without them a route is generated
+/// for every service that exposes a container port, targeting its container in
+/// the Compose project `compose_name`.
+pub fn collect_routes(
+    config: &ProjectConfig,
+    compose_info: &ComposeInfo,
+    compose_name: &str,
+) -> Vec<Route> {
+    let mut routes: Vec<Route> = if !config.caddy.routes.is_empty() {
+        config
+            .caddy
+            .routes
+            .iter()
+            .map(|(subdomain, target)| Route {
+                domain: format!("{}.{}", subdomain, config.project.domain),
+                target: target.clone(),
+            })
+            .collect()
+    } else {
+        compose_info
+            .services
+            .iter()
+            // Skip services without container ports (like databases without HTTP interface)
+            .filter(|(_, service_info)| !service_info.container_ports.is_empty())
+            .map(|(service_name, service_info)| {
+                // Use the first container port as default
+                let port = service_info.container_ports[0];
+                let container_name = service_info.resolved_container_name(compose_name);
+
+                Route {
+                    domain: format!("{}.{}", service_name, config.project.domain),
+                    target: format!("{}:{}", container_name, port),
+                }
+            })
+            .collect()
+    };
+
+    routes.sort_by(|a, b| a.domain.cmp(&b.domain));
+    routes
+}
+
+/// Generate the proxy route file for a project
+pub fn generate_project_config(
+    backend: &dyn ProxyBackend,
+    config: &ProjectConfig,
+    compose_info: &ComposeInfo,
+    compose_name: &str,
+) -> Result<()> {
+    println!(
+        "{} Generating {} configuration...",
+        "ℹ".blue(),
+        backend.name()
+    );
+
+    let global_config = load_global_config()?;
+    let output_dir = backend.routes_dir()?;
+    fs::create_dir_all(&output_dir).context("Failed to create proxy routes directory")?;
+
+    if config.caddy.routes.is_empty() {
+        println!(
+            "{} Auto-generating routes from docker-compose.yml...",
+            "ℹ".blue()
+        );
+    } else {
+        println!("{} Adding custom routes...", "ℹ".blue());
+    }
+
+    let site = SiteConfig {
+        name: config.project.name.clone(),
+        domain: config.project.domain.clone(),
+        routes: collect_routes(config, compose_info, compose_name),
+        tls: certs::resolve_tls_mode(&config.project.domain, &global_config)?,
+        port: global_config.caddy_site_port(),
+    };
+
+    for route in &site.routes {
+        println!("  {} -> {}", route.domain.bright_white(), route.target);
+    }
+
+    let output_file = output_dir.join(backend.routes_file_name(&site.name));
+    fs::write(&output_file, backend.render_routes(&site))
+        .context(format!("Failed to write {} configuration", backend.name()))?;
+
+    println!("{} Generated {:?}", "✓".green(), output_file);
+
+    Ok(())
+}
+
+/// Remove the proxy route file of a project
+/// Returns true if a file was removed
+pub fn remove_project_config(backend: &dyn ProxyBackend, project_name: &str) -> Result<bool> {
+    let routes_file = backend
+        .routes_dir()?
+        .join(backend.routes_file_name(project_name));
+
+    if !routes_file.exists() {
+        return Ok(false);
+    }
+
+    fs::remove_file(&routes_file)
+        .context(format!("Failed to remove {} configuration", backend.name()))?;
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::docker::compose::ServiceInfo;
+
+    fn project_config(routes: &[(&str, &str)]) -> ProjectConfig {
+        let mut toml_str = String::from(
+            "[project]\nname = \"shop\"\ndomain = \"shop.local\"\n\n[network]\nname = \"shop-net\"\n\n[caddy.routes]\n",
+        );
+        for (subdomain, target) in routes {
+            toml_str.push_str(&format!("{} = \"{}\"\n", subdomain, target));
+        }
+        toml::from_str(&toml_str).unwrap()
+    }
+
+    fn compose_info() -> ComposeInfo {
+        let mut services = HashMap::new();
+        for (name, container_name, ports) in [
+            ("web", None, vec![3000]),
+            ("api", Some("shop-api"), vec![8080, 9090]),
+            ("postgres", None, vec![]),
+        ] {
+            services.insert(
+                name.to_string(),
+                ServiceInfo {
+                    name: name.to_string(),
+                    container_name: container_name.map(str::to_string),
+                    host_ports: vec![],
+                    container_ports: ports,
+                    networks: vec![],
+                    profiles: vec![],
+                },
+            );
+        }
+        ComposeInfo { services }
+    }
+
+    #[test]
+    fn test_collect_routes_from_compose() {
+        let routes = collect_routes(&project_config(&[]), &compose_info(), "shop");
+
+        assert_eq!(
+            routes,
+            vec![
+                Route {
+                    domain: "api.shop.local".to_string(),
+                    target: "shop-api:8080".to_string(),
+                },
+                Route {
+                    domain: "web.shop.local".to_string(),
+                    target: "shop-web-1:3000".to_string(),
+                },
+            ]
+        );
+
+        // Containers without a container_name are named after the Compose project
+        let routes = collect_routes(&project_config(&[]), &compose_info(), "myshop");
+        let targets: Vec<&str> = routes.iter().map(|r| r.target.as_str()).collect();
+        assert_eq!(targets, vec!["shop-api:8080", "myshop-web-1:3000"]);
+    }
+
+    #[test]
+    fn test_collect_routes_custom_routes_take_precedence() {
+        let config = project_config(&[("app", "web:3000"), ("admin", "admin:8000")]);
+        let routes = collect_routes(&config, &compose_info(), "shop");
+
+        let domains: Vec<&str> = routes.iter().map(|r| r.domain.as_str()).collect();
+        assert_eq!(domains, vec!["admin.shop.local", "app.shop.local"]);
+    }
+}
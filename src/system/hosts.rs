@@ -1,19 +1,26 @@
 //! /etc/hosts file management
 //!
-//! This module handles adding and removing domain entries from /etc/hosts
-//! for local development domains with backup support.
-
-use std::collections::{HashMap, HashSet};
-use std::fs;
-use std::io::{self, Write};
-use std::path::{Path, PathBuf};
-use std::process::Command;
+//! This module handles adding and removing domain entries from the hosts file
+//! (/etc/hosts unless configured otherwise) for local development domains
+//! with backup and restore support.
+
+use std::{
+    collections::{HashMap, HashSet},
+    env, fs,
+    io::{self, Write},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    path::{Path, PathBuf},
+};
 
 use anyhow::{Context, Result};
-use chrono::Local;
+use chrono::{Local, NaiveDateTime};
 use colored::Colorize;
 
-use crate::config::get_config_dir;
+use super::privileged;
+use crate::{
+    config::{get_config_dir, load_global_config},
+    diff::{print_diff, unified_diff},
+};
 
 /// Marker for oh-my-dockers managed entries in /etc/hosts
 const MARKER_PREFIX: &str = "# oh-my-dockers";
@@ -21,15 +28,178 @@ const MARKER_PREFIX: &str = "# oh-my-dockers";
 const SECTION_START_PREFIX: &str = "# === oh-my-dockers start ===";
 const SECTION_END_PREFIX: &str = "# === oh-my-dockers end ===";
 
+/// Addresses written for every domain unless a project configures its own
+pub const DEFAULT_ADDRESSES: [IpAddr; 2] = [
+    IpAddr::V4(Ipv4Addr::LOCALHOST),
+    IpAddr::V6(Ipv6Addr::LOCALHOST),
+];
+
+/// Environment variable overriding the hosts file path
+const HOSTS_FILE_ENV: &str = "OH_MY_DOCKERS_HOSTS_FILE";
+
+/// Default hosts file path
+const DEFAULT_HOSTS_FILE: &str = "/etc/hosts";
+
+/// Number of hosts backups to keep
+const KEEP_BACKUPS: usize = 10;
+
+/// A hosts file backup in the backups directory
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct HostsBackup {
+    /// Timestamp from the file name (YYYYMMDD_HHMMSS)
+    timestamp: String,
+    path: PathBuf,
+}
+
 /// Represents a project's hosts entries
 #[derive(Debug, Clone)]
 struct ProjectSection {
     project_name: String,
+    /// Unique domains in file order
     domains: Vec<String>,
+    /// All (address, domain) pairs of the section
+    entries: Vec<(IpAddr, String)>,
     start_line: usize,
     end_line: usize,
 }
 
+/// Desired hosts section of a registered project
+#[derive(Debug, Clone)]
+pub struct DesiredSection {
+    pub project_name: String,
+    /// Project domain, route domains and aliases
+    pub domains: Vec<String>,
+    /// Addresses for every domain (empty = `DEFAULT_ADDRESSES`)
+    pub addresses: Vec<IpAddr>,
+}
+
+/// How a project section changes during a sync
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SyncAction {
+    Add,
+    Update,
+    Remove,
+}
+
+/// Result of comparing the hosts file with the desired sections
+#[derive(Debug)]
+struct SyncPlan {
+    /// Hosts file content after applying all changes
+    content: String,
+    /// Changed projects in the order they were applied
+    changes: Vec<(String, SyncAction)>,
+    /// (project, domain, reason) for domains that cannot be written
+    skipped: Vec<(String, String, String)>,
+}
+
+/// Get the hosts file path
+/// Checks OH_MY_DOCKERS_HOSTS_FILE first, then global.hosts_file in
+/// config.toml, then defaults to /etc/hosts
+pub fn hosts_file_path() -> Result<PathBuf> {
+    if let Ok(custom_path) = env::var(HOSTS_FILE_ENV) {
+        return Ok(PathBuf::from(custom_path));
+    }
+
+    let configured = load_global_config()
+        .ok()
+        .and_then(|config| config.global.hosts_file);
+
+    Ok(PathBuf::from(
+        configured.unwrap_or_else(|| DEFAULT_HOSTS_FILE.to_string()),
+    ))
+}
+
+/// Get the directory holding hosts file backups
+fn backups_dir() -> Result<PathBuf> {
+    Ok(get_config_dir()?.join("backups").join("hosts"))
+}
+
+/// Find all hosts backups in a directory, newest first
+fn find_backups(backup_dir: &Path) -> Result<Vec<HostsBackup>> {
+    if !backup_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut backups: Vec<HostsBackup> = fs::read_dir(backup_dir)
+        .context("Failed to read backup directory")?
+        .filter_map(|e| e.ok())
+        .filter_map(|entry| {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            let timestamp = file_name.strip_prefix("hosts_")?.strip_suffix(".bak")?;
+            Some(HostsBackup {
+                timestamp: timestamp.to_string(),
+                path: entry.path(),
+            })
+        })
+        .collect();
+
+    // Timestamps sort chronologically as strings
+    backups.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+
+    Ok(backups)
+}
+
+/// Select a backup by timestamp (or unique prefix), or the newest one
+fn select_backup<'a>(
+    backups: &'a [HostsBackup],
+    timestamp: Option<&str>,
+) -> Result<&'a HostsBackup> {
+    let Some(timestamp) = timestamp else {
+        return backups.first().context("No hosts backups found");
+    };
+
+    let matches: Vec<&HostsBackup> = backups
+        .iter()
+        .filter(|b| b.timestamp.starts_with(timestamp))
+        .collect();
+
+    match matches.as_slice() {
+        [] => anyhow::bail!(
+            "No hosts backup found for '{}'. Run 'omd hosts backups' to list them.",
+            timestamp
+        ),
+        [backup] => Ok(backup),
+        _ => matches
+            .iter()
+            .find(|b| b.timestamp == timestamp)
+            .copied()
+            .context(format!(
+                "'{}' matches {} backups, please be more specific",
+                timestamp,
+                matches.len()
+            )),
+    }
+}
+
+/// Parse a hosts line into its address and host names
+/// Returns None for comments, blank lines and lines without a valid address
+fn parse_hosts_line(line: &str) -> Option<(IpAddr, Vec<String>)> {
+    let content = line.split('#').next().unwrap_or("");
+    let mut parts = content.split_whitespace();
+
+    // Drop an IPv6 zone index such as fe80::1%lo0
+    let address = parts.next()?.split('%').next()?.parse::<IpAddr>().ok()?;
+    let names: Vec<String> = parts.map(|name| name.to_ascii_lowercase()).collect();
+
+    if names.is_empty() {
+        None
+    } else {
+        Some((address, names))
+    }
+}
+
+/// Format the hosts lines for a set of domains
+fn format_entries(domains: &[String], addresses: &[IpAddr]) -> Vec<String> {
+    domains
+        .iter()
+        .flat_map(|domain| {
+            addresses
+                .iter()
+                .map(move |address| format!("{} {}", address, domain))
+        })
+        .collect()
+}
+
 /// Parse all oh-my-dockers managed sections from hosts file
 fn parse_hosts_file(content: &str) -> (Vec<String>, HashMap<String, ProjectSection>) {
     let lines: Vec<String> = content.lines().map(|s| s.to_string()).collect();
@@ -38,6 +208,7 @@ fn parse_hosts_file(content: &str) -> (Vec<String>, HashMap<String, ProjectSecti
     let mut current_project: Option<String> = None;
     let mut current_start: Option<usize> = None;
     let mut current_domains: Vec<String> = Vec::new();
+    let mut current_entries: Vec<(IpAddr, String)> = Vec::new();
 
     for (idx, line) in lines.iter().enumerate() {
         let trimmed = line.trim();
@@ -55,6 +226,7 @@ fn parse_hosts_file(content: &str) -> (Vec<String>, HashMap<String, ProjectSecti
                 current_project = Some(project_name);
                 current_start = Some(idx);
                 current_domains.clear();
+                current_entries.clear();
             }
         }
         // Check for section end
@@ -73,6 +245,7 @@ fn parse_hosts_file(content: &str) -> (Vec<String>, HashMap<String, ProjectSecti
                         ProjectSection {
                             project_name: project.clone(),
                             domains: current_domains.clone(),
+                            entries: current_entries.clone(),
                             start_line: start,
                             end_line: idx + 1, // Include the end marker
                         },
@@ -80,14 +253,19 @@ fn parse_hosts_file(content: &str) -> (Vec<String>, HashMap<String, ProjectSecti
                     current_project = None;
                     current_start = None;
                     current_domains.clear();
+                    current_entries.clear();
                 }
             }
         }
         // Collect domains within a section
-        else if current_project.is_some() && trimmed.starts_with("127.0.0.1") {
-            let parts: Vec<&str> = trimmed.split_whitespace().collect();
-            if parts.len() >= 2 {
-                current_domains.push(parts[1].to_string());
+        else if current_project.is_some()
+            && let Some((address, names)) = parse_hosts_line(trimmed)
+        {
+            for name in names {
+                if !current_domains.contains(&name) {
+                    current_domains.push(name.clone());
+                }
+                current_entries.push((address, name));
             }
         }
     }
@@ -99,6 +277,7 @@ fn parse_hosts_file(content: &str) -> (Vec<String>, HashMap<String, ProjectSecti
             ProjectSection {
                 project_name: project,
                 domains: current_domains,
+                entries: current_entries,
                 start_line: start,
                 end_line: lines.len(),
             },
@@ -109,65 +288,53 @@ fn parse_hosts_file(content: &str) -> (Vec<String>, HashMap<String, ProjectSecti
 }
 
 /// Create a backup of the hosts file
-fn backup_hosts_file() -> Result<PathBuf> {
-    let hosts_path = Path::new("/etc/hosts");
-    let config_dir = get_config_dir()?;
-    let backup_dir = config_dir.join("backups").join("hosts");
+/// With `prune`, only the most recent `KEEP_BACKUPS` backups are kept
+fn backup_hosts_file(hosts_path: &Path, prune: bool) -> Result<PathBuf> {
+    let content = fs::read_to_string(hosts_path)
+        .context(format!("Failed to read {}", hosts_path.display()))?;
+    save_backup(&backups_dir()?, &content, prune)
+}
 
+/// Write a backup named after the current time to the backups directory
+fn save_backup(backup_dir: &Path, content: &str, prune: bool) -> Result<PathBuf> {
     // Create backup directory if it doesn't exist
-    fs::create_dir_all(&backup_dir).context("Failed to create backup directory")?;
-
-    // Generate backup filename with timestamp
-    let timestamp = Local::now().format("%Y%m%d_%H%M%S");
-    let backup_filename = format!("hosts_{}.bak", timestamp);
-    let backup_path = backup_dir.join(&backup_filename);
-
-    // Read and copy hosts file
-    let content = fs::read_to_string(hosts_path).context("Failed to read /etc/hosts")?;
-    fs::write(&backup_path, &content).context("Failed to write backup file")?;
+    fs::create_dir_all(backup_dir).context("Failed to create backup directory")?;
+
+    // Generate backup filename with timestamp; backups of the same second
+    // get a counter so that none is overwritten
+    let timestamp = Local::now().format("%Y%m%d_%H%M%S").to_string();
+    let mut backup_path = backup_dir.join(format!("hosts_{}.bak", timestamp));
+    let mut counter = 2;
+    while backup_path.exists() {
+        backup_path = backup_dir.join(format!("hosts_{}_{}.bak", timestamp, counter));
+        counter += 1;
+    }
+    fs::write(&backup_path, content).context("Failed to write backup file")?;
 
-    // Keep only last 10 backups
-    cleanup_old_backups(&backup_dir, 10)?;
+    if prune {
+        cleanup_old_backups(backup_dir, KEEP_BACKUPS)?;
+    }
 
     Ok(backup_path)
 }
 
 /// Remove old backups, keeping only the most recent `keep_count`
 fn cleanup_old_backups(backup_dir: &Path, keep_count: usize) -> Result<()> {
-    let mut entries: Vec<_> = fs::read_dir(backup_dir)?
-        .filter_map(|e| e.ok())
-        .filter(|e| {
-            e.path()
-                .extension()
-                .map(|ext| ext == "bak")
-                .unwrap_or(false)
-        })
-        .collect();
-
-    // Sort by modification time (newest first)
-    entries.sort_by(|a, b| {
-        b.metadata()
-            .and_then(|m| m.modified())
-            .unwrap_or(std::time::SystemTime::UNIX_EPOCH)
-            .cmp(
-                &a.metadata()
-                    .and_then(|m| m.modified())
-                    .unwrap_or(std::time::SystemTime::UNIX_EPOCH),
-            )
-    });
-
-    // Remove old backups
-    for entry in entries.into_iter().skip(keep_count) {
-        let _ = fs::remove_file(entry.path());
+    for backup in find_backups(backup_dir)?.into_iter().skip(keep_count) {
+        let _ = fs::remove_file(&backup.path);
     }
 
     Ok(())
 }
 
 /// Find all existing domains in the hosts file (not managed by oh-my-dockers)
-fn find_unmanaged_domains(lines: &[String], sections: &HashMap<String, ProjectSection>) -> HashSet<String> {
-    let mut unmanaged: HashSet<String> = HashSet::new();
-    
+/// Returns domain -> addresses it is mapped to, for any address family
+fn find_unmanaged_domains(
+    lines: &[String],
+    sections: &HashMap<String, ProjectSection>,
+) -> HashMap<String, Vec<IpAddr>> {
+    let mut unmanaged: HashMap<String, Vec<IpAddr>> = HashMap::new();
+
     // Collect all managed line ranges
     let managed_ranges: Vec<(usize, usize)> = sections
         .values()
@@ -176,18 +343,19 @@ fn find_unmanaged_domains(lines: &[String], sections: &HashMap<String, ProjectSe
 
     for (idx, line) in lines.iter().enumerate() {
         let trimmed = line.trim();
-        
+
         // Skip if this line is in a managed section
-        let in_managed = managed_ranges.iter().any(|(start, end)| idx >= *start && idx < *end);
+        let in_managed = managed_ranges
+            .iter()
+            .any(|(start, end)| idx >= *start && idx < *end);
         if in_managed {
             continue;
         }
 
-        // Check if this is a 127.0.0.1 entry
-        if trimmed.starts_with("127.0.0.1") {
-            let parts: Vec<&str> = trimmed.split_whitespace().collect();
-            if parts.len() >= 2 {
-                unmanaged.insert(parts[1].to_string());
+        // Collect every host name of an address entry (including aliases)
+        if let Some((address, names)) = parse_hosts_line(trimmed) {
+            for name in names {
+                unmanaged.entry(name).or_default().push(address);
             }
         }
     }
@@ -201,6 +369,7 @@ fn build_hosts_content(
     sections: &HashMap<String, ProjectSection>,
     project_name: &str,
     new_domains: Option<&[String]>,
+    addresses: &[IpAddr],
 ) -> String {
     let mut result_lines: Vec<String> = Vec::new();
     let mut skip_until: Option<usize> = None;
@@ -221,13 +390,11 @@ fn build_hosts_content(
         // Check if this line starts a section we need to handle
         let mut handled = false;
         for section in &sorted_sections {
-            if idx == section.start_line {
-                if section.project_name == project_name {
-                    // Skip this section (we'll add the new one at the end)
-                    skip_until = Some(section.end_line);
-                    handled = true;
-                    break;
-                }
+            if idx == section.start_line && section.project_name == project_name {
+                // Skip this section (we'll add the new one at the end)
+                skip_until = Some(section.end_line);
+                handled = true;
+                break;
             }
         }
 
@@ -237,33 +404,280 @@ fn build_hosts_content(
     }
 
     // Remove trailing empty lines
-    while result_lines.last().map(|l| l.trim().is_empty()).unwrap_or(false) {
+    while result_lines
+        .last()
+        .map(|l| l.trim().is_empty())
+        .unwrap_or(false)
+    {
         result_lines.pop();
     }
 
     // Add new section if domains are provided
-    if let Some(domains) = new_domains {
-        if !domains.is_empty() {
-            result_lines.push(String::new()); // Empty line before section
-            result_lines.push(format!("{} {}", SECTION_START_PREFIX, project_name));
-            result_lines.push(format!("{} {}", MARKER_PREFIX, project_name));
-            for domain in domains {
-                result_lines.push(format!("127.0.0.1 {}", domain));
-            }
-            result_lines.push(format!("{} {}", SECTION_END_PREFIX, project_name));
-        }
+    if let Some(domains) = new_domains
+        && !domains.is_empty()
+    {
+        result_lines.push(String::new()); // Empty line before section
+        result_lines.push(format!("{} {}", SECTION_START_PREFIX, project_name));
+        result_lines.push(format!("{} {}", MARKER_PREFIX, project_name));
+        result_lines.extend(format_entries(domains, addresses));
+        result_lines.push(format!("{} {}", SECTION_END_PREFIX, project_name));
     }
 
     // Ensure file ends with newline
     result_lines.join("\n") + "\n"
 }
 
+/// Compute the hosts file content matching the desired sections
+///
+/// Sections of projects that are not desired are removed. A domain claimed by
+/// several projects stays with the project whose section already has it, then
+/// goes to the first project by name; unmanaged entries always win.
+fn plan_sync(content: &str, desired: &[DesiredSection]) -> SyncPlan {
+    let (lines, sections) = parse_hosts_file(content);
+    let unmanaged_domains = find_unmanaged_domains(&lines, &sections);
+
+    let mut desired: Vec<&DesiredSection> = desired.iter().collect();
+    desired.sort_by(|a, b| a.project_name.cmp(&b.project_name));
+
+    // Decide which project owns each domain
+    let mut owners: HashMap<String, String> = HashMap::new();
+    for section in &desired {
+        if let Some(existing) = sections.get(&section.project_name) {
+            for domain in &section.domains {
+                let domain = domain.to_ascii_lowercase();
+                if existing.domains.contains(&domain) {
+                    owners
+                        .entry(domain)
+                        .or_insert_with(|| section.project_name.clone());
+                }
+            }
+        }
+    }
+    for section in &desired {
+        for domain in &section.domains {
+            owners
+                .entry(domain.to_ascii_lowercase())
+                .or_insert_with(|| section.project_name.clone());
+        }
+    }
+
+    let mut plan = SyncPlan {
+        content: content.to_string(),
+        changes: Vec::new(),
+        skipped: Vec::new(),
+    };
+
+    // Remove sections of projects that are no longer registered
+    let mut stale: Vec<&String> = sections
+        .keys()
+        .filter(|name| !desired.iter().any(|d| &d.project_name == *name))
+        .collect();
+    stale.sort();
+
+    for project_name in stale {
+        let (current_lines, current_sections) = parse_hosts_file(&plan.content);
+        plan.content =
+            build_hosts_content(&current_lines, &current_sections, project_name, None, &[]);
+        plan.changes
+            .push((project_name.clone(), SyncAction::Remove));
+    }
+
+    for section in &desired {
+        let project_name = &section.project_name;
+        let addresses = if section.addresses.is_empty() {
+            &DEFAULT_ADDRESSES[..]
+        } else {
+            &section.addresses[..]
+        };
+
+        let mut domains: Vec<String> = Vec::new();
+        for domain in &section.domains {
+            let domain = domain.to_ascii_lowercase();
+            if domains.contains(&domain) {
+                continue;
+            }
+
+            if unmanaged_domains.contains_key(&domain) {
+                plan.skipped.push((
+                    project_name.clone(),
+                    domain,
+                    "exists as unmanaged entry".to_string(),
+                ));
+            } else if let Some(owner) = owners.get(&domain).filter(|o| *o != project_name) {
+                plan.skipped.push((
+                    project_name.clone(),
+                    domain,
+                    format!("already managed by project '{}'", owner),
+                ));
+            } else {
+                domains.push(domain);
+            }
+        }
+
+        let action = match sections.get(project_name) {
+            Some(existing) => {
+                let existing_set: HashSet<String> = existing
+                    .entries
+                    .iter()
+                    .map(|(address, domain)| format!("{} {}", address, domain))
+                    .collect();
+                let new_set: HashSet<String> =
+                    format_entries(&domains, addresses).into_iter().collect();
+
+                if existing_set == new_set {
+                    continue;
+                }
+                if domains.is_empty() {
+                    SyncAction::Remove
+                } else {
+                    SyncAction::Update
+                }
+            }
+            None if domains.is_empty() => continue,
+            None => SyncAction::Add,
+        };
+
+        let (current_lines, current_sections) = parse_hosts_file(&plan.content);
+        plan.content = build_hosts_content(
+            &current_lines,
+            &current_sections,
+            project_name,
+            Some(&domains),
+            addresses,
+        );
+        plan.changes.push((project_name.clone(), action));
+    }
+
+    plan
+}
+
+/// Compare the hosts file with the desired sections and print the differences
+/// Returns the plan and the hosts file path, or None if already in sync
+fn print_sync_plan(desired: &[DesiredSection]) -> Result<Option<(SyncPlan, PathBuf)>> {
+    let hosts_path = hosts_file_path()?;
+    let content = fs::read_to_string(&hosts_path)
+        .context(format!("Failed to read {}", hosts_path.display()))?;
+    let plan = plan_sync(&content, desired);
+
+    if !plan.skipped.is_empty() {
+        println!("{} Skipped domains:", "⚠".yellow());
+        for (project_name, domain, reason) in &plan.skipped {
+            println!(
+                "  {} ({}) - {}",
+                domain.bright_white(),
+                project_name,
+                reason
+            );
+        }
+        println!();
+    }
+
+    if plan.changes.is_empty() {
+        println!(
+            "{} {} is in sync with the registry",
+            "✓".green(),
+            hosts_path.display()
+        );
+        return Ok(None);
+    }
+
+    println!(
+        "{} {} differs from the registry:",
+        "ℹ".blue(),
+        hosts_path.display()
+    );
+    for (project_name, action) in &plan.changes {
+        let label = match action {
+            SyncAction::Add => "add".green(),
+            SyncAction::Update => "update".yellow(),
+            SyncAction::Remove => "remove".red(),
+        };
+        println!("  {:<8} {}", label, project_name.bright_white());
+    }
+    println!();
+
+    let path_label = hosts_path.display().to_string();
+    print_diff(&unified_diff(
+        &content,
+        &plan.content,
+        &path_label,
+        &format!("{} (registry)", path_label),
+    ));
+
+    Ok(Some((plan, hosts_path)))
+}
+
+/// Show the differences between the hosts file and the registry
+pub fn diff_sections(desired: &[DesiredSection]) -> Result<()> {
+    if print_sync_plan(desired)?.is_some() {
+        println!();
+        println!("Apply with: {}", "omd hosts sync".bright_white());
+    }
+
+    Ok(())
+}
+
+/// Bring the hosts file in line with the registry in a single write
+pub fn sync_sections(desired: &[DesiredSection]) -> Result<()> {
+    let Some((plan, hosts_path)) = print_sync_plan(desired)? else {
+        return Ok(());
+    };
+
+    println!();
+    print!("{} Apply these changes? [Y/n]: ", "?".bright_yellow());
+    io::stdout().flush()?;
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+
+    let trimmed = input.trim();
+    if trimmed.eq_ignore_ascii_case("n") || trimmed.eq_ignore_ascii_case("no") {
+        println!("{} Changes cancelled", "ℹ".blue());
+        return Ok(());
+    }
+
+    // Create backup before modifying
+    match backup_hosts_file(&hosts_path, true) {
+        Ok(backup_path) => {
+            println!("{} Backup created: {}", "✓".green(), backup_path.display());
+        }
+        Err(e) => {
+            println!("{} Warning: Could not create backup: {}", "⚠".yellow(), e);
+        }
+    }
+
+    write_hosts_file(&hosts_path, &plan.content)?;
+
+    println!(
+        "{} Synced {} ({} project(s) changed)",
+        "✓".green(),
+        hosts_path.display(),
+        plan.changes.len()
+    );
+
+    Ok(())
+}
+
 /// Add domains to /etc/hosts for a project
-pub fn add_project_domains(project_name: &str, domains: &[String]) -> Result<()> {
-    let hosts_path = Path::new("/etc/hosts");
+///
+/// Every domain is written once per address; an empty address list means
+/// `DEFAULT_ADDRESSES` (IPv4 and IPv6 loopback).
+pub fn add_project_domains(
+    project_name: &str,
+    domains: &[String],
+    addresses: &[IpAddr],
+) -> Result<()> {
+    let addresses = if addresses.is_empty() {
+        &DEFAULT_ADDRESSES[..]
+    } else {
+        addresses
+    };
+
+    let hosts_path = &hosts_file_path()?;
 
     // Read and parse current hosts file
-    let content = fs::read_to_string(hosts_path).context("Failed to read /etc/hosts")?;
+    let content = fs::read_to_string(hosts_path)
+        .context(format!("Failed to read {}", hosts_path.display()))?;
     let (lines, sections) = parse_hosts_file(&content);
 
     // Find unmanaged domains to check for conflicts
@@ -275,6 +689,8 @@ pub fn add_project_domains(project_name: &str, domains: &[String]) -> Result<()>
     let mut skipped_domains: Vec<(String, String)> = Vec::new(); // (domain, reason)
 
     for domain in domains {
+        let domain = &domain.to_ascii_lowercase();
+
         // Skip duplicates in input
         if seen.contains(domain) {
             continue;
@@ -299,10 +715,16 @@ pub fn add_project_domains(project_name: &str, domains: &[String]) -> Result<()>
         }
 
         // Check if exists as unmanaged entry
-        if unmanaged_domains.contains(domain) {
+        if let Some(existing_addresses) = unmanaged_domains.get(domain) {
+            let existing_addresses: Vec<String> =
+                existing_addresses.iter().map(|a| a.to_string()).collect();
             skipped_domains.push((
                 domain.clone(),
-                "exists as unmanaged entry in /etc/hosts".to_string(),
+                format!(
+                    "exists as unmanaged entry in {} ({})",
+                    hosts_path.display(),
+                    existing_addresses.join(", ")
+                ),
             ));
             continue;
         }
@@ -312,14 +734,21 @@ pub fn add_project_domains(project_name: &str, domains: &[String]) -> Result<()>
 
     // Check if the hosts file already has the exact same entries for this project
     if let Some(existing) = sections.get(project_name) {
-        let existing_set: HashSet<&String> = existing.domains.iter().collect();
-        let new_set: HashSet<&String> = domains_to_add.iter().collect();
+        let existing_set: HashSet<String> = existing
+            .entries
+            .iter()
+            .map(|(address, domain)| format!("{} {}", address, domain))
+            .collect();
+        let new_set: HashSet<String> = format_entries(&domains_to_add, addresses)
+            .into_iter()
+            .collect();
 
         if existing_set == new_set {
             // No changes needed
             println!(
-                "{} /etc/hosts already up to date for project {}",
+                "{} {} already up to date for project {}",
                 "✓".green(),
+                hosts_path.display(),
                 project_name.bright_white()
             );
             return Ok(());
@@ -327,8 +756,9 @@ pub fn add_project_domains(project_name: &str, domains: &[String]) -> Result<()>
     } else if domains_to_add.is_empty() {
         // No existing section and no domains to add
         println!(
-            "{} No domains to add to /etc/hosts for project {}",
+            "{} No domains to add to {} for project {}",
             "ℹ".blue(),
+            hosts_path.display(),
             project_name.bright_white()
         );
         return Ok(());
@@ -342,8 +772,8 @@ pub fn add_project_domains(project_name: &str, domains: &[String]) -> Result<()>
             "ℹ".blue(),
             project_name.bright_white()
         );
-        for domain in &existing.domains {
-            println!("  127.0.0.1 {}", domain);
+        for (address, domain) in &existing.entries {
+            println!("  {} {}", address, domain);
         }
     }
 
@@ -358,7 +788,11 @@ pub fn add_project_domains(project_name: &str, domains: &[String]) -> Result<()>
 
     // Show preview
     println!();
-    println!("{} Preview of changes to /etc/hosts:", "ℹ".blue());
+    println!(
+        "{} Preview of changes to {}:",
+        "ℹ".blue(),
+        hosts_path.display()
+    );
     println!();
     if sections.contains_key(project_name) {
         println!("{}", "Will replace existing section with:".bright_white());
@@ -368,8 +802,8 @@ pub fn add_project_domains(project_name: &str, domains: &[String]) -> Result<()>
     println!();
     println!("  {} {}", SECTION_START_PREFIX, project_name);
     println!("  {} {}", MARKER_PREFIX, project_name);
-    for domain in &domains_to_add {
-        println!("  127.0.0.1 {}", domain);
+    for entry in format_entries(&domains_to_add, addresses) {
+        println!("  {}", entry);
     }
     println!("  {} {}", SECTION_END_PREFIX, project_name);
     println!();
@@ -388,32 +822,31 @@ pub fn add_project_domains(project_name: &str, domains: &[String]) -> Result<()>
     }
 
     // Create backup before modifying
-    match backup_hosts_file() {
+    match backup_hosts_file(hosts_path, true) {
         Ok(backup_path) => {
-            println!(
-                "{} Backup created: {}",
-                "✓".green(),
-                backup_path.display()
-            );
+            println!("{} Backup created: {}", "✓".green(), backup_path.display());
         }
         Err(e) => {
-            println!(
-                "{} Warning: Could not create backup: {}",
-                "⚠".yellow(),
-                e
-            );
+            println!("{} Warning: Could not create backup: {}", "⚠".yellow(), e);
         }
     }
 
     // Build new content
-    let new_content = build_hosts_content(&lines, &sections, project_name, Some(&domains_to_add));
+    let new_content = build_hosts_content(
+        &lines,
+        &sections,
+        project_name,
+        Some(&domains_to_add),
+        addresses,
+    );
 
     // Write back to file
     write_hosts_file(hosts_path, &new_content)?;
 
     println!(
-        "{} Updated /etc/hosts with {} domain(s) for project {}",
+        "{} Updated {} with {} domain(s) for project {}",
         "✓".green(),
+        hosts_path.display(),
         domains_to_add.len(),
         project_name.bright_white()
     );
@@ -423,14 +856,15 @@ pub fn add_project_domains(project_name: &str, domains: &[String]) -> Result<()>
 
 /// Remove domains from /etc/hosts for a project
 pub fn remove_project_domains(project_name: &str) -> Result<()> {
-    let hosts_path = Path::new("/etc/hosts");
+    let hosts_path = &hosts_file_path()?;
 
     if !hosts_path.exists() {
         return Ok(());
     }
 
     // Read and parse current hosts file
-    let content = fs::read_to_string(hosts_path).context("Failed to read /etc/hosts")?;
+    let content = fs::read_to_string(hosts_path)
+        .context(format!("Failed to read {}", hosts_path.display()))?;
     let (lines, sections) = parse_hosts_file(&content);
 
     // Check if project has entries
@@ -448,14 +882,18 @@ pub fn remove_project_domains(project_name: &str) -> Result<()> {
 
     // Show preview
     println!();
-    println!("{} Preview of changes to /etc/hosts:", "ℹ".blue());
+    println!(
+        "{} Preview of changes to {}:",
+        "ℹ".blue(),
+        hosts_path.display()
+    );
     println!();
     println!("{}", "Will remove the following entries:".bright_white());
     println!();
     println!("  {} {}", SECTION_START_PREFIX, project_name);
     println!("  {} {}", MARKER_PREFIX, project_name);
-    for domain in &section.domains {
-        println!("  127.0.0.1 {}", domain);
+    for (address, domain) in &section.entries {
+        println!("  {} {}", address, domain);
     }
     println!("  {} {}", SECTION_END_PREFIX, project_name);
     println!();
@@ -474,34 +912,27 @@ pub fn remove_project_domains(project_name: &str) -> Result<()> {
     }
 
     // Create backup before modifying
-    match backup_hosts_file() {
+    match backup_hosts_file(hosts_path, true) {
         Ok(backup_path) => {
-            println!(
-                "{} Backup created: {}",
-                "✓".green(),
-                backup_path.display()
-            );
+            println!("{} Backup created: {}", "✓".green(), backup_path.display());
         }
         Err(e) => {
-            println!(
-                "{} Warning: Could not create backup: {}",
-                "⚠".yellow(),
-                e
-            );
+            println!("{} Warning: Could not create backup: {}", "⚠".yellow(), e);
         }
     }
 
     // Build new content without this project's section
-    let new_content = build_hosts_content(&lines, &sections, project_name, None);
+    let new_content = build_hosts_content(&lines, &sections, project_name, None, &[]);
 
     // Write back to file
     write_hosts_file(hosts_path, &new_content)?;
 
     println!(
-        "{} Removed {} domain(s) for project {} from /etc/hosts",
+        "{} Removed {} domain(s) for project {} from {}",
         "✓".green(),
         section.domains.len(),
-        project_name.bright_white()
+        project_name.bright_white(),
+        hosts_path.display()
     );
 
     Ok(())
@@ -509,14 +940,18 @@ pub fn remove_project_domains(project_name: &str) -> Result<()> {
 
 /// List all domains managed by oh-my-dockers
 pub fn list_managed_domains() -> Result<()> {
-    let hosts_path = Path::new("/etc/hosts");
+    let hosts_path = &hosts_file_path()?;
 
     if !hosts_path.exists() {
-        println!("{}", "No /etc/hosts file found".yellow());
+        println!(
+            "{}",
+            format!("No hosts file found at {}", hosts_path.display()).yellow()
+        );
         return Ok(());
     }
 
-    let content = fs::read_to_string(hosts_path).context("Failed to read /etc/hosts")?;
+    let content = fs::read_to_string(hosts_path)
+        .context(format!("Failed to read {}", hosts_path.display()))?;
     let (_, sections) = parse_hosts_file(&content);
 
     if sections.is_empty() {
@@ -546,13 +981,14 @@ pub fn list_managed_domains() -> Result<()> {
 
 /// Clean up all oh-my-dockers managed entries from /etc/hosts
 pub fn cleanup_all_domains() -> Result<()> {
-    let hosts_path = Path::new("/etc/hosts");
+    let hosts_path = &hosts_file_path()?;
 
     if !hosts_path.exists() {
         return Ok(());
     }
 
-    let content = fs::read_to_string(hosts_path).context("Failed to read /etc/hosts")?;
+    let content = fs::read_to_string(hosts_path)
+        .context(format!("Failed to read {}", hosts_path.display()))?;
     let (lines, sections) = parse_hosts_file(&content);
 
     if sections.is_empty() {
@@ -562,7 +998,11 @@ pub fn cleanup_all_domains() -> Result<()> {
 
     // Show preview
     println!();
-    println!("{} Preview of changes to /etc/hosts:", "ℹ".blue());
+    println!(
+        "{} Preview of changes to {}:",
+        "ℹ".blue(),
+        hosts_path.display()
+    );
     println!();
     println!(
         "{}",
@@ -603,20 +1043,12 @@ pub fn cleanup_all_domains() -> Result<()> {
     }
 
     // Create backup before modifying
-    match backup_hosts_file() {
+    match backup_hosts_file(hosts_path, true) {
         Ok(backup_path) => {
-            println!(
-                "{} Backup created: {}",
-                "✓".green(),
-                backup_path.display()
-            );
+            println!("{} Backup created: {}", "✓".green(), backup_path.display());
         }
         Err(e) => {
-            println!(
-                "{} Warning: Could not create backup: {}",
-                "⚠".yellow(),
-                e
-            );
+            println!("{} Warning: Could not create backup: {}", "⚠".yellow(), e);
         }
     }
 
@@ -669,48 +1101,131 @@ pub fn cleanup_all_domains() -> Result<()> {
     Ok(())
 }
 
-/// Write content to the hosts file, using sudo if necessary
-fn write_hosts_file(hosts_path: &Path, content: &str) -> Result<()> {
-    // Try to write directly first
-    if let Err(e) = fs::write(hosts_path, content) {
-        // If direct write fails, try using sudo tee
-        println!("{} Attempting to write with sudo privileges...", "ℹ".blue());
-
-        // Use sudo tee to write the file
-        let mut child = Command::new("sudo")
-            .arg("tee")
-            .arg(hosts_path)
-            .stdin(std::process::Stdio::piped())
-            .stdout(std::process::Stdio::null())
-            .stderr(std::process::Stdio::piped())
-            .spawn()
-            .context("Failed to execute sudo tee. Make sure sudo is available.")?;
-
-        if let Some(mut stdin) = child.stdin.take() {
-            stdin
-                .write_all(content.as_bytes())
-                .context("Failed to write to sudo tee stdin")?;
-            // Close stdin to signal EOF
-            drop(stdin);
-        }
-
-        let output = child
-            .wait_with_output()
-            .context("Failed to wait for sudo tee")?;
-
-        if !output.status.success() {
-            let error_msg = String::from_utf8_lossy(&output.stderr);
-            anyhow::bail!(
-                "Failed to write /etc/hosts with sudo: {}. Error: {}. Please run with sudo or manually add entries.",
-                e,
-                error_msg
-            );
+/// List available hosts file backups
+pub fn list_backups() -> Result<()> {
+    let backup_dir = backups_dir()?;
+    let backups = find_backups(&backup_dir)?;
+
+    if backups.is_empty() {
+        println!("{}", "No hosts backups found".yellow());
+        return Ok(());
+    }
+
+    println!("{}", "Hosts file backups (newest first):".blue());
+    println!();
+    println!(
+        "  {:<18} {:<22} {:<10} OMD PROJECTS",
+        "TIMESTAMP", "CREATED", "SIZE"
+    );
+    println!("  {}", "-".repeat(70));
+
+    for backup in &backups {
+        // Backups of the same second carry a counter after the time
+        let time = backup.timestamp.get(..15).unwrap_or(&backup.timestamp);
+        let created = NaiveDateTime::parse_from_str(time, "%Y%m%d_%H%M%S")
+            .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_else(|_| "-".to_string());
+        let content = fs::read_to_string(&backup.path).unwrap_or_default();
+        let (_, sections) = parse_hosts_file(&content);
+
+        println!(
+            "  {:<18} {:<22} {:<10} {}",
+            backup.timestamp.bright_white(),
+            created,
+            format!("{} B", content.len()),
+            sections.len()
+        );
+    }
+
+    println!();
+    println!("Backups are stored in {}", backup_dir.display());
+    println!(
+        "Restore one with: {}",
+        "omd hosts restore [TIMESTAMP]".bright_white()
+    );
+
+    Ok(())
+}
+
+/// Restore the hosts file from a backup (the newest one by default)
+pub fn restore_backup(timestamp: Option<&str>) -> Result<()> {
+    let hosts_path = &hosts_file_path()?;
+    let backups = find_backups(&backups_dir()?)?;
+    let backup = select_backup(&backups, timestamp)?;
+
+    let backup_content = fs::read_to_string(&backup.path).context("Failed to read backup file")?;
+    let current_content = fs::read_to_string(hosts_path).unwrap_or_default();
+
+    let diff = unified_diff(
+        &current_content,
+        &backup_content,
+        &hosts_path.display().to_string(),
+        &backup.path.display().to_string(),
+    );
+
+    if diff.is_empty() {
+        println!(
+            "{} {} already matches backup {}",
+            "✓".green(),
+            hosts_path.display(),
+            backup.timestamp.bright_white()
+        );
+        return Ok(());
+    }
+
+    println!();
+    println!(
+        "{} Restoring {} from backup {}:",
+        "ℹ".blue(),
+        hosts_path.display(),
+        backup.timestamp.bright_white()
+    );
+    println!();
+    print_diff(&diff);
+    println!();
+
+    print!("{} Restore this backup? [y/N]: ", "?".bright_yellow());
+    io::stdout().flush()?;
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+
+    let trimmed = input.trim();
+    if !trimmed.eq_ignore_ascii_case("y") && !trimmed.eq_ignore_ascii_case("yes") {
+        println!("{} Changes cancelled", "ℹ".blue());
+        return Ok(());
+    }
+
+    // Back up the current file so the restore itself can be undone
+    // Nothing is pruned, so the backup being restored is kept as well
+    if hosts_path.exists() {
+        match backup_hosts_file(hosts_path, false) {
+            Ok(backup_path) => {
+                println!("{} Backup created: {}", "✓".green(), backup_path.display());
+            }
+            Err(e) => {
+                println!("{} Warning: Could not create backup: {}", "⚠".yellow(), e);
+            }
         }
     }
 
+    write_hosts_file(hosts_path, &backup_content)?;
+
+    println!(
+        "{} Restored {} from backup {}",
+        "✓".green(),
+        hosts_path.display(),
+        backup.timestamp
+    );
+
     Ok(())
 }
 
+/// Write content to the hosts file, using sudo if necessary
+fn write_hosts_file(hosts_path: &Path, content: &str) -> Result<()> {
+    privileged::write_file(hosts_path, content)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -740,7 +1255,11 @@ mod tests {
         let section_a = sections.get("project-a").unwrap();
         assert_eq!(section_a.domains.len(), 2);
         assert!(section_a.domains.contains(&"project-a.local".to_string()));
-        assert!(section_a.domains.contains(&"api.project-a.local".to_string()));
+        assert!(
+            section_a
+                .domains
+                .contains(&"api.project-a.local".to_string())
+        );
 
         let section_b = sections.get("project-b").unwrap();
         assert_eq!(section_b.domains.len(), 1);
@@ -753,11 +1272,18 @@ mod tests {
         let (lines, sections) = parse_hosts_file(content);
 
         let new_domains = vec!["test.local".to_string(), "api.test.local".to_string()];
-        let result = build_hosts_content(&lines, &sections, "test-project", Some(&new_domains));
+        let result = build_hosts_content(
+            &lines,
+            &sections,
+            "test-project",
+            Some(&new_domains),
+            &DEFAULT_ADDRESSES,
+        );
 
         assert!(result.contains("# === oh-my-dockers start === test-project"));
-        assert!(result.contains("127.0.0.1 test.local"));
+        assert!(result.contains("127.0.0.1 test.local\n::1 test.local\n"));
         assert!(result.contains("127.0.0.1 api.test.local"));
+        assert!(result.contains("::1 api.test.local"));
         assert!(result.contains("# === oh-my-dockers end === test-project"));
     }
 
@@ -772,10 +1298,208 @@ mod tests {
 "#;
         let (lines, sections) = parse_hosts_file(content);
 
-        let result = build_hosts_content(&lines, &sections, "test-project", None);
+        let result = build_hosts_content(&lines, &sections, "test-project", None, &[]);
 
         assert!(!result.contains("test-project"));
         assert!(!result.contains("test.local"));
         assert!(result.contains("127.0.0.1 localhost"));
     }
+
+    #[test]
+    fn test_parse_hosts_file_any_address_family() {
+        let content = r#"127.0.0.1 localhost
+
+# === oh-my-dockers start === shop
+# oh-my-dockers shop
+127.0.0.1 shop.local
+::1 shop.local
+192.168.1.20 api.shop.local
+# === oh-my-dockers end === shop
+"#;
+
+        let (_, sections) = parse_hosts_file(content);
+        let section = sections.get("shop").unwrap();
+
+        assert_eq!(section.domains, vec!["shop.local", "api.shop.local"]);
+        assert_eq!(section.entries.len(), 3);
+        assert_eq!(
+            section.entries[1],
+            ("::1".parse().unwrap(), "shop.local".to_string())
+        );
+    }
+
+    #[test]
+    fn test_find_unmanaged_domains_any_address() {
+        let content = r#"127.0.0.1 localhost
+::1 ip6-localhost ip6-loopback
+0.0.0.0 ads.example.com # blocked
+192.168.1.5 nas.lan nas
+fe80::1%lo0 link.local
+# 127.0.0.1 commented.local
+
+# === oh-my-dockers start === shop
+127.0.0.1 shop.local
+# === oh-my-dockers end === shop
+"#;
+
+        let (lines, sections) = parse_hosts_file(content);
+        let unmanaged = find_unmanaged_domains(&lines, &sections);
+
+        assert!(unmanaged.contains_key("localhost"));
+        assert!(unmanaged.contains_key("ip6-loopback"));
+        assert_eq!(
+            unmanaged.get("ads.example.com"),
+            Some(&vec!["0.0.0.0".parse::<IpAddr>().unwrap()])
+        );
+        assert!(unmanaged.contains_key("nas"));
+        assert!(unmanaged.contains_key("link.local"));
+        assert!(!unmanaged.contains_key("commented.local"));
+        assert!(!unmanaged.contains_key("shop.local"));
+    }
+
+    #[test]
+    fn test_build_hosts_content_custom_address() {
+        let (lines, sections) = parse_hosts_file("127.0.0.1 localhost\n");
+
+        let address: IpAddr = "192.168.1.20".parse().unwrap();
+        let result = build_hosts_content(
+            &lines,
+            &sections,
+            "shop",
+            Some(&["shop.local".to_string()]),
+            &[address],
+        );
+
+        assert!(result.contains("192.168.1.20 shop.local\n"));
+        assert!(!result.contains("127.0.0.1 shop.local"));
+    }
+
+    #[test]
+    fn test_find_and_select_backups() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in [
+            "hosts_20261017_093000.bak",
+            "hosts_20261018_101500.bak",
+            "hosts_20261018_120000.bak",
+            "unrelated.txt",
+        ] {
+            fs::write(dir.path().join(name), "127.0.0.1 localhost\n").unwrap();
+        }
+
+        let backups = find_backups(dir.path()).unwrap();
+        let timestamps: Vec<&str> = backups.iter().map(|b| b.timestamp.as_str()).collect();
+        assert_eq!(
+            timestamps,
+            vec!["20261018_120000", "20261018_101500", "20261017_093000"]
+        );
+
+        assert_eq!(
+            select_backup(&backups, None).unwrap().timestamp,
+            "20261018_120000"
+        );
+        assert_eq!(
+            select_backup(&backups, Some("20261017")).unwrap().timestamp,
+            "20261017_093000"
+        );
+        assert!(select_backup(&backups, Some("20261018")).is_err());
+        assert!(select_backup(&backups, Some("2025")).is_err());
+    }
+
+    #[test]
+    fn test_save_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let oldest = dir.path().join("hosts_20200101_000000.bak");
+        fs::write(&oldest, "# oldest\n").unwrap();
+        for i in 1..KEEP_BACKUPS {
+            fs::write(
+                dir.path().join(format!("hosts_20200101_0000{:02}.bak", i)),
+                "",
+            )
+            .unwrap();
+        }
+
+        // Backups of the same second do not overwrite each other
+        let first = save_backup(dir.path(), "# first\n", false).unwrap();
+        let second = save_backup(dir.path(), "# second\n", false).unwrap();
+        assert_ne!(first, second);
+        assert_eq!(fs::read_to_string(&first).unwrap(), "# first\n");
+        assert_eq!(find_backups(dir.path()).unwrap()[0].path, second);
+
+        // Without pruning the oldest backup stays
+        assert!(oldest.exists());
+        assert_eq!(find_backups(dir.path()).unwrap().len(), KEEP_BACKUPS + 2);
+
+        save_backup(dir.path(), "# third\n", true).unwrap();
+        let backups = find_backups(dir.path()).unwrap();
+        assert_eq!(backups.len(), KEEP_BACKUPS);
+        assert!(!oldest.exists());
+        assert!(backups.iter().any(|b| b.path == first));
+    }
+
+    fn desired(project_name: &str, domains: &[&str]) -> DesiredSection {
+        DesiredSection {
+            project_name: project_name.to_string(),
+            domains: domains.iter().map(|d| d.to_string()).collect(),
+            addresses: vec![IpAddr::V4(Ipv4Addr::LOCALHOST)],
+        }
+    }
+
+    #[test]
+    fn test_plan_sync_adds_updates_and_removes_sections() {
+        let content = r#"127.0.0.1 localhost
+127.0.0.1 manual.local
+
+# === oh-my-dockers start === shop
+# oh-my-dockers shop
+127.0.0.1 shop.local
+# === oh-my-dockers end === shop
+
+# === oh-my-dockers start === old
+# oh-my-dockers old
+127.0.0.1 old.local
+# === oh-my-dockers end === old
+"#;
+
+        let plan = plan_sync(
+            content,
+            &[
+                desired("shop", &["shop.local", "api.shop.local"]),
+                desired("blog", &["blog.local", "manual.local", "shop.local"]),
+            ],
+        );
+
+        assert_eq!(
+            plan.changes,
+            vec![
+                ("old".to_string(), SyncAction::Remove),
+                ("blog".to_string(), SyncAction::Add),
+                ("shop".to_string(), SyncAction::Update),
+            ]
+        );
+        assert_eq!(plan.skipped.len(), 2);
+
+        let (_, sections) = parse_hosts_file(&plan.content);
+        assert_eq!(sections.len(), 2);
+        assert_eq!(sections["blog"].domains, vec!["blog.local"]);
+        assert_eq!(
+            sections["shop"].domains,
+            vec!["shop.local", "api.shop.local"]
+        );
+        assert!(plan.content.contains("127.0.0.1 manual.local\n"));
+    }
+
+    #[test]
+    fn test_plan_sync_in_sync() {
+        let content = r#"127.0.0.1 localhost
+
+# === oh-my-dockers start === shop
+# oh-my-dockers shop
+127.0.0.1 shop.local
+# === oh-my-dockers end === shop
+"#;
+
+        let plan = plan_sync(content, &[desired("shop", &["Shop.local"])]);
+        assert!(plan.changes.is_empty());
+        assert_eq!(plan.content, content);
+    }
 }
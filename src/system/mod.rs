@@ -2,5 +2,7 @@
 //!
 //! This module contains functionality for interacting with the operating system:
 //! - /etc/hosts management
+//! - Privileged writes to system files
 
 pub mod hosts;
+pub mod privileged;
@@ -0,0 +1,97 @@
+//! Privileged file operations
+//!
+//! System files such as /etc/hosts or resolver drop-ins are usually owned
+//! by root. These helpers try a direct write first and fall back to sudo.
+
+use std::{
+    fs,
+    io::Write,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+
+/// Write content to a file, using sudo if necessary
+pub fn write_file(path: &Path, content: &str) -> Result<()> {
+    if let Some(parent) = path.parent()
+        && !parent.exists()
+        && fs::create_dir_all(parent).is_err()
+    {
+        let status = Command::new("sudo")
+            .arg("mkdir")
+            .arg("-p")
+            .arg(parent)
+            .status()
+            .context("Failed to execute sudo mkdir. Make sure sudo is available.")?;
+
+        if !status.success() {
+            anyhow::bail!("Failed to create directory {}", parent.display());
+        }
+    }
+
+    // Try to write directly first
+    if let Err(e) = fs::write(path, content) {
+        // If direct write fails, try using sudo tee
+        println!("{} Attempting to write with sudo privileges...", "ℹ".blue());
+
+        // Use sudo tee to write the file
+        let mut child = Command::new("sudo")
+            .arg("tee")
+            .arg(path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context("Failed to execute sudo tee. Make sure sudo is available.")?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin
+                .write_all(content.as_bytes())
+                .context("Failed to write to sudo tee stdin")?;
+            // Close stdin to signal EOF
+            drop(stdin);
+        }
+
+        let output = child
+            .wait_with_output()
+            .context("Failed to wait for sudo tee")?;
+
+        if !output.status.success() {
+            let error_msg = String::from_utf8_lossy(&output.stderr);
+            anyhow::bail!(
+                "Failed to write {} with sudo: {}. Error: {}. Please run with sudo or edit the file manually.",
+                path.display(),
+                e,
+                error_msg
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Remove a file, using sudo if necessary
+pub fn remove_file(path: &Path) -> Result<()> {
+    if fs::remove_file(path).is_ok() {
+        return Ok(());
+    }
+
+    println!(
+        "{} Attempting to remove with sudo privileges...",
+        "ℹ".blue()
+    );
+    let status = Command::new("sudo")
+        .arg("rm")
+        .arg("-f")
+        .arg(path)
+        .status()
+        .context("Failed to execute sudo rm. Make sure sudo is available.")?;
+
+    if !status.success() {
+        anyhow::bail!("Failed to remove {}", path.display());
+    }
+
+    Ok(())
+}
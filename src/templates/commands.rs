@@ -0,0 +1,132 @@
+//! Template commands (list, show)
+
+use std::{env, path::Path};
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+
+use super::{PROJECT_TEMPLATES_DIR, StringOrList, TemplateCatalog, user_templates_dir};
+use crate::project::{
+    compose_generator::{SelectedService, generate_compose_content},
+    config::find_project_config,
+};
+
+/// Load the catalog for the project containing the current directory
+fn load_catalog() -> Result<TemplateCatalog> {
+    let current_dir = env::current_dir().context("Failed to get current directory")?;
+    let project_dir = find_project_config(&current_dir)
+        .and_then(|path| path.parent().map(Path::to_path_buf))
+        .unwrap_or(current_dir);
+    TemplateCatalog::load(&project_dir)
+}
+
+/// List all available templates
+pub fn list() -> Result<()> {
+    let catalog = load_catalog()?;
+
+    println!("{}", "Available service templates:".blue());
+    println!();
+    println!(
+        "  {:<14} {:<18} {:<54} {:<12} SOURCE",
+        "NAME", "DISPLAY NAME", "IMAGE", "PORTS"
+    );
+    println!("  {}", "-".repeat(110));
+
+    for template in catalog.templates() {
+        println!(
+            "  {:<14} {:<18} {:<54} {:<12} {}",
+            template.name.bright_white(),
+            template.display_name,
+            template.image,
+            template.ports_label(),
+            template.source
+        );
+    }
+
+    println!();
+    println!(
+        "Add your own templates to {} or {}/<name>.toml",
+        user_templates_dir()?.join("<name>.toml").display(),
+        PROJECT_TEMPLATES_DIR
+    );
+
+    Ok(())
+}
+
+/// Show a single template and the compose service it generates
+pub fn show(name: &str) -> Result<()> {
+    let catalog = load_catalog()?;
+    let template = catalog.get(name).with_context(|| {
+        format!(
+            "Template '{}' not found. Run 'omd templates list' to see available templates.",
+            name
+        )
+    })?;
+
+    println!(
+        "{} ({})",
+        template.display_name.bright_white(),
+        template.name
+    );
+    if let Some(description) = &template.description {
+        println!("  {}", description);
+    }
+    println!();
+    println!("  Source: {}", template.source);
+    println!("  Image:  {}", template.image);
+
+    if !template.ports.is_empty() {
+        let ports: Vec<String> = template
+            .ports
+            .iter()
+            .map(|p| format!("{}:{}", p.host, p.container))
+            .collect();
+        println!("  Ports:  {}", ports.join(", "));
+    }
+
+    for (subdomain, port) in template.http_routes() {
+        println!("  Route:  {}.<domain> -> port {}", subdomain, port);
+    }
+
+    if let Some(command) = &template.command {
+        let command = match command {
+            StringOrList::String(command) => command.clone(),
+            StringOrList::List(args) => args.join(" "),
+        };
+        println!("  Command: {}", command);
+    }
+
+    if let Some(healthcheck) = &template.healthcheck {
+        let test = match &healthcheck.test {
+            StringOrList::String(command) => command.clone(),
+            StringOrList::List(args) => args.join(" "),
+        };
+        println!("  Healthcheck: {}", test);
+    }
+
+    if !template.secrets.is_empty() {
+        println!(
+            "  Secrets: {} (generated into .env)",
+            template.secrets.join(", ")
+        );
+    }
+
+    // Preview the generated service block with the default ports
+    let preview = generate_compose_content(
+        "<project>",
+        "<network>",
+        &[SelectedService::new(
+            template.clone(),
+            template.ports.iter().map(|p| p.host).collect(),
+        )],
+    )?;
+
+    println!();
+    println!("{}", "Generated service:".blue());
+    println!();
+    for line in preview.lines() {
+        println!("  {}", line);
+    }
+
+    Ok(())
+}
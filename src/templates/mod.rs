@@ -0,0 +1,648 @@
+//! Service templates
+//!
+//! This module manages the service templates offered when generating
+//! docker-compose files:
+//! - Built-in templates shipped with omd
+//! - User templates from ~/.oh-my-dockers/templates/*.toml
+//! - Project templates from .omd/templates/*.toml in the project directory
+//!
+//! Templates are merged in that order; a template replaces an earlier one
+//! with the same name.
+
+pub mod commands;
+
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use indexmap::IndexMap;
+use serde::{Deserialize, Deserializer};
+
+use crate::config::get_config_dir;
+
+/// Project-local template directory (relative to the project directory)
+pub const PROJECT_TEMPLATES_DIR: &str = ".omd/templates";
+
+/// Built-in templates as (name, TOML source), in catalog order
+const BUILTIN_TEMPLATES: &[(&str, &str)] = &[
+    // Databases
+    ("postgres", include_str!("builtin/postgres.toml")),
+    ("mysql", include_str!("builtin/mysql.toml")),
+    ("mariadb", include_str!("builtin/mariadb.toml")),
+    ("mongodb", include_str!("builtin/mongodb.toml")),
+    ("redis", include_str!("builtin/redis.toml")),
+    // Messaging
+    ("kafka", include_str!("builtin/kafka.toml")),
+    ("rabbitmq", include_str!("builtin/rabbitmq.toml")),
+    // Search
+    ("elasticsearch", include_str!("builtin/elasticsearch.toml")),
+    ("opensearch", include_str!("builtin/opensearch.toml")),
+    // Storage and cloud stand-ins
+    ("minio", include_str!("builtin/minio.toml")),
+    ("localstack", include_str!("builtin/localstack.toml")),
+    // Development tools
+    ("mailpit", include_str!("builtin/mailpit.toml")),
+    ("adminer", include_str!("builtin/adminer.toml")),
+    ("pgadmin", include_str!("builtin/pgadmin.toml")),
+];
+
+/// Where a template was loaded from
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum TemplateSource {
+    #[default]
+    BuiltIn,
+    /// ~/.oh-my-dockers/templates
+    User(PathBuf),
+    /// .omd/templates in the project directory
+    Project(PathBuf),
+}
+
+impl fmt::Display for TemplateSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TemplateSource::BuiltIn => write!(f, "built-in"),
+            TemplateSource::User(path) => write!(f, "user ({})", path.display()),
+            TemplateSource::Project(path) => write!(f, "project ({})", path.display()),
+        }
+    }
+}
+
+/// A string or a list of strings (compose accepts both for command and test)
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+pub enum StringOrList {
+    String(String),
+    List(Vec<String>),
+}
+
+/// Port published by a template
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(from = "PortEntry")]
+pub struct TemplatePort {
+    /// Preferred host port (the next free port is used on conflicts)
+    pub host: u16,
+    /// Port inside the container
+    pub container: u16,
+    /// Serve this port through the reverse proxy
+    pub http: bool,
+    /// Subdomain of the HTTP route (defaults to the service name)
+    pub subdomain: Option<String>,
+}
+
+/// Port as written in a template file:
+/// `5432` or `{ host = 5050, container = 80, http = true, subdomain = "pgadmin" }`
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PortEntry {
+    Same(u16),
+    Mapping {
+        host: Option<u16>,
+        container: u16,
+        #[serde(default)]
+        http: bool,
+        subdomain: Option<String>,
+    },
+}
+
+impl From<PortEntry> for TemplatePort {
+    fn from(entry: PortEntry) -> Self {
+        match entry {
+            PortEntry::Same(port) => TemplatePort {
+                host: port,
+                container: port,
+                http: false,
+                subdomain: None,
+            },
+            PortEntry::Mapping {
+                host,
+                container,
+                http,
+                subdomain,
+            } => TemplatePort {
+                host: host.unwrap_or(container),
+                container,
+                http,
+                subdomain,
+            },
+        }
+    }
+}
+
+/// Container healthcheck
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Healthcheck {
+    /// Check command; a plain string runs through the shell (CMD-SHELL)
+    pub test: StringOrList,
+    pub interval: Option<String>,
+    pub timeout: Option<String>,
+    pub retries: Option<u32>,
+    pub start_period: Option<String>,
+}
+
+/// Start of a placeholder in environment values that is replaced by the host
+/// port published for a container port: `{host_port:9092}`
+const HOST_PORT_PLACEHOLDER: &str = "{host_port:";
+
+/// Service template definition
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ServiceTemplate {
+    /// Service name in docker-compose.yml (defaults to the file name)
+    #[serde(default)]
+    pub name: String,
+    /// Name shown in menus (defaults to the service name)
+    #[serde(default)]
+    pub display_name: String,
+    /// Short description shown by `omd templates show`
+    #[serde(default)]
+    pub description: Option<String>,
+    pub image: String,
+    #[serde(default)]
+    pub ports: Vec<TemplatePort>,
+    #[serde(default, deserialize_with = "deserialize_environment")]
+    pub environment: IndexMap<String, String>,
+    /// Environment variables generated per project and stored in `.env`
+    #[serde(default)]
+    pub secrets: Vec<String>,
+    #[serde(default)]
+    pub volumes: Vec<String>,
+    #[serde(default)]
+    pub command: Option<StringOrList>,
+    #[serde(default)]
+    pub healthcheck: Option<Healthcheck>,
+    #[serde(default)]
+    pub labels: IndexMap<String, String>,
+    /// Services to start first when they are generated together with this one
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    #[serde(skip)]
+    pub source: TemplateSource,
+}
+
+impl ServiceTemplate {
+    /// Parse a template from TOML; `name` is used when the file sets none
+    pub fn parse(content: &str, name: &str, source: TemplateSource) -> Result<Self> {
+        let mut template: ServiceTemplate =
+            toml::from_str(content).context("Failed to parse template")?;
+
+        if template.name.is_empty() {
+            template.name = name.to_string();
+        }
+        if template.display_name.is_empty() {
+            template.display_name = template.name.clone();
+        }
+        template.source = source;
+
+        template.validate()?;
+        Ok(template)
+    }
+
+    /// Check that the template can be written to a compose file
+    fn validate(&self) -> Result<()> {
+        let valid_name = !self.name.is_empty()
+            && self
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+        if !valid_name {
+            anyhow::bail!(
+                "Invalid service name '{}': use letters, digits, '-', '_' and '.'",
+                self.name
+            );
+        }
+
+        if self.image.trim().is_empty() {
+            anyhow::bail!("Template '{}' has no image", self.name);
+        }
+
+        for secret in &self.secrets {
+            let valid_key = !secret.is_empty()
+                && !secret.starts_with(|c: char| c.is_ascii_digit())
+                && secret
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_');
+            if !valid_key {
+                anyhow::bail!(
+                    "Invalid secret '{}' in template '{}': use letters, digits and '_'",
+                    secret,
+                    self.name
+                );
+            }
+            if self.environment.contains_key(secret) {
+                anyhow::bail!(
+                    "Secret '{}' in template '{}' must not also be set in [environment]",
+                    secret,
+                    self.name
+                );
+            }
+        }
+
+        for (key, value) in &self.environment {
+            expand_host_ports(value, |port| {
+                self.ports
+                    .iter()
+                    .any(|p| p.container == port)
+                    .then_some(port)
+            })
+            .with_context(|| format!("Invalid {} in template '{}'", key, self.name))?;
+        }
+
+        for (subdomain, _) in self.http_routes() {
+            let valid_label = !subdomain.starts_with('-')
+                && !subdomain.ends_with('-')
+                && subdomain
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+            if subdomain.is_empty() || !valid_label {
+                anyhow::bail!(
+                    "Invalid route subdomain '{}' in template '{}': use lowercase letters, digits and '-'",
+                    subdomain,
+                    self.name
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Environment with `{host_port:<container port>}` replaced by the host
+    /// port chosen for that container port (`host_ports` follows `ports`)
+    pub fn environment_with_ports(&self, host_ports: &[u16]) -> IndexMap<String, String> {
+        self.environment
+            .iter()
+            .map(|(key, value)| {
+                let value = expand_host_ports(value, |container| {
+                    self.ports
+                        .iter()
+                        .zip(host_ports)
+                        .find(|(port, _)| port.container == container)
+                        .map(|(_, host_port)| *host_port)
+                })
+                .unwrap_or_else(|_| value.clone());
+                (key.clone(), value)
+            })
+            .collect()
+    }
+
+    /// Named volumes used by this template (bind mounts are skipped)
+    pub fn named_volumes(&self) -> Vec<&str> {
+        self.volumes
+            .iter()
+            .filter_map(|volume| {
+                let (source, _) = volume.split_once(':')?;
+                let is_path = source.starts_with(['/', '.', '~', '$']);
+                (!is_path).then_some(source)
+            })
+            .collect()
+    }
+
+    /// HTTP routes as (subdomain, container port)
+    pub fn http_routes(&self) -> Vec<(String, u16)> {
+        self.ports
+            .iter()
+            .filter(|port| port.http)
+            .map(|port| {
+                let subdomain = port.subdomain.clone().unwrap_or_else(|| self.name.clone());
+                (subdomain, port.container)
+            })
+            .collect()
+    }
+
+    /// Comma separated default host ports, for display
+    pub fn ports_label(&self) -> String {
+        if self.ports.is_empty() {
+            return "-".to_string();
+        }
+
+        self.ports
+            .iter()
+            .map(|p| p.host.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Replace the `{host_port:<container port>}` placeholders of a value
+fn expand_host_ports(value: &str, host_port: impl Fn(u16) -> Option<u16>) -> Result<String> {
+    let mut expanded = String::new();
+    let mut rest = value;
+    while let Some(start) = rest.find(HOST_PORT_PLACEHOLDER) {
+        expanded.push_str(&rest[..start]);
+        let after = &rest[start + HOST_PORT_PLACEHOLDER.len()..];
+        let end = after
+            .find('}')
+            .with_context(|| format!("'{}' is not closed", HOST_PORT_PLACEHOLDER))?;
+        let port = after[..end]
+            .parse::<u16>()
+            .ok()
+            .and_then(&host_port)
+            .with_context(|| {
+                format!(
+                    "'{}{}}}' does not name a container port of the template",
+                    HOST_PORT_PLACEHOLDER,
+                    &after[..end]
+                )
+            })?;
+        expanded.push_str(&port.to_string());
+        rest = &after[end + 1..];
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+/// Accept strings, numbers and booleans as environment values
+fn deserialize_environment<'de, D>(deserializer: D) -> Result<IndexMap<String, String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let values = IndexMap::<String, toml::Value>::deserialize(deserializer)?;
+
+    values
+        .into_iter()
+        .map(|(key, value)| {
+            let value = match value {
+                toml::Value::String(s) => s,
+                toml::Value::Integer(i) => i.to_string(),
+                toml::Value::Float(f) => f.to_string(),
+                toml::Value::Boolean(b) => b.to_string(),
+                other => {
+                    return Err(serde::de::Error::custom(format!(
+                        "environment variable {} must be a string, number or boolean, got {}",
+                        key,
+                        other.type_str()
+                    )));
+                }
+            };
+            Ok((key, value))
+        })
+        .collect()
+}
+
+/// All templates available to a project
+#[derive(Debug, Clone)]
+pub struct TemplateCatalog {
+    templates: Vec<ServiceTemplate>,
+}
+
+impl TemplateCatalog {
+    /// Catalog with only the built-in templates
+    pub fn builtin() -> Self {
+        let templates = BUILTIN_TEMPLATES
+            .iter()
+            .map(|(name, content)| {
+                ServiceTemplate::parse(content, name, TemplateSource::BuiltIn)
+                    .unwrap_or_else(|e| panic!("invalid built-in template {}: {:#}", name, e))
+            })
+            .collect();
+
+        Self { templates }
+    }
+
+    /// Load built-in, user and project templates
+    ///
+    /// Template files that fail to parse are reported and skipped.
+    pub fn load(project_dir: &Path) -> Result<Self> {
+        let mut catalog = Self::builtin();
+
+        let user_dir = user_templates_dir()?;
+        for template in load_dir(&user_dir, TemplateSource::User)? {
+            catalog.insert(template);
+        }
+
+        let project_templates = project_dir.join(PROJECT_TEMPLATES_DIR);
+        for template in load_dir(&project_templates, TemplateSource::Project)? {
+            catalog.insert(template);
+        }
+
+        Ok(catalog)
+    }
+
+    /// Add a template, replacing one with the same name in place
+    pub fn insert(&mut self, template: ServiceTemplate) {
+        match self.templates.iter_mut().find(|t| t.name == template.name) {
+            Some(existing) => *existing = template,
+            None => self.templates.push(template),
+        }
+    }
+
+    /// Find a template by service name
+    pub fn get(&self, name: &str) -> Option<&ServiceTemplate> {
+        self.templates.iter().find(|t| t.name == name)
+    }
+
+    /// All templates in catalog order
+    pub fn templates(&self) -> &[ServiceTemplate] {
+        &self.templates
+    }
+}
+
+/// Get the user template directory
+pub fn user_templates_dir() -> Result<PathBuf> {
+    Ok(get_config_dir()?.join("templates"))
+}
+
+/// Load all *.toml templates of a directory, sorted by file name
+fn load_dir(dir: &Path, source: fn(PathBuf) -> TemplateSource) -> Result<Vec<ServiceTemplate>> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+        .context(format!("Failed to read {}", dir.display()))?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+        .collect();
+    paths.sort();
+
+    let mut templates = Vec::new();
+    for path in paths {
+        let name = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        let result = fs::read_to_string(&path)
+            .context("Failed to read template")
+            .and_then(|content| ServiceTemplate::parse(&content, &name, source(path.clone())));
+
+        match result {
+            Ok(template) => templates.push(template),
+            Err(e) => println!(
+                "{} Skipping template {}: {:#}",
+                "⚠".yellow(),
+                path.display(),
+                e
+            ),
+        }
+    }
+
+    Ok(templates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_templates_parse() {
+        let catalog = TemplateCatalog::builtin();
+        let names: Vec<&str> = catalog
+            .templates()
+            .iter()
+            .map(|t| t.name.as_str())
+            .collect();
+        assert_eq!(names.len(), BUILTIN_TEMPLATES.len());
+
+        let postgres = catalog.get("postgres").unwrap();
+        assert_eq!(postgres.display_name, "PostgreSQL");
+        assert_eq!(
+            postgres.ports,
+            vec![TemplatePort {
+                host: 5432,
+                container: 5432,
+                http: false,
+                subdomain: None,
+            }]
+        );
+        assert_eq!(postgres.environment["POSTGRES_DB"], "app");
+        assert_eq!(postgres.secrets, vec!["POSTGRES_PASSWORD"]);
+        assert!(postgres.http_routes().is_empty());
+
+        let rabbitmq = catalog.get("rabbitmq").unwrap();
+        assert_eq!(
+            rabbitmq.http_routes(),
+            vec![("rabbitmq".to_string(), 15672)]
+        );
+    }
+
+    #[test]
+    fn test_builtin_templates_are_pinned_with_healthchecks() {
+        for template in TemplateCatalog::builtin().templates() {
+            let tag = template.image.rsplit_once(':').map(|(_, tag)| tag);
+            assert!(
+                tag.is_some_and(|tag| tag != "latest"),
+                "{} is not pinned",
+                template.name
+            );
+            assert!(
+                template.healthcheck.is_some(),
+                "{} has no healthcheck",
+                template.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_user_template() {
+        let content = r#"
+display_name = "Internal API"
+image = "registry.example.com/team/api:1.4"
+ports = [{ host = 8081, container = 8080 }]
+volumes = ["./config:/etc/api", "api_cache:/cache"]
+command = ["serve", "--verbose"]
+
+[environment]
+WORKERS = 4
+DEBUG = false
+
+[healthcheck]
+test = "curl -f http://localhost:8080/health"
+interval = "10s"
+retries = 3
+"#;
+        let path = PathBuf::from("/tmp/internal-api.toml");
+        let template =
+            ServiceTemplate::parse(content, "internal-api", TemplateSource::User(path)).unwrap();
+
+        assert_eq!(template.name, "internal-api");
+        assert_eq!(template.ports[0].host, 8081);
+        assert_eq!(template.ports[0].container, 8080);
+        assert_eq!(template.environment["WORKERS"], "4");
+        assert_eq!(template.environment["DEBUG"], "false");
+        assert_eq!(template.named_volumes(), vec!["api_cache"]);
+        assert_eq!(
+            template.healthcheck.unwrap().test,
+            StringOrList::String("curl -f http://localhost:8080/health".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_templates() {
+        assert!(ServiceTemplate::parse("image = \"\"", "x", TemplateSource::BuiltIn).is_err());
+        assert!(
+            ServiceTemplate::parse("image = \"nginx\"", "bad name", TemplateSource::BuiltIn)
+                .is_err()
+        );
+        assert!(
+            ServiceTemplate::parse("image = \"nginx\"\nimgae = 1", "x", TemplateSource::BuiltIn)
+                .is_err()
+        );
+        assert!(
+            ServiceTemplate::parse(
+                "image = \"nginx\"\nsecrets = [\"TOKEN\"]\n[environment]\nTOKEN = \"x\"",
+                "x",
+                TemplateSource::BuiltIn
+            )
+            .is_err()
+        );
+        for url in [
+            "http://localhost:{host_port:8080}",
+            "http://localhost:{host_port:80",
+        ] {
+            let content = format!(
+                "image = \"nginx\"\nports = [80]\n[environment]\nURL = \"{}\"",
+                url
+            );
+            assert!(ServiceTemplate::parse(&content, "x", TemplateSource::BuiltIn).is_err());
+        }
+    }
+
+    #[test]
+    fn test_environment_with_ports() {
+        let template = ServiceTemplate::parse(
+            "image = \"nginx\"\nports = [80, { host = 8443, container = 443 }]\n[environment]\nURL = \"http://localhost:{host_port:80}\"\nURLS = \"{host_port:80},{host_port:443}\"\nMODE = \"dev\"\n",
+            "web",
+            TemplateSource::BuiltIn,
+        )
+        .unwrap();
+
+        let environment = template.environment_with_ports(&[8081, 9443]);
+        assert_eq!(environment["URL"], "http://localhost:8081");
+        assert_eq!(environment["URLS"], "8081,9443");
+        assert_eq!(environment["MODE"], "dev");
+    }
+
+    #[test]
+    fn test_catalog_merges_user_templates() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("redis.toml"),
+            "display_name = \"Valkey\"\nimage = \"valkey/valkey:8\"\nports = [6379]\n",
+        )
+        .unwrap();
+        fs::write(dir.path().join("mailer.toml"), "image = \"mailer:1\"\n").unwrap();
+        fs::write(dir.path().join("broken.toml"), "ports = [\n").unwrap();
+
+        let mut catalog = TemplateCatalog::builtin();
+        for template in load_dir(dir.path(), TemplateSource::User).unwrap() {
+            catalog.insert(template);
+        }
+
+        let names: Vec<&str> = catalog
+            .templates()
+            .iter()
+            .map(|t| t.name.as_str())
+            .collect();
+        assert_eq!(names.last(), Some(&"mailer"));
+        assert_eq!(names.len(), BUILTIN_TEMPLATES.len() + 1);
+
+        let redis = catalog.get("redis").unwrap();
+        assert_eq!(redis.image, "valkey/valkey:8");
+        assert!(matches!(redis.source, TemplateSource::User(_)));
+    }
+}
@@ -0,0 +1,59 @@
+//! Traefik implementation of the proxy backend
+//!
+//! This module adapts the Traefik container management and file provider
+//! rendering to the `ProxyBackend` trait.
+
+use std::path::PathBuf;
+
+use anyhow::Result;
+
+use super::{TRAEFIK_CONTAINER_NAME, TRAEFIK_DYNAMIC_DIR, config, manager};
+use crate::{
+    config::get_config_dir,
+    proxy::{ProxyBackend, SiteConfig},
+};
+
+/// Traefik reverse proxy backend (file provider)
+pub struct TraefikBackend;
+
+impl ProxyBackend for TraefikBackend {
+    fn name(&self) -> &'static str {
+        "Traefik"
+    }
+
+    fn container_name(&self) -> &str {
+        TRAEFIK_CONTAINER_NAME
+    }
+
+    fn routes_dir(&self) -> Result<PathBuf> {
+        Ok(get_config_dir()?.join(TRAEFIK_DYNAMIC_DIR))
+    }
+
+    fn routes_file_name(&self, project_name: &str) -> String {
+        format!("{}.yml", project_name)
+    }
+
+    fn render_routes(&self, site: &SiteConfig) -> String {
+        config::render_site(site)
+    }
+
+    fn start(&self) -> Result<()> {
+        manager::start()
+    }
+
+    fn stop(&self) -> Result<()> {
+        manager::stop()
+    }
+
+    fn reload(&self) -> Result<()> {
+        manager::reload()
+    }
+
+    fn status(&self) -> Result<()> {
+        manager::status()
+    }
+
+    fn is_running(&self) -> bool {
+        manager::is_running()
+    }
+}
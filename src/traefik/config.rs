@@ -0,0 +1,180 @@
+//! Traefik configuration rendering for projects
+//!
+//! This module renders per-project file provider configurations (YAML)
+//! from the backend-independent route model.
+
+use crate::proxy::{Route, SiteConfig, TlsMode};
+
+/// Entry point for plain HTTP traffic
+pub const WEB_ENTRYPOINT: &str = "web";
+
+/// Entry point for HTTPS traffic
+pub const WEBSECURE_ENTRYPOINT: &str = "websecure";
+
+/// Build a router/service name that is unique across all projects
+fn router_name(route: &Route) -> String {
+    route.domain.replace('.', "-")
+}
+
+/// Render the file provider configuration for a project
+pub fn render_site(site: &SiteConfig) -> String {
+    let mut traefik_config = format!(
+        "# Auto-generated Traefik configuration for {}\n# Domain: {}\n",
+        site.name, site.domain
+    );
+
+    if site.routes.is_empty() {
+        return traefik_config;
+    }
+
+    let (entrypoint, router_tls) = match site.tls {
+        TlsMode::Disabled => (WEB_ENTRYPOINT, ""),
+        TlsMode::Certificate(_) | TlsMode::Internal => (WEBSECURE_ENTRYPOINT, "      tls: {}\n"),
+    };
+
+    traefik_config.push_str("\nhttp:\n  routers:\n");
+    for route in &site.routes {
+        let name = router_name(route);
+        traefik_config.push_str(&format!(
+            "    {}:\n      rule: \"Host(`{}`)\"\n      entryPoints:\n        - {}\n      service: {}\n{}",
+            name, route.domain, entrypoint, name, router_tls
+        ));
+    }
+
+    traefik_config.push_str("  services:\n");
+    for route in &site.routes {
+        traefik_config.push_str(&format!(
+            "    {}:\n      loadBalancer:\n        servers:\n          - url: \"http://{}\"\n",
+            router_name(route),
+            route.target
+        ));
+    }
+
+    // Without a certificate Traefik falls back to its default self-signed one
+    if let TlsMode::Certificate(cert_name) = &site.tls {
+        traefik_config.push_str(&format!(
+            "\ntls:\n  certificates:\n    - certFile: /certs/{}.crt\n      keyFile: /certs/{}.key\n",
+            cert_name, cert_name
+        ));
+    }
+
+    traefik_config
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn site(tls: TlsMode) -> SiteConfig {
+        SiteConfig {
+            name: "shop".to_string(),
+            domain: "shop.local".to_string(),
+            routes: vec![
+                Route {
+                    domain: "api.shop.local".to_string(),
+                    target: "shop-api:8080".to_string(),
+                },
+                Route {
+                    domain: "web.shop.local".to_string(),
+                    target: "shop-web-1:3000".to_string(),
+                },
+            ],
+            tls,
+            port: None,
+        }
+    }
+
+    #[test]
+    fn test_render_site_with_certificate() {
+        let expected = "\
+# Auto-generated Traefik configuration for shop
+# Domain: shop.local
+
+http:
+  routers:
+    api-shop-local:
+      rule: \"Host(`api.shop.local`)\"
+      entryPoints:
+        - websecure
+      service: api-shop-local
+      tls: {}
+    web-shop-local:
+      rule: \"Host(`web.shop.local`)\"
+      entryPoints:
+        - websecure
+      service: web-shop-local
+      tls: {}
+  services:
+    api-shop-local:
+      loadBalancer:
+        servers:
+          - url: \"http://shop-api:8080\"
+    web-shop-local:
+      loadBalancer:
+        servers:
+          - url: \"http://shop-web-1:3000\"
+
+tls:
+  certificates:
+    - certFile: /certs/shop_local.crt
+      keyFile: /certs/shop_local.key
+";
+        assert_eq!(
+            render_site(&site(TlsMode::Certificate("shop_local".to_string()))),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_render_site_without_tls() {
+        let expected = "\
+# Auto-generated Traefik configuration for shop
+# Domain: shop.local
+
+http:
+  routers:
+    api-shop-local:
+      rule: \"Host(`api.shop.local`)\"
+      entryPoints:
+        - web
+      service: api-shop-local
+    web-shop-local:
+      rule: \"Host(`web.shop.local`)\"
+      entryPoints:
+        - web
+      service: web-shop-local
+  services:
+    api-shop-local:
+      loadBalancer:
+        servers:
+          - url: \"http://shop-api:8080\"
+    web-shop-local:
+      loadBalancer:
+        servers:
+          - url: \"http://shop-web-1:3000\"
+";
+        assert_eq!(render_site(&site(TlsMode::Disabled)), expected);
+    }
+
+    #[test]
+    fn test_render_site_is_valid_yaml() {
+        let rendered = render_site(&site(TlsMode::Internal));
+        let yaml: serde_yaml::Value = serde_yaml::from_str(&rendered).unwrap();
+
+        let router = &yaml["http"]["routers"]["api-shop-local"];
+        assert_eq!(router["rule"].as_str(), Some("Host(`api.shop.local`)"));
+        assert!(router["tls"].is_mapping());
+        assert!(yaml.get("tls").is_none());
+    }
+
+    #[test]
+    fn test_render_site_without_routes() {
+        let mut empty = site(TlsMode::Disabled);
+        empty.routes.clear();
+
+        assert_eq!(
+            render_site(&empty),
+            "# Auto-generated Traefik configuration for shop\n# Domain: shop.local\n"
+        );
+    }
+}
@@ -0,0 +1,475 @@
+//! Traefik container lifecycle management
+//!
+//! This module handles starting, stopping, and monitoring the Traefik container.
+
+use std::{path::Path, process::Command};
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+
+use super::{
+    TRAEFIK_CONTAINER_NAME, TRAEFIK_DYNAMIC_DIR,
+    config::{WEB_ENTRYPOINT, WEBSECURE_ENTRYPOINT},
+};
+use crate::{
+    caddy::OMD_SERVICE_LABEL,
+    config::{GlobalConfig, TraefikSettings, get_config_dir, load_global_config},
+    docker::network::ensure_network,
+};
+
+/// Check if Traefik container is running
+pub fn is_running() -> bool {
+    let output = Command::new("docker")
+        .args([
+            "ps",
+            "--filter",
+            &format!("name={}", TRAEFIK_CONTAINER_NAME),
+            "--format",
+            "{{.Names}}",
+        ])
+        .output();
+
+    if let Ok(output) = output {
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        stdout.contains(TRAEFIK_CONTAINER_NAME)
+    } else {
+        false
+    }
+}
+
+/// Check if Traefik container exists (running or stopped)
+fn container_exists() -> bool {
+    let output = Command::new("docker")
+        .args([
+            "ps",
+            "-a",
+            "--filter",
+            &format!("name={}", TRAEFIK_CONTAINER_NAME),
+            "--format",
+            "{{.Names}}",
+        ])
+        .output();
+
+    if let Ok(output) = output {
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        stdout.contains(TRAEFIK_CONTAINER_NAME)
+    } else {
+        false
+    }
+}
+
+/// Label holding a fingerprint of the settings the container was created with
+const SETTINGS_LABEL: &str = "oh-my-dockers.traefik-settings";
+
+/// Preferred port of the `traefik` entrypoint that serves the dashboard and API
+const DASHBOARD_PORT: u16 = 8080;
+
+/// Container port of the dashboard, next to the HTTP and HTTPS entrypoints
+fn dashboard_port(settings: &TraefikSettings) -> u16 {
+    let taken = [settings.http_port(), settings.https_port()];
+    (DASHBOARD_PORT..)
+        .find(|port| !taken.contains(port))
+        .unwrap_or(DASHBOARD_PORT)
+}
+
+/// Build the static configuration passed to Traefik as command line flags
+fn static_config_args(global_config: &GlobalConfig) -> Vec<String> {
+    let mut args = vec![
+        "--providers.file.directory=/etc/traefik/dynamic".to_string(),
+        "--providers.file.watch=true".to_string(),
+    ];
+    // Traefik listens on the published ports themselves, so HTTPS redirects
+    // point to the port the browser can reach
+    let settings = &global_config.traefik;
+    args.push(format!(
+        "--entrypoints.{}.address=:{}",
+        WEB_ENTRYPOINT,
+        settings.http_port()
+    ));
+    args.push(format!(
+        "--entrypoints.{}.address=:{}",
+        WEBSECURE_ENTRYPOINT,
+        settings.https_port()
+    ));
+
+    // The insecure API has no authentication, so it is only enabled when it
+    // is published on the host address chosen in [traefik] dashboard
+    if !settings.dashboard.is_empty() {
+        args.push("--api.dashboard=true".to_string());
+        args.push("--api.insecure=true".to_string());
+        args.push(format!(
+            "--entrypoints.traefik.address=:{}",
+            dashboard_port(settings)
+        ));
+    }
+
+    if global_config.global.enable_https {
+        args.push(format!(
+            "--entrypoints.{}.http.redirections.entrypoint.to={}",
+            WEB_ENTRYPOINT, WEBSECURE_ENTRYPOINT
+        ));
+        args.push(format!(
+            "--entrypoints.{}.http.redirections.entrypoint.scheme=https",
+            WEB_ENTRYPOINT
+        ));
+    }
+
+    args
+}
+
+/// Arguments of `docker run` for the Traefik container
+/// The container is labeled with a fingerprint of the arguments, which is
+/// returned as well
+fn run_args(global_config: &GlobalConfig, config_dir: &Path) -> (Vec<String>, String) {
+    let dynamic_path = config_dir.join(TRAEFIK_DYNAMIC_DIR);
+    let certs_path = config_dir.join(&global_config.global.caddy_certs_dir);
+
+    let mut options: Vec<String> = vec![
+        "-d".to_string(),
+        "--name".to_string(),
+        TRAEFIK_CONTAINER_NAME.to_string(),
+        "--restart".to_string(),
+        "unless-stopped".to_string(),
+    ];
+    let settings = &global_config.traefik;
+    for port in [settings.http_port(), settings.https_port()] {
+        options.push("-p".to_string());
+        options.push(format!("{}:{}", port, port));
+    }
+    if !settings.dashboard.is_empty() {
+        options.push("-p".to_string());
+        options.push(format!(
+            "{}:{}",
+            settings.dashboard,
+            dashboard_port(settings)
+        ));
+    }
+    options.extend([
+        "-v".to_string(),
+        format!("{}:/etc/traefik/dynamic:ro", dynamic_path.display()),
+        "-v".to_string(),
+        format!("{}:/certs:ro", certs_path.display()),
+        "--network".to_string(),
+        global_config.traefik_network().to_string(),
+        "--label".to_string(),
+        format!("{}=traefik", OMD_SERVICE_LABEL),
+    ]);
+    let image = settings.image.clone();
+    let command = static_config_args(global_config);
+
+    let fingerprint = fingerprint(options.iter().chain([&image]).chain(&command));
+
+    let mut args = vec!["run".to_string()];
+    args.extend(options);
+    args.push("--label".to_string());
+    args.push(format!("{}={}", SETTINGS_LABEL, fingerprint));
+    args.push(image);
+    args.extend(command);
+
+    (args, fingerprint)
+}
+
+/// 64-bit FNV-1a hash of the NUL-separated arguments
+/// The hash must not change between omd builds, otherwise every upgrade
+/// would report the running container as outdated.
+fn fingerprint<'a>(args: impl IntoIterator<Item = &'a String>) -> String {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    let mut hash = OFFSET_BASIS;
+    for (i, arg) in args.into_iter().enumerate() {
+        let separator: &[u8] = if i == 0 { b"" } else { b"\0" };
+        for byte in separator.iter().chain(arg.as_bytes()) {
+            hash ^= u64::from(*byte);
+            hash = hash.wrapping_mul(PRIME);
+        }
+    }
+    format!("{:016x}", hash)
+}
+
+/// Settings fingerprint of the existing container ("" for containers created
+/// before it was recorded)
+fn container_fingerprint() -> String {
+    Command::new("docker")
+        .args([
+            "inspect",
+            "--format",
+            &format!("{{{{ index .Config.Labels \"{}\" }}}}", SETTINGS_LABEL),
+            TRAEFIK_CONTAINER_NAME,
+        ])
+        .output()
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .unwrap_or_default()
+}
+
+/// Dashboard URL on the host, if it is published
+fn dashboard_url(global_config: &GlobalConfig) -> Option<String> {
+    let dashboard = &global_config.traefik.dashboard;
+    if dashboard.is_empty() {
+        return None;
+    }
+    // Wildcard bindings are reachable on localhost
+    let address = dashboard
+        .strip_prefix("0.0.0.0:")
+        .map(|port| format!("localhost:{}", port))
+        .unwrap_or_else(|| dashboard.clone());
+    Some(format!("http://{}/dashboard/", address))
+}
+
+/// Print where to find the dashboard and logs of a running container
+fn print_access(global_config: &GlobalConfig) {
+    if let Some(url) = dashboard_url(global_config) {
+        println!("Traefik Dashboard: {}", url);
+    }
+    println!("View logs: docker logs {} -f", TRAEFIK_CONTAINER_NAME);
+}
+
+/// Start Traefik container
+/// A container created with other settings is recreated; Traefik keeps no
+/// state besides the mounted configuration
+pub fn start() -> Result<()> {
+    let global_config = load_global_config()?;
+    let config_dir = get_config_dir()?;
+    let (args, fingerprint) = run_args(&global_config, &config_dir);
+
+    if is_running() {
+        println!("{} Traefik is already running", "ℹ".blue());
+        if container_fingerprint() != fingerprint {
+            println!(
+                "{} It was started with other settings. Run {} to apply the current ones.",
+                "⚠".yellow(),
+                "omd proxy restart".bright_white()
+            );
+        }
+        return Ok(());
+    }
+
+    if container_exists() && container_fingerprint() == fingerprint {
+        println!("{} Starting existing Traefik container...", "ℹ".blue());
+        let status = Command::new("docker")
+            .args(["start", TRAEFIK_CONTAINER_NAME])
+            .status()
+            .context("Failed to start container")?;
+
+        if !status.success() {
+            anyhow::bail!("Failed to start existing container");
+        }
+    } else {
+        if container_exists() {
+            println!(
+                "{} Recreating the Traefik container to apply the current settings...",
+                "ℹ".blue()
+            );
+            let status = Command::new("docker")
+                .args(["rm", "-f", TRAEFIK_CONTAINER_NAME])
+                .status()
+                .context("Failed to remove container")?;
+
+            if !status.success() {
+                anyhow::bail!("Failed to remove existing container");
+            }
+        } else {
+            println!("{}", "Starting Traefik reverse proxy...".blue());
+        }
+
+        ensure_network(global_config.traefik_network())?;
+        std::fs::create_dir_all(config_dir.join(TRAEFIK_DYNAMIC_DIR))
+            .context("Failed to create Traefik dynamic configuration directory")?;
+
+        println!("{} Starting Traefik container...", "ℹ".blue());
+
+        let status = Command::new("docker")
+            .args(&args)
+            .status()
+            .context("Failed to start Traefik")?;
+
+        if !status.success() {
+            anyhow::bail!("Failed to start Traefik container");
+        }
+    }
+
+    // Wait a bit for Traefik to start
+    std::thread::sleep(std::time::Duration::from_secs(2));
+
+    if is_running() {
+        println!("{}", "✓ Traefik started successfully".green());
+        println!();
+        print_access(&global_config);
+    } else {
+        println!("{}", "⚠ Traefik may have failed to start".yellow());
+        println!("Check logs: docker logs {}", TRAEFIK_CONTAINER_NAME);
+    }
+
+    Ok(())
+}
+
+/// Stop Traefik container
+pub fn stop() -> Result<()> {
+    if !is_running() {
+        println!("{} Traefik is not running", "ℹ".blue());
+        return Ok(());
+    }
+
+    println!("{}", "Stopping Traefik...".blue());
+
+    let status = Command::new("docker")
+        .args(["stop", TRAEFIK_CONTAINER_NAME])
+        .status()
+        .context("Failed to stop Traefik")?;
+
+    if !status.success() {
+        anyhow::bail!("Failed to stop Traefik");
+    }
+
+    println!("{}", "✓ Traefik stopped".green());
+
+    Ok(())
+}
+
+/// Reload Traefik configuration
+///
+/// The file provider watches the dynamic configuration directory, so there is
+/// nothing to trigger; this only reports whether Traefik will pick up changes.
+pub fn reload() -> Result<()> {
+    if !is_running() {
+        println!("{} Traefik is not running, skipping reload", "⚠".yellow());
+        return Ok(());
+    }
+
+    println!(
+        "{}",
+        "✓ Traefik watches its configuration directory, changes are applied automatically".green()
+    );
+
+    Ok(())
+}
+
+/// Show Traefik status
+pub fn status() -> Result<()> {
+    println!("{}", "Traefik Status:".blue());
+    println!();
+
+    if is_running() {
+        println!("  Status: {}", "Running".green());
+
+        let output = Command::new("docker")
+            .args([
+                "ps",
+                "--filter",
+                &format!("name={}", TRAEFIK_CONTAINER_NAME),
+                "--format",
+                "table {{.Status}}\t{{.Ports}}",
+            ])
+            .output()
+            .context("Failed to get container status")?;
+
+        let info = String::from_utf8_lossy(&output.stdout);
+        for line in info.lines().skip(1) {
+            println!("  {}", line);
+        }
+
+        println!();
+        print_access(&load_global_config()?);
+    } else {
+        println!("  Status: {}", "Not running".red());
+        println!();
+        println!("Start Traefik with: {}", "omd proxy start".bright_white());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn global_config(extra: &str) -> GlobalConfig {
+        toml::from_str(&format!(
+            "[global]\ncaddy_network = \"caddy-net\"\ncaddy_projects_dir = \"caddy/projects\"\ncaddy_certs_dir = \"caddy/certs\"\n{}",
+            extra
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_static_config_args_redirects_only_with_https() {
+        let http_only = static_config_args(&global_config(""));
+        assert!(!http_only.iter().any(|a| a.contains("redirections")));
+
+        let https = static_config_args(&global_config("enable_https = true\n"));
+        assert!(
+            https.contains(
+                &"--entrypoints.web.http.redirections.entrypoint.to=websecure".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_fingerprint_is_stable() {
+        let args = |a: &[&str]| a.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        // Reference values of 64-bit FNV-1a
+        assert_eq!(fingerprint(&args(&[])), "cbf29ce484222325");
+        assert_eq!(fingerprint(&args(&["a"])), "af63dc4c8601ec8c");
+        assert_eq!(fingerprint(&args(&["foobar"])), "85944171f73967e8");
+
+        // Arguments are separated, so moving text between them changes it
+        assert_ne!(
+            fingerprint(&args(&["-p", "80:80"])),
+            fingerprint(&args(&["-p80", ":80"]))
+        );
+    }
+
+    #[test]
+    fn test_dashboard_is_only_published_on_request() {
+        let config_dir = Path::new("/home/user/.oh-my-dockers");
+
+        let global_config = global_config("");
+        let (args, fingerprint) = run_args(&global_config, config_dir);
+        assert!(!args.iter().any(|a| a.starts_with("--api")));
+        assert!(!args.iter().any(|a| a.contains("8080")));
+        assert_eq!(dashboard_url(&global_config), None);
+
+        let dashboard = self::global_config("[traefik]\ndashboard = \"127.0.0.1:8090\"\n");
+        let (args, other) = run_args(&dashboard, config_dir);
+        assert!(args.contains(&"--api.insecure=true".to_string()));
+        assert!(
+            args.windows(2)
+                .any(|a| a[0] == "-p" && a[1] == "127.0.0.1:8090:8080")
+        );
+        assert_eq!(
+            dashboard_url(&dashboard).as_deref(),
+            Some("http://127.0.0.1:8090/dashboard/")
+        );
+
+        // Changed settings lead to a new container
+        assert_ne!(fingerprint, other);
+        assert!(args.contains(&format!("{}={}", SETTINGS_LABEL, other)));
+    }
+
+    #[test]
+    fn test_run_args_use_traefik_settings() {
+        let global_config = global_config(
+            "enable_https = true\n\n[caddy]\nnetwork = \"caddy-only\"\n\n[traefik]\nimage = \"traefik:v3.3\"\nnetwork = \"edge-net\"\nhigh_ports = true\ndashboard = \"127.0.0.1:8090\"\n",
+        );
+        let (args, _) = run_args(&global_config, Path::new("/home/user/.oh-my-dockers"));
+
+        let published: Vec<&str> = args
+            .windows(2)
+            .filter(|a| a[0] == "-p")
+            .map(|a| a[1].as_str())
+            .collect();
+        assert_eq!(
+            published,
+            vec!["8080:8080", "8443:8443", "127.0.0.1:8090:8081"]
+        );
+        assert!(
+            args.windows(2)
+                .any(|a| a[0] == "--network" && a[1] == "edge-net")
+        );
+        assert!(args.contains(&"traefik:v3.3".to_string()));
+        assert!(args.contains(&"--entrypoints.websecure.address=:8443".to_string()));
+        // The dashboard moves out of the way of the HTTP entrypoint
+        assert!(args.contains(&"--entrypoints.traefik.address=:8081".to_string()));
+    }
+}
@@ -0,0 +1,16 @@
+//! Traefik reverse proxy management module
+//!
+//! This module contains the Traefik alternative to the Caddy backend:
+//! - Container lifecycle management (start, stop, status)
+//! - File provider configuration generation for projects
+//! - `ProxyBackend` implementation
+
+pub mod backend;
+pub mod config;
+pub mod manager;
+
+/// The name of the Traefik container managed by oh-my-dockers
+pub const TRAEFIK_CONTAINER_NAME: &str = "oh-my-dockers-traefik";
+
+/// Directory for file provider configurations (relative to config directory)
+pub const TRAEFIK_DYNAMIC_DIR: &str = "traefik/dynamic";
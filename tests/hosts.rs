@@ -0,0 +1,172 @@
+//! End-to-end tests of `omd hosts` on a hosts file in a temporary directory
+//!
+//! OH_MY_DOCKERS_HOSTS_FILE points omd at the file and OH_MY_DOCKERS_DIR at a
+//! configuration directory holding the registry and the backups.
+
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+/// Registry with one project, two domains and an alias
+const REGISTRY: &str = r#"{
+  "projects": {
+    "shop": {
+      "name": "shop",
+      "path": "/srv/shop",
+      "domain": "shop.local",
+      "network": "shop-net",
+      "ports": [],
+      "containers": [],
+      "domains": ["shop.local", "api.shop.local"],
+      "aliases": ["shop.test"]
+    }
+  }
+}"#;
+
+const ORIGINAL: &str = "127.0.0.1 localhost\n::1 localhost\n";
+
+struct Sandbox {
+    _dir: tempfile::TempDir,
+    config_dir: PathBuf,
+    hosts: PathBuf,
+}
+
+impl Sandbox {
+    fn new() -> Self {
+        let dir = tempfile::tempdir().unwrap();
+        let config_dir = dir.path().join("config");
+        let hosts = dir.path().join("hosts");
+        fs::create_dir_all(&config_dir).unwrap();
+        fs::write(config_dir.join("registry.json"), REGISTRY).unwrap();
+        fs::write(&hosts, ORIGINAL).unwrap();
+
+        Self {
+            _dir: dir,
+            config_dir,
+            hosts,
+        }
+    }
+
+    /// Run omd with `input` on stdin and return its output
+    fn omd(&self, args: &[&str], input: &str) -> String {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_omd"))
+            .args(args)
+            .env("OH_MY_DOCKERS_DIR", &self.config_dir)
+            .env("OH_MY_DOCKERS_HOSTS_FILE", &self.hosts)
+            .env("NO_COLOR", "1")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(input.as_bytes())
+            .unwrap();
+
+        let output = child.wait_with_output().unwrap();
+        assert!(
+            output.status.success(),
+            "omd {:?} failed: {}",
+            args,
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    }
+
+    fn hosts_content(&self) -> String {
+        fs::read_to_string(&self.hosts).unwrap()
+    }
+
+    fn backups_dir(&self) -> PathBuf {
+        self.config_dir.join("backups").join("hosts")
+    }
+}
+
+fn backup_count(dir: &Path) -> usize {
+    fs::read_dir(dir)
+        .map(|entries| entries.count())
+        .unwrap_or(0)
+}
+
+#[test]
+fn test_sync_and_restore() {
+    let sandbox = Sandbox::new();
+
+    // The diff lists every domain of the missing section
+    let diff = sandbox.omd(&["hosts", "diff"], "");
+    for domain in ["shop.local", "api.shop.local", "shop.test"] {
+        assert!(diff.contains(domain), "{}", diff);
+    }
+    assert_eq!(sandbox.hosts_content(), ORIGINAL);
+
+    // Sync writes the project section and backs up the original file
+    sandbox.omd(&["hosts", "sync"], "y\n");
+    let synced = sandbox.hosts_content();
+    assert!(synced.starts_with(ORIGINAL));
+    for line in [
+        "127.0.0.1 shop.local",
+        "::1 shop.local",
+        "127.0.0.1 api.shop.local",
+        "::1 api.shop.local",
+        "127.0.0.1 shop.test",
+        "::1 shop.test",
+    ] {
+        assert!(synced.contains(&format!("{}\n", line)), "{}", synced);
+    }
+    assert_eq!(backup_count(&sandbox.backups_dir()), 1);
+
+    let output = sandbox.omd(&["hosts", "sync"], "");
+    assert!(
+        output.contains("is in sync with the registry"),
+        "{}",
+        output
+    );
+
+    // Restoring the newest backup brings back the original file, and the
+    // synced file is backed up first
+    sandbox.omd(&["hosts", "restore"], "y\n");
+    assert_eq!(sandbox.hosts_content(), ORIGINAL);
+    assert_eq!(backup_count(&sandbox.backups_dir()), 2);
+
+    // The restore can be undone with the backup it created
+    sandbox.omd(&["hosts", "restore"], "y\n");
+    assert_eq!(sandbox.hosts_content(), synced);
+
+    // Answering no leaves the file alone
+    sandbox.omd(&["hosts", "cleanup"], "n\n");
+    assert_eq!(sandbox.hosts_content(), synced);
+}
+
+#[test]
+fn test_restore_keeps_the_selected_backup() {
+    let sandbox = Sandbox::new();
+    let backups_dir = sandbox.backups_dir();
+    fs::create_dir_all(&backups_dir).unwrap();
+
+    // A full set of backups; the oldest one is restored
+    let oldest = backups_dir.join("hosts_20200101_000000.bak");
+    fs::write(&oldest, "# oldest\n").unwrap();
+    for i in 1..10 {
+        fs::write(
+            backups_dir.join(format!("hosts_20200101_0000{:02}.bak", i)),
+            format!("# backup {}\n", i),
+        )
+        .unwrap();
+    }
+
+    sandbox.omd(&["hosts", "restore", "20200101_000000"], "y\n");
+    assert_eq!(sandbox.hosts_content(), "# oldest\n");
+    assert!(oldest.exists());
+    assert_eq!(backup_count(&backups_dir), 11);
+
+    // The next sync prunes back to ten backups
+    sandbox.omd(&["hosts", "sync"], "y\n");
+    assert_eq!(backup_count(&backups_dir), 10);
+    assert!(!oldest.exists());
+}